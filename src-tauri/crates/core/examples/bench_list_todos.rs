@@ -0,0 +1,204 @@
+//! 列表查询与并发写入的基准：在临时目录建一个 10 万条任务的库，分别在当前结构（只有 sort_order 索引）、
+//! 加上三种排序的表达式索引（迁移 v4 曾建立、v10 移除）与没有任何排序索引时，计时三种排序的 list_todos
+//! 并打印查询计划，再模拟手机与桌面同时写入，确认不会出现 database is locked。
+//!
+//!     cargo run --release -p xy-todo-core --example bench_list_todos [任务条数]
+
+use std::time::{Duration, Instant};
+
+use xy_todo_core::store::{self, begin_write};
+use xy_todo_core::validation::NewTodo;
+
+const DEFAULT_TODOS: usize = 100_000;
+const SORTS: [&str; 3] = ["comprehensive", "importance", "deadline"];
+/// 每种排序的计时次数，取中位数
+const RUNS: usize = 5;
+/// 并发写入的任务数与每个任务的写入次数
+const WRITERS: usize = 8;
+const WRITES_PER_WRITER: usize = 50;
+/// 迁移 v4 曾为三种排序建立的表达式索引，v10 因读取反而变慢而移除，这里重建以便对比
+const EXPRESSION_INDEXES: [(&str, &str); 3] = [
+    (
+        "idx_todos_sort_comprehensive",
+        "(CASE WHEN end_time IS NULL THEN 1 ELSE 0 END), end_time, sort_order, \
+         (CASE importance WHEN 'urgent' THEN 0 WHEN 'important' THEN 1 ELSE 2 END), id",
+    ),
+    (
+        "idx_todos_sort_deadline",
+        "(CASE WHEN end_time IS NULL THEN 1 ELSE 0 END), end_time, sort_order, id",
+    ),
+    (
+        "idx_todos_sort_importance",
+        "(CASE importance WHEN 'urgent' THEN 0 WHEN 'important' THEN 1 ELSE 2 END), sort_order, id",
+    ),
+];
+/// 当前保留的排序索引：新增任务时取 MAX(sort_order)
+const SORT_ORDER_INDEX: &str = "idx_todos_sort_order";
+
+#[tokio::main]
+async fn main() -> Result<(), String> {
+    let count = match std::env::args().nth(1) {
+        Some(raw) => raw
+            .parse()
+            .map_err(|_| format!("无效的任务条数：{}", raw))?,
+        None => DEFAULT_TODOS,
+    };
+    let dir = std::env::temp_dir().join(format!("douko-bench-{}", std::process::id()));
+    let result = run(&dir, count).await;
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+async fn run(dir: &std::path::Path, count: usize) -> Result<(), String> {
+    let pool = store::open_database(&dir.join("todos.db")).await?;
+    let started = Instant::now();
+    seed(&pool, count).await.map_err(|e| e.to_string())?;
+    println!("写入 {} 条任务：{:?}", count, started.elapsed());
+
+    println!("\n当前结构（只有 {}）：", SORT_ORDER_INDEX);
+    list_all(&pool).await.map_err(|e| e.to_string())?;
+    concurrent_writes(&pool).await;
+
+    for (name, columns) in EXPRESSION_INDEXES {
+        execute(
+            &pool,
+            &format!("CREATE INDEX {} ON todos ({})", name, columns),
+        )
+        .await?;
+    }
+    println!("\n加上三种排序的表达式索引：");
+    list_all(&pool).await.map_err(|e| e.to_string())?;
+    concurrent_writes(&pool).await;
+
+    for (name, _) in EXPRESSION_INDEXES {
+        execute(&pool, &format!("DROP INDEX {}", name)).await?;
+    }
+    execute(&pool, &format!("DROP INDEX {}", SORT_ORDER_INDEX)).await?;
+    println!("\n无排序索引：");
+    list_all(&pool).await.map_err(|e| e.to_string())?;
+    concurrent_writes(&pool).await;
+
+    pool.close().await;
+    Ok(())
+}
+
+async fn execute(pool: &sqlx::SqlitePool, sql: &str) -> Result<(), String> {
+    sqlx::query(sql)
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// 一个事务内批量插入；截止时间、重要程度与排序值打散，约三成任务没有截止时间
+async fn seed(pool: &sqlx::SqlitePool, count: usize) -> Result<(), sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for i in 0..count {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let end_time = (seed % 10 >= 3).then(|| {
+            format!(
+                "2026-{:02}-{:02}T{:02}:00:00",
+                seed % 12 + 1,
+                (seed >> 8) % 28 + 1,
+                (seed >> 16) % 24
+            )
+        });
+        let importance = ["urgent", "important", "normal"][(seed >> 24) as usize % 3];
+        sqlx::query(
+            "INSERT INTO todos (id, content, status, end_time, importance, sort_order, updated_at) VALUES (?, ?, 'pending', ?, ?, ?, 0)",
+        )
+        .bind(format!("bench-{:06}", i))
+        .bind(format!("任务 {}", i))
+        .bind(end_time)
+        .bind(importance)
+        .bind((seed >> 32) as i64 % count as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+async fn list_all(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    for sort in SORTS {
+        let mut times = Vec::with_capacity(RUNS);
+        for _ in 0..RUNS {
+            let started = Instant::now();
+            store::query_todos(pool, sort).await?;
+            times.push(started.elapsed());
+        }
+        times.sort();
+        println!("  list_todos({}): {:?}", sort, times[RUNS / 2]);
+        for detail in query_plan(pool, sort).await? {
+            println!("    {}", detail);
+        }
+    }
+    Ok(())
+}
+
+/// EXPLAIN QUERY PLAN 的 detail 列；使用索引时为 SCAN todos USING INDEX …，否则出现 USE TEMP B-TREE FOR ORDER BY
+async fn query_plan(pool: &sqlx::SqlitePool, sort: &str) -> Result<Vec<String>, sqlx::Error> {
+    let rows: Vec<(i64, i64, i64, String)> = sqlx::query_as(&format!(
+        "EXPLAIN QUERY PLAN {}",
+        store::list_todos_sql(sort)
+    ))
+    // 不缓存预处理语句：删除索引后缓存的查询计划不会更新
+    .persistent(false)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(_, _, _, detail)| detail).collect())
+}
+
+/// 多个任务同时新增任务（手机与桌面同时写入的情形），统计失败次数与最慢一次写入
+async fn concurrent_writes(pool: &sqlx::SqlitePool) {
+    let started = Instant::now();
+    let tasks: Vec<_> = (0..WRITERS)
+        .map(|w| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut failed = 0;
+                let mut slowest = Duration::ZERO;
+                for i in 0..WRITES_PER_WRITER {
+                    let new = NewTodo {
+                        content: format!("并发写入 {}-{}", w, i),
+                        status: "pending",
+                        start_time: None,
+                        end_time: None,
+                        importance: "normal",
+                        group_id: None,
+                    };
+                    let id = format!("writer-{}-{}-{}", std::process::id(), w, i);
+                    let one = Instant::now();
+                    if let Err(e) = store::insert_todo(&pool, &id, &new).await {
+                        eprintln!("insert_todo: {}", e);
+                        failed += 1;
+                    }
+                    slowest = slowest.max(one.elapsed());
+                }
+                (failed, slowest)
+            })
+        })
+        .collect();
+    let mut failed = 0;
+    let mut slowest = Duration::ZERO;
+    for task in tasks {
+        if let Ok((f, s)) = task.await {
+            failed += f;
+            slowest = slowest.max(s);
+        }
+    }
+    // 清掉本轮写入，下一轮对比时数据量一致
+    let _ = sqlx::query("DELETE FROM todos WHERE id LIKE 'writer-%'")
+        .execute(pool)
+        .await;
+    println!(
+        "  并发新增 {}×{}：{:?}，失败 {} 次，最慢一次 {:?}",
+        WRITERS,
+        WRITES_PER_WRITER,
+        started.elapsed(),
+        failed,
+        slowest
+    );
+}
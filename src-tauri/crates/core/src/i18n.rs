@@ -0,0 +1,435 @@
+//! 后端文案的多语言目录（zh-CN / en / ja，与前端 src/locales 对应）。
+//! HTTP 请求按 `Accept-Language` 选择语言（前端会带上当前界面语言），未携带或不支持时使用应用设置中的 `locale`；
+//! 窗口标题、命令返回的错误等本机文案直接使用应用设置。
+
+use std::fmt::Display;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    ZhCn = 0,
+    En = 1,
+    Ja = 2,
+}
+
+impl Locale {
+    /// 解析语言标签：zh、zh-CN、zh-Hans 等归为简体中文，en-US 归为 en，ja-JP 归为 ja
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "zh" => Some(Locale::ZhCn),
+            "en" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+
+    /// 按 q 值从高到低取第一个支持的语言，如 `ja-JP,ja;q=0.9,en;q=0.8`
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut tags: Vec<(f32, &str)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut it = part.split(';');
+                let tag = it.next()?.trim();
+                let q = it
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((q, tag))
+            })
+            .filter(|(q, _)| *q > 0.0)
+            .collect();
+        tags.sort_by(|a, b| b.0.total_cmp(&a.0));
+        tags.into_iter().find_map(|(_, tag)| Locale::parse(tag))
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::ZhCn => "zh-CN",
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+}
+
+/// 应用设置中的界面语言，启动时从 app-settings.json 读取，设置窗口修改后通过 set_locale 更新
+static APP_LOCALE: AtomicU8 = AtomicU8::new(Locale::ZhCn as u8);
+
+tokio::task_local! {
+    /// 当前 HTTP 请求协商出的语言，由 scope_request_locale 设置
+    static REQUEST_LOCALE: Locale;
+}
+
+pub fn app_locale() -> Locale {
+    match APP_LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::En,
+        2 => Locale::Ja,
+        _ => Locale::ZhCn,
+    }
+}
+
+pub fn set_app_locale(locale: Locale) {
+    APP_LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// 从设置文件读取界面语言；文件不存在或未设置时保持默认的简体中文
+pub fn load_app_locale(settings_path: &Path) {
+    let Ok(data) = std::fs::read_to_string(settings_path) else {
+        return;
+    };
+    let Ok(root) = serde_json::from_str::<serde_json::Value>(&data) else {
+        return;
+    };
+    if let Some(locale) = root
+        .get("locale")
+        .and_then(|v| v.as_str())
+        .and_then(Locale::parse)
+    {
+        set_app_locale(locale);
+    }
+}
+
+/// 当前应使用的语言：HTTP 请求内为协商结果，其余场景为应用设置
+pub fn current() -> Locale {
+    REQUEST_LOCALE
+        .try_with(|l| *l)
+        .unwrap_or_else(|_| app_locale())
+}
+
+/// 在 locale 下执行 fut，其中的 t/tf 使用该语言；HTTP 服务按 Accept-Language 协商后包住整个请求
+pub async fn scope_request_locale<F: Future>(locale: Locale, fut: F) -> F::Output {
+    REQUEST_LOCALE.scope(locale, fut).await
+}
+
+/// 按当前语言取文案
+pub fn t(msg: Msg) -> &'static str {
+    msg.text(current())
+}
+
+/// 按当前语言取文案，并依次替换其中的 `{}` 占位符
+pub fn tf(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut rest = t(msg);
+    for arg in args {
+        let Some(i) = rest.find("{}") else { break };
+        out.push_str(&rest[..i]);
+        out.push_str(&arg.to_string());
+        rest = &rest[i + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    // HTTP 接口错误
+    ValidationFailed,
+    InvalidIdempotencyKey,
+    IdempotencyKeyReused,
+    TodoIdConflict,
+    TodoNotFound,
+    GroupNotFound,
+    InvalidPairingCode,
+    MissingToken,
+    InvalidToken,
+    ClientOutdated,
+    ListTodosFailed,
+    CreateTodoFailed,
+    UpdateTodoFailed,
+    DeleteTodoFailed,
+    ReorderTodosFailed,
+    ListGroupsFailed,
+    CreateGroupFailed,
+    UpdateGroupFailed,
+    DeleteGroupFailed,
+    ReorderGroupsFailed,
+    LoadChangesFailed,
+    SyncOpFailed,
+    MergeChangesFailed,
+    PairFailed,
+    CheckTokenFailed,
+    DeviceInfoFailed,
+    UnnamedDevice,
+    MobilePageClient,
+    MobileTokenFailed,
+    // 字段校验
+    ContentEmpty,
+    ContentTooLong,
+    InvalidTime,
+    EndBeforeStart,
+    InvalidStatus,
+    InvalidImportance,
+    InvalidTodoId,
+    MissingTodoId,
+    MissingGroupId,
+    GroupNameEmpty,
+    // 窗口与页面
+    QrcodeWindowTitle,
+    SettingsWindowTitle,
+    MobilePageTitle,
+    DevModeHeading,
+    DevModeBuildHint,
+    DevModeRefreshHint,
+    // 命令错误
+    RemoteModeUnavailable,
+    PathEncodingError,
+    PathNotFound,
+    NoParentDir,
+    NoAppDataDir,
+    FolderNotFound,
+    InvalidPeerUrl,
+    PeerUnreachable,
+    PeerBadResponse,
+    PairWithSelf,
+    PeerNeedsRepair,
+    PullChangesFailed,
+    PushChangesFailed,
+    InvalidHostUrl,
+    HostUnreachable,
+    HostBadResponse,
+    SavePasswordFailed,
+    InvalidWebDavUrl,
+    SaveWebDavFailed,
+    WebDavNotConfigured,
+    SnapshotFailed,
+    InvalidRemoteFile,
+    WebDavUnreachable,
+    WebDavAuthFailed,
+    WebDavRequestFailed,
+    WebDavBadListing,
+    WebDavMkdirFailed,
+    // 数据库恢复
+    DbCorrupt,
+    DbLocked,
+    RecoveryWindowTitle,
+    NoBackupAvailable,
+    RecoveryNotNeeded,
+    DbLockedRetry,
+    DbNotCorruptRetry,
+    DbRestoreHint,
+    DbNoBackupHint,
+    BackupFileMissing,
+    MoveCorruptDbFailed,
+    MoveFileFailed,
+    DeleteFileFailed,
+    CopyBackupFailed,
+    ReplaceDbFailed,
+    // 数据库迁移
+    DbSchemaTooNew,
+    MigrationFailed,
+    MigrationBackupDirFailed,
+    MigrationBackupFailed,
+    CreateTriggersFailed,
+    // 定时备份
+    InvalidBackupSettings,
+    BackupNotFound,
+    BackupCorrupt,
+    BackupNoTodosTable,
+    RestoreFailed,
+    // 命令行
+    CliOpenDbFailed,
+    CliRuntimeFailed,
+    CliTodoNotFound,
+    CliAmbiguousId,
+    CliGroupNotFound,
+    CliInvalidDate,
+    CliNothingToEdit,
+    CliAdded,
+    CliUpdated,
+    CliCompleted,
+    CliDeleted,
+    CliNoTodos,
+    CliNoGroups,
+    CliColId,
+    CliColStatus,
+    CliColImportance,
+    CliColDue,
+    CliColGroup,
+    CliColContent,
+    CliColName,
+    CliStatusPending,
+    CliStatusCompleted,
+    CliImportanceNormal,
+    CliImportanceImportant,
+    CliImportanceUrgent,
+}
+
+impl Msg {
+    pub fn text(self, locale: Locale) -> &'static str {
+        let [zh, en, ja] = self.texts();
+        match locale {
+            Locale::ZhCn => zh,
+            Locale::En => en,
+            Locale::Ja => ja,
+        }
+    }
+
+    /// [简体中文, English, 日本語]
+    #[rustfmt::skip]
+    fn texts(self) -> [&'static str; 3] {
+        match self {
+            Msg::ValidationFailed => ["参数校验失败：{}", "Validation failed: {}", "入力内容に誤りがあります：{}"],
+            Msg::InvalidIdempotencyKey => ["Idempotency-Key 无效", "Invalid Idempotency-Key", "Idempotency-Key が無効です"],
+            Msg::IdempotencyKeyReused => ["该 Idempotency-Key 已用于内容不同的请求", "This Idempotency-Key was already used for a different request", "この Idempotency-Key は内容の異なるリクエストで使用済みです"],
+            Msg::TodoIdConflict => ["该任务 id 已用于内容不同的任务", "This todo id is already used by a todo with different content", "このタスク ID は内容の異なるタスクで使用済みです"],
+            Msg::TodoNotFound => ["任务不存在", "Task not found", "タスクが見つかりません"],
+            Msg::GroupNotFound => ["分组不存在", "Group not found", "グループが見つかりません"],
+            Msg::InvalidPairingCode => ["配对码无效或已过期", "Pairing code is invalid or expired", "ペアリングコードが無効か期限切れです"],
+            Msg::MissingToken => ["缺少访问令牌，请先配对或重新扫码", "Access token required; pair this device or scan the QR code again", "アクセストークンがありません。ペアリングするか QR コードを再度読み取ってください"],
+            Msg::InvalidToken => ["访问令牌无效", "Invalid access token", "アクセストークンが無効です"],
+            Msg::ClientOutdated => ["页面版本过旧，请刷新页面或重新扫码", "This page is out of date; reload it or scan the QR code again", "ページが古くなっています。再読み込みするか QR コードを再度読み取ってください"],
+            Msg::ListTodosFailed => ["获取列表失败", "Failed to load tasks", "タスクの取得に失敗しました"],
+            Msg::CreateTodoFailed => ["新增失败", "Failed to create task", "タスクの追加に失敗しました"],
+            Msg::UpdateTodoFailed => ["更新失败", "Failed to update task", "タスクの更新に失敗しました"],
+            Msg::DeleteTodoFailed => ["删除失败", "Failed to delete task", "タスクの削除に失敗しました"],
+            Msg::ReorderTodosFailed => ["排序失败", "Failed to reorder tasks", "タスクの並べ替えに失敗しました"],
+            Msg::ListGroupsFailed => ["获取分组失败", "Failed to load groups", "グループの取得に失敗しました"],
+            Msg::CreateGroupFailed => ["新增分组失败", "Failed to create group", "グループの追加に失敗しました"],
+            Msg::UpdateGroupFailed => ["更新分组失败", "Failed to update group", "グループの更新に失敗しました"],
+            Msg::DeleteGroupFailed => ["删除分组失败", "Failed to delete group", "グループの削除に失敗しました"],
+            Msg::ReorderGroupsFailed => ["分组排序失败", "Failed to reorder groups", "グループの並べ替えに失敗しました"],
+            Msg::LoadChangesFailed => ["获取变更失败", "Failed to load changes", "変更の取得に失敗しました"],
+            Msg::SyncOpFailed => ["同步操作失败", "Sync operation failed", "同期操作に失敗しました"],
+            Msg::MergeChangesFailed => ["合并变更失败", "Failed to merge changes", "変更のマージに失敗しました"],
+            Msg::PairFailed => ["配对失败", "Pairing failed", "ペアリングに失敗しました"],
+            Msg::CheckTokenFailed => ["校验访问令牌失败", "Failed to verify access token", "アクセストークンの確認に失敗しました"],
+            Msg::DeviceInfoFailed => ["获取设备信息失败", "Failed to get device info", "デバイス情報の取得に失敗しました"],
+            Msg::UnnamedDevice => ["未命名设备", "Unnamed device", "名前のないデバイス"],
+            Msg::MobilePageClient => ["手机扫码页面", "Mobile QR page", "スマホ用 QR ページ"],
+            Msg::MobileTokenFailed => ["生成扫码地址的访问令牌失败", "Failed to create the access token for the QR code", "QR コード用のアクセストークンを作成できませんでした"],
+            Msg::ContentEmpty => ["内容不能为空", "Content must not be empty", "内容を入力してください"],
+            Msg::ContentTooLong => ["内容不能超过 {} 个字符", "Content must be at most {} characters", "内容は {} 文字以内で入力してください"],
+            Msg::InvalidTime => ["时间格式无效，应为 RFC3339 或 YYYY-MM-DDTHH:mm:ss", "Invalid time, expected RFC3339 or YYYY-MM-DDTHH:mm:ss", "時刻の形式が無効です（RFC3339 または YYYY-MM-DDTHH:mm:ss）"],
+            Msg::EndBeforeStart => ["结束时间不能早于开始时间", "End time must not be earlier than start time", "終了時刻は開始時刻より前にできません"],
+            Msg::InvalidStatus => ["状态只能是 pending 或 completed", "Status must be pending or completed", "ステータスは pending または completed のみ指定できます"],
+            Msg::InvalidImportance => ["重要性只能是 normal、important 或 urgent", "Importance must be normal, important or urgent", "重要度は normal、important、urgent のいずれかです"],
+            Msg::InvalidTodoId => ["任务 id 无效", "Invalid task id", "タスク ID が無効です"],
+            Msg::MissingTodoId => ["缺少任务 id", "Task id is required", "タスク ID がありません"],
+            Msg::MissingGroupId => ["缺少分组 id", "Group id is required", "グループ ID がありません"],
+            Msg::GroupNameEmpty => ["分组名称不能为空", "Group name must not be empty", "グループ名を入力してください"],
+            Msg::QrcodeWindowTitle => ["扫码用手机编辑", "Scan to edit on phone", "スマホで読み取って編集"],
+            Msg::SettingsWindowTitle => ["设置", "Settings", "設定"],
+            Msg::MobilePageTitle => ["手机端", "Mobile", "モバイル"],
+            Msg::DevModeHeading => ["开发模式", "Development mode", "開発モード"],
+            Msg::DevModeBuildHint => ["请先在电脑上执行：", "Run this on your computer first: ", "先にパソコンで次を実行してください："],
+            Msg::DevModeRefreshHint => ["构建完成后刷新本页即可使用手机端。", "Refresh this page after the build finishes to use the mobile version.", "ビルド完了後にこのページを再読み込みするとモバイル版を使えます。"],
+            Msg::RemoteModeUnavailable => ["远程模式下不可用", "Not available in remote mode", "リモートモードでは利用できません"],
+            Msg::PathEncodingError => ["路径编码错误", "Invalid path encoding", "パスのエンコードが不正です"],
+            Msg::PathNotFound => ["路径不存在", "Path does not exist", "パスが存在しません"],
+            Msg::NoParentDir => ["无法获取父目录", "Cannot determine parent directory", "親フォルダを取得できません"],
+            Msg::NoAppDataDir => ["无法获取应用数据目录", "Cannot locate app data directory", "アプリのデータフォルダを取得できません"],
+            Msg::FolderNotFound => ["目录不存在", "Folder does not exist", "フォルダが存在しません"],
+            Msg::InvalidPeerUrl => ["对端地址无效", "Invalid peer address", "接続先のアドレスが無効です"],
+            Msg::PeerUnreachable => ["无法连接对端：{}", "Cannot reach peer: {}", "接続先に接続できません：{}"],
+            Msg::PeerBadResponse => ["对端响应无效：{}", "Invalid response from peer: {}", "接続先の応答が無効です：{}"],
+            Msg::PairWithSelf => ["不能与本机配对", "Cannot pair with this device", "このデバイス自身とはペアリングできません"],
+            Msg::PeerNeedsRepair => ["对端缺少访问令牌，请用对端的配对码重新配对", "No access token for this peer; pair again with a pairing code from the peer", "このピアのアクセストークンがありません。ピアのペアリングコードで再度ペアリングしてください"],
+            Msg::PullChangesFailed => ["拉取变更失败：{}", "Failed to pull changes: {}", "変更の取得に失敗しました：{}"],
+            Msg::PushChangesFailed => ["推送变更失败：{}", "Failed to push changes: {}", "変更の送信に失敗しました：{}"],
+            Msg::InvalidHostUrl => ["主机地址无效", "Invalid host address", "ホストのアドレスが無効です"],
+            Msg::HostUnreachable => ["无法连接主机：{}", "Cannot reach host: {}", "ホストに接続できません：{}"],
+            Msg::HostBadResponse => ["主机响应无效：{}", "Invalid response from host: {}", "ホストの応答が無効です：{}"],
+            Msg::SavePasswordFailed => ["保存密码失败：{}", "Failed to save password: {}", "パスワードの保存に失敗しました：{}"],
+            Msg::InvalidWebDavUrl => ["WebDAV 地址无效", "Invalid WebDAV URL", "WebDAV の URL が無効です"],
+            Msg::SaveWebDavFailed => ["保存 WebDAV 配置失败", "Failed to save WebDAV settings", "WebDAV 設定の保存に失敗しました"],
+            Msg::WebDavNotConfigured => ["尚未配置 WebDAV", "WebDAV is not configured", "WebDAV が設定されていません"],
+            Msg::SnapshotFailed => ["生成快照失败：{}", "Failed to create snapshot: {}", "スナップショットの作成に失敗しました：{}"],
+            Msg::InvalidRemoteFile => ["{} 内容无效：{}", "{} has invalid content: {}", "{} の内容が無効です：{}"],
+            Msg::WebDavUnreachable => ["无法连接 WebDAV：{}", "Cannot reach WebDAV: {}", "WebDAV に接続できません：{}"],
+            Msg::WebDavAuthFailed => ["WebDAV 用户名或密码错误", "Wrong WebDAV username or password", "WebDAV のユーザー名またはパスワードが違います"],
+            Msg::WebDavRequestFailed => ["WebDAV 请求失败：{}", "WebDAV request failed: {}", "WebDAV リクエストに失敗しました：{}"],
+            Msg::WebDavBadListing => ["无法解析 WebDAV 目录列表：{}", "Could not parse the WebDAV directory listing: {}", "WebDAV のディレクトリ一覧を解析できません：{}"],
+            Msg::WebDavMkdirFailed => ["创建 WebDAV 目录失败：{}", "Failed to create WebDAV folder: {}", "WebDAV フォルダの作成に失敗しました：{}"],
+            Msg::DbCorrupt => ["数据库文件已损坏：{}", "The database file is damaged: {}", "データベースファイルが破損しています：{}"],
+            Msg::DbLocked => ["数据库正被其他程序占用：{}", "The database is in use by another program: {}", "データベースが他のプログラムで使用中です：{}"],
+            Msg::RecoveryWindowTitle => ["数据库恢复", "Database recovery", "データベースの復旧"],
+            Msg::NoBackupAvailable => ["没有可用的备份", "No backup available", "利用できるバックアップがありません"],
+            Msg::RecoveryNotNeeded => ["数据库已正常打开，无需恢复", "The database opened normally; no recovery needed", "データベースは正常に開かれているため、復旧は不要です"],
+            Msg::DbLockedRetry => ["数据库正被其他程序占用，请关闭该程序后重试", "The database is in use by another program; close it and try again", "データベースが他のプログラムで使用中です。そのプログラムを終了してから再試行してください"],
+            Msg::DbNotCorruptRetry => ["数据库没有损坏，替换文件无法解决该问题，请按错误信息处理后重试", "The database is not damaged, so replacing it will not help; resolve the error shown and try again", "データベースは破損していないため、置き換えても解決しません。表示されたエラーに対処してから再試行してください"],
+            Msg::DbRestoreHint => ["可从最近的备份恢复：停止服务后把损坏的 {} 移走，再将 {} 复制过去", "To restore the latest backup, stop the service, move the damaged {} away and copy {} in its place", "最新のバックアップから復元するには、サービスを停止して破損した {} を移動し、{} をその場所にコピーしてください"],
+            Msg::DbNoBackupHint => ["没有可用的备份；把损坏的 {} 移走后重新启动会创建新的空数据库", "No backup is available; move the damaged {} away and restart to create a new empty database", "バックアップがありません。破損した {} を移動してから再起動すると、新しい空のデータベースが作成されます"],
+            Msg::BackupFileMissing => ["备份文件不存在：{}", "Backup file does not exist: {}", "バックアップファイルが存在しません：{}"],
+            Msg::MoveCorruptDbFailed => ["移动损坏的数据库失败：{}", "Failed to move the damaged database: {}", "破損したデータベースを移動できませんでした：{}"],
+            Msg::MoveFileFailed => ["移动 {} 失败：{}", "Failed to move {}: {}", "{} を移動できませんでした：{}"],
+            Msg::DeleteFileFailed => ["删除 {} 失败：{}", "Failed to delete {}: {}", "{} を削除できませんでした：{}"],
+            Msg::CopyBackupFailed => ["复制备份失败：{}", "Failed to copy the backup: {}", "バックアップをコピーできませんでした：{}"],
+            Msg::ReplaceDbFailed => ["替换数据库失败：{}", "Failed to replace the database: {}", "データベースを置き換えられませんでした：{}"],
+            Msg::DbSchemaTooNew => ["数据库结构版本 {} 高于当前应用支持的版本 {}，请升级应用", "The database schema version {} is newer than this app supports ({}); please update the app", "データベースの構造バージョン {} はこのアプリが対応するバージョン {} より新しいため、アプリを更新してください"],
+            Msg::MigrationFailed => ["数据库迁移 v{}（{}）失败：{}", "Database migration v{} ({}) failed: {}", "データベースの移行 v{}（{}）に失敗しました：{}"],
+            Msg::MigrationBackupDirFailed => ["创建备份目录失败：{}", "Failed to create the backup folder: {}", "バックアップフォルダを作成できませんでした：{}"],
+            Msg::MigrationBackupFailed => ["迁移前备份失败：{}", "Backup before migration failed: {}", "移行前のバックアップに失敗しました：{}"],
+            Msg::CreateTriggersFailed => ["创建 {} 触发器失败：{}", "Failed to create {} triggers: {}", "{} トリガーを作成できませんでした：{}"],
+            Msg::InvalidBackupSettings => ["备份间隔须为 1–720 小时，保留份数须为 1–100", "Backup interval must be 1–720 hours and the number kept 1–100", "バックアップ間隔は 1〜720 時間、保持数は 1〜100 で指定してください"],
+            Msg::BackupNotFound => ["找不到该备份", "Backup not found", "バックアップが見つかりません"],
+            Msg::BackupCorrupt => ["备份文件不可用：{}", "Backup file is unusable: {}", "バックアップファイルを使用できません：{}"],
+            Msg::BackupNoTodosTable => ["备份中没有任务表", "the backup has no todos table", "バックアップにタスクのテーブルがありません"],
+            Msg::RestoreFailed => ["还原失败，已继续使用当前数据库：{}", "Restore failed; the current database is still in use: {}", "復元に失敗しました。現在のデータベースをそのまま使用します：{}"],
+            Msg::CliOpenDbFailed => ["打开数据库失败：{}", "Failed to open database: {}", "データベースを開けませんでした：{}"],
+            Msg::CliRuntimeFailed => ["启动异步运行时失败：{}", "Failed to start the async runtime: {}", "非同期ランタイムを起動できませんでした：{}"],
+            Msg::CliTodoNotFound => ["找不到 id 为 {} 的任务", "No task with id {}", "id が {} のタスクが見つかりません"],
+            Msg::CliAmbiguousId => ["{} 匹配到多个任务，请输入更长的 id", "{} matches several tasks, use a longer id", "{} に一致するタスクが複数あります。より長い id を指定してください"],
+            Msg::CliGroupNotFound => ["找不到分组：{}", "No group named {}", "グループが見つかりません：{}"],
+            Msg::CliInvalidDate => ["无法识别的时间：{}（格式为 YYYY-MM-DD 或 YYYY-MM-DD HH:MM）", "Unrecognized time: {} (use YYYY-MM-DD or YYYY-MM-DD HH:MM)", "日時を認識できません：{}（YYYY-MM-DD または YYYY-MM-DD HH:MM の形式で指定してください）"],
+            Msg::CliNothingToEdit => ["没有指定要修改的内容", "Nothing to change", "変更する内容が指定されていません"],
+            Msg::CliAdded => ["已添加：{}", "Added: {}", "追加しました：{}"],
+            Msg::CliUpdated => ["已更新：{}", "Updated: {}", "更新しました：{}"],
+            Msg::CliCompleted => ["已完成：{}", "Completed: {}", "完了しました：{}"],
+            Msg::CliDeleted => ["已删除：{}", "Deleted: {}", "削除しました：{}"],
+            Msg::CliNoTodos => ["没有任务", "No tasks", "タスクはありません"],
+            Msg::CliNoGroups => ["没有分组", "No groups", "グループはありません"],
+            Msg::CliColId => ["ID", "ID", "ID"],
+            Msg::CliColStatus => ["状态", "Status", "状態"],
+            Msg::CliColImportance => ["重要性", "Priority", "重要度"],
+            Msg::CliColDue => ["截止", "Due", "期限"],
+            Msg::CliColGroup => ["分组", "Group", "グループ"],
+            Msg::CliColContent => ["内容", "Task", "内容"],
+            Msg::CliColName => ["名称", "Name", "名前"],
+            Msg::CliStatusPending => ["待办", "pending", "未完了"],
+            Msg::CliStatusCompleted => ["已完成", "done", "完了"],
+            Msg::CliImportanceNormal => ["普通", "normal", "通常"],
+            Msg::CliImportanceImportant => ["重要", "important", "重要"],
+            Msg::CliImportanceUrgent => ["紧急", "urgent", "緊急"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_language_picks_the_highest_supported_q_value() {
+        let parse = Locale::from_accept_language;
+        assert_eq!(parse("en-US"), Some(Locale::En));
+        assert_eq!(parse("ja-JP,ja;q=0.9,en;q=0.8"), Some(Locale::Ja));
+        assert_eq!(parse("fr-FR, en;q=0.5, ja;q=0.7"), Some(Locale::Ja));
+        assert_eq!(parse("en;q=0.2, zh-Hans;q=0.9"), Some(Locale::ZhCn));
+        assert_eq!(parse("zh_TW"), Some(Locale::ZhCn));
+        // q=0 表示不接受
+        assert_eq!(parse("ja;q=0, en;q=0.1"), Some(Locale::En));
+        // 无法解析的 q 值按默认的 1 处理
+        assert_eq!(parse("en;q=abc"), Some(Locale::En));
+    }
+
+    #[test]
+    fn unsupported_or_malformed_headers_fall_back() {
+        let parse = Locale::from_accept_language;
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("*"), None);
+        assert_eq!(parse("fr-FR,de;q=0.9"), None);
+        assert_eq!(parse(";;,, ;q=1"), None);
+        assert_eq!(parse("ja;q=0"), None);
+    }
+
+    #[tokio::test]
+    async fn request_scope_overrides_the_app_locale() {
+        let inside = scope_request_locale(Locale::Ja, async { t(Msg::TodoNotFound) }).await;
+        assert_eq!(inside, Msg::TodoNotFound.text(Locale::Ja));
+        let formatted =
+            scope_request_locale(Locale::En, async { tf(Msg::ValidationFailed, &[&"x"]) }).await;
+        assert_eq!(formatted, "Validation failed: x");
+    }
+}
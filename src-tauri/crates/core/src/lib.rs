@@ -0,0 +1,121 @@
+//! 任务数据的核心：数据模型、写入校验、多语言文案、数据库迁移、存储层与同步合并。
+//! 不依赖 Tauri 与 axum，桌面端、局域网服务、命令行与测试共用。
+
+pub mod i18n;
+pub mod maintenance;
+pub mod merge;
+pub mod migrations;
+pub mod recovery;
+pub mod store;
+pub mod validation;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Todo {
+    pub id: String,
+    pub content: String,
+    /// pending | completed
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reminder_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+    /// normal | important | urgent
+    pub importance: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    pub sort_order: i64,
+    /// 最后修改时间（毫秒），逐字段的修改时间另记在 field_clock 表，同步时按字段做“最后写入者胜”
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Group {
+    pub id: String,
+    pub name: String,
+    pub sort_order: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GroupItem {
+    pub id: String,
+    pub name: String,
+}
+
+/// 新增任务的请求体，服务端、命令行与 client 模块共用
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct CreateTodoBody {
+    /// 客户端生成的 id（可选）；已存在时视为重放，直接返回该任务
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// 任务内容，去掉首尾空白后不能为空，最多 1000 个字符
+    pub content: String,
+    /// pending（默认）| completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+    /// normal（默认）| important | urgent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+}
+
+/// 只更新出现的字段；start_time、end_time、group_id 传 null 或空串表示清空。
+/// 序列化时 None 的字段不输出，Some(None) 输出为 null。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateTodoBody {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "validation::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub start_time: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "validation::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub end_time: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importance: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "validation::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub group_id: Option<Option<String>>,
+}
+
+/// 访问令牌的摘要（SHA-256 十六进制）。api_tokens 只保存摘要，数据库快照与备份中不含可用的令牌
+pub fn token_hash(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,180 @@
+//! 数据库维护：完整性检查、整理（VACUUM + ANALYZE）与统计，供设置「数据与安全」诊断与压缩数据库。
+
+use serde::Serialize;
+
+use crate::store;
+
+/// 完整性检查最多报告的问题条数
+const MAX_INTEGRITY_PROBLEMS: i64 = 100;
+
+/// 一张表的行数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRowCount {
+    pub name: String,
+    pub rows: i64,
+}
+
+/// 数据库统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStats {
+    /// 各表行数（不含 SQLite 内部表），按表名排序
+    pub tables: Vec<TableRowCount>,
+    pub completed_todos: i64,
+    pub pending_todos: i64,
+    pub page_size: i64,
+    pub page_count: i64,
+    /// 空闲页数；删除数据后留下的空间，VACUUM 后归还给文件系统
+    pub freelist_count: i64,
+    pub free_bytes: i64,
+}
+
+impl DbStats {
+    /// 数据库文件的逻辑大小（不含 -wal）
+    pub fn size_bytes(&self) -> i64 {
+        self.page_size * self.page_count
+    }
+}
+
+/// 完整性检查结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub ok: bool,
+    /// 发现的问题，最多 100 条
+    pub problems: Vec<String>,
+}
+
+/// 整理结果：前后的文件大小与整理后的统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactResult {
+    pub size_before_bytes: i64,
+    pub size_after_bytes: i64,
+    pub stats: DbStats,
+}
+
+async fn pragma_i64(pool: &sqlx::SqlitePool, name: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(&format!("PRAGMA {}", name))
+        .fetch_one(pool)
+        .await
+}
+
+/// 各表行数、任务完成情况与页统计
+pub async fn stats(pool: &sqlx::SqlitePool) -> Result<DbStats, sqlx::Error> {
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut tables = Vec::with_capacity(names.len());
+    for name in names {
+        let rows: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM \"{}\"",
+            name.replace('"', "\"\"")
+        ))
+        .fetch_one(pool)
+        .await?;
+        tables.push(TableRowCount { name, rows });
+    }
+    let (completed_todos, pending_todos): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(status = 'completed'), 0), COALESCE(SUM(status <> 'completed'), 0) FROM todos",
+    )
+    .fetch_one(pool)
+    .await?;
+    let page_size = pragma_i64(pool, "page_size").await?;
+    let page_count = pragma_i64(pool, "page_count").await?;
+    let freelist_count = pragma_i64(pool, "freelist_count").await?;
+    Ok(DbStats {
+        tables,
+        completed_todos,
+        pending_todos,
+        page_size,
+        page_count,
+        freelist_count,
+        free_bytes: page_size * freelist_count,
+    })
+}
+
+/// 完整检查（PRAGMA integrity_check），包括索引与表内容是否一致；数据多时需要几秒
+pub async fn integrity_check(pool: &sqlx::SqlitePool) -> Result<IntegrityReport, sqlx::Error> {
+    let problems = store::integrity_problems(pool, true, MAX_INTEGRITY_PROBLEMS).await?;
+    Ok(IntegrityReport {
+        ok: problems.is_empty(),
+        problems,
+    })
+}
+
+/// VACUUM 重建数据库并归还空闲页，随后把 WAL 写回并截断，再 ANALYZE 更新查询规划用的统计。
+/// 整理期间其他写入会等待（最多 busy_timeout），数据越多耗时越长
+pub async fn compact(pool: &sqlx::SqlitePool) -> Result<CompactResult, sqlx::Error> {
+    let page_size = pragma_i64(pool, "page_size").await?;
+    let size_before_bytes = page_size * pragma_i64(pool, "page_count").await?;
+    sqlx::query("VACUUM").execute(pool).await?;
+    // 有其他连接正在读时检查点可能只完成一部分，不算失败，下次自动检查点会继续
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+    sqlx::query("ANALYZE").execute(pool).await?;
+    let stats = stats(pool).await?;
+    Ok(CompactResult {
+        size_before_bytes,
+        size_after_bytes: stats.size_bytes(),
+        stats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{SqliteStore, TodoStore};
+    use crate::{validation, CreateTodoBody};
+
+    async fn seeded_store(count: usize) -> SqliteStore {
+        let store = SqliteStore::in_memory().await.unwrap();
+        for i in 0..count {
+            let new = validation::new_todo(&CreateTodoBody {
+                content: format!("任务 {} {}", i, "x".repeat(200)),
+                status: Some(if i % 2 == 0 { "completed" } else { "pending" }.into()),
+                ..Default::default()
+            })
+            .unwrap();
+            store.create_todo(&format!("t{}", i), &new).await.unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn integrity_check_passes_on_a_fresh_database() {
+        let store = seeded_store(3).await;
+        let report = integrity_check(store.pool()).await.unwrap();
+        assert!(report.ok, "{:?}", report.problems);
+        assert!(report.problems.is_empty());
+    }
+
+    #[tokio::test]
+    async fn compact_returns_free_pages_and_keeps_the_data() {
+        let store = seeded_store(200).await;
+        for i in (0..200).filter(|i| i % 4 != 0) {
+            assert!(store.delete_todo(&format!("t{}", i)).await.unwrap());
+        }
+        let before = stats(store.pool()).await.unwrap();
+        assert!(before.freelist_count > 0);
+
+        let result = compact(store.pool()).await.unwrap();
+        assert_eq!(result.size_before_bytes, before.size_bytes());
+        assert!(result.size_after_bytes < result.size_before_bytes);
+        assert_eq!(result.stats.freelist_count, 0);
+        assert_eq!(result.stats.completed_todos, 50);
+        assert_eq!(result.stats.pending_todos, 0);
+        let todos = result
+            .stats
+            .tables
+            .iter()
+            .find(|t| t.name == "todos")
+            .unwrap();
+        assert_eq!(todos.rows, 50);
+        assert!(integrity_check(store.pool()).await.unwrap().ok);
+    }
+}
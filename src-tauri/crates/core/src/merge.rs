@@ -0,0 +1,535 @@
+//! 同步变更集与逐字段合并：点对点、共享文件夹与 WebDAV 同步交换的 ChangeSet 结构，以及把对端变更并入本机的逻辑。
+//! 每个字段的修改时间记在 field_clock 表，合并时逐字段“最后写入者胜”，两端改了同一任务的不同字段时各自保留；
+//! 删除以墓碑（change_log 中 deleted = 1 的记录）表示，删除时间不早于本地修改时删除优先。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+use crate::migrations::{GROUP_FIELDS, TODO_FIELDS};
+use crate::store::{begin_write, fetch_one_todo};
+use crate::{Group, Todo};
+
+/// 删除墓碑：deleted_at 为删除时间（毫秒），合并时与对端的 updated_at 比较
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Tombstone {
+    pub id: String,
+    pub deleted_at: i64,
+}
+
+/// 一个任务/分组各字段的修改时间（毫秒），键为字段名
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct FieldTimes {
+    pub id: String,
+    pub times: BTreeMap<String, i64>,
+}
+
+/// 一页变更：cursor 为本页最后一条的序号，下次以它作为 since；has_more 表示还有未拉取的变更。
+/// todo_fields / group_fields 为本页任务与分组的字段修改时间，旧版本不发送，缺失时按整行的 updated_at 计；
+/// origin 为写出这页变更的设备，只在共享目录的日志中填写，合并时记到 change_log.origin
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ChangeSet {
+    pub cursor: i64,
+    pub has_more: bool,
+    pub todos: Vec<Todo>,
+    pub groups: Vec<Group>,
+    pub deleted_todos: Vec<Tombstone>,
+    pub deleted_groups: Vec<Tombstone>,
+    pub todo_fields: Vec<FieldTimes>,
+    pub group_fields: Vec<FieldTimes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.todos.is_empty()
+            && self.groups.is_empty()
+            && self.deleted_todos.is_empty()
+            && self.deleted_groups.is_empty()
+    }
+}
+
+/// 一次写入（回放离线操作或合并对端变更）实际改动的任务与分组，写完后统一通知界面
+#[derive(Debug, Default)]
+pub struct Changed {
+    pub todos: Vec<String>,
+    pub groups: Vec<String>,
+    /// 删除了分组：其下任务的 group_id 被一并清空，但不逐条记录
+    pub groups_deleted: bool,
+}
+
+impl Changed {
+    /// 实际写入的条数
+    pub fn len(&self) -> usize {
+        self.todos.len() + self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub fn group_from_row((id, name, sort_order, updated_at): (String, String, i64, i64)) -> Group {
+    Group {
+        id,
+        name,
+        sort_order,
+        updated_at,
+    }
+}
+
+/// 参与合并的一端：整行数据（JSON 对象，字段名即列名）、各字段的修改时间与整行的 updated_at
+struct Side<'a> {
+    row: serde_json::Value,
+    times: Option<&'a BTreeMap<String, i64>>,
+    updated_at: i64,
+}
+
+impl Side<'_> {
+    fn value(&self, field: &str) -> &serde_json::Value {
+        self.row.get(field).unwrap_or(&serde_json::Value::Null)
+    }
+
+    /// 没有字段时间（旧版本对端）时按整行的 updated_at 计
+    fn time(&self, field: &str) -> i64 {
+        self.times
+            .and_then(|t| t.get(field).copied())
+            .unwrap_or(self.updated_at)
+    }
+}
+
+/// 逐字段合并的结果
+struct FieldMerge {
+    /// 合并后的整行
+    merged: serde_json::Map<String, serde_json::Value>,
+    /// 取自对端的字段及其修改时间
+    taken: Vec<(&'static str, i64)>,
+    /// 有字段保留了与对端不同的本地值
+    diverged: bool,
+}
+
+/// 合并一个任务/分组的结果
+#[derive(Debug, PartialEq)]
+enum Merged {
+    /// 本地已是相同或更新的内容
+    Unchanged,
+    /// 合并后与对端内容一致
+    Taken,
+    /// 部分字段取自对端，其余保留本地较新的值
+    Combined,
+}
+
+impl FieldMerge {
+    fn outcome(&self) -> Merged {
+        match (self.taken.is_empty(), self.diverged) {
+            (true, _) => Merged::Unchanged,
+            (false, false) => Merged::Taken,
+            (false, true) => Merged::Combined,
+        }
+    }
+}
+
+/// 逐字段“最后写入者胜”：每个字段比较两端的修改时间，较晚的一方胜；时间相同时比较序列化后的值，
+/// 保证两端独立得出同一结论。值相同的字段不算改动，避免同步回声反复写入。
+fn merge_fields(fields: &[&'static str], local: &Side, remote: &Side) -> FieldMerge {
+    let mut merged = local.row.as_object().cloned().unwrap_or_default();
+    let mut taken = Vec::new();
+    let mut diverged = false;
+    for &field in fields {
+        let (l, r) = (local.value(field), remote.value(field));
+        if l == r {
+            continue;
+        }
+        let (lt, rt) = (local.time(field), remote.time(field));
+        let tie_break = || serde_json::to_string(r).ok() > serde_json::to_string(l).ok();
+        if rt > lt || (rt == lt && tie_break()) {
+            merged.insert(field.to_string(), r.clone());
+            taken.push((field, rt));
+        } else {
+            diverged = true;
+        }
+    }
+    FieldMerge {
+        merged,
+        taken,
+        diverged,
+    }
+}
+
+async fn local_field_times(
+    conn: &mut sqlx::SqliteConnection,
+    entity: &str,
+    id: &str,
+) -> Result<BTreeMap<String, i64>, sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT field, updated_at FROM field_clock WHERE entity = ? AND entity_id = ?",
+    )
+    .bind(entity)
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// 写入取自对端的字段时间，覆盖触发器按整行 updated_at 记下的值
+async fn save_field_times(
+    conn: &mut sqlx::SqliteConnection,
+    entity: &str,
+    id: &str,
+    times: &[(&str, i64)],
+) -> Result<(), sqlx::Error> {
+    for (field, updated_at) in times {
+        sqlx::query(
+            "INSERT INTO field_clock (entity, entity_id, field, updated_at) VALUES (?, ?, ?, ?) \
+             ON CONFLICT (entity, entity_id, field) DO UPDATE SET updated_at = excluded.updated_at",
+        )
+        .bind(entity)
+        .bind(id)
+        .bind(field)
+        .bind(updated_at)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn group_known(conn: &mut sqlx::SqliteConnection, id: &str) -> Result<bool, sqlx::Error> {
+    let n: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM groups WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(n > 0)
+}
+
+async fn local_tombstone(
+    conn: &mut sqlx::SqliteConnection,
+    entity: &str,
+    id: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let ts: Option<Option<i64>> = sqlx::query_scalar(
+        "SELECT deleted_at FROM change_log WHERE entity = ? AND entity_id = ? AND deleted = 1",
+    )
+    .bind(entity)
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(ts.map(|t| t.unwrap_or(0)))
+}
+
+/// 删除后把墓碑时间改为对端的删除时间，使墓碑继续向其他设备传播时保持原始时间；origin 同时记下来源设备
+async fn set_tombstone_time(
+    conn: &mut sqlx::SqliteConnection,
+    entity: &str,
+    t: &Tombstone,
+    origin: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO change_log (entity, entity_id, deleted, deleted_at, origin) VALUES (?, ?, 1, ?, ?) \
+         ON CONFLICT (entity, entity_id) DO UPDATE SET deleted = 1, deleted_at = excluded.deleted_at, origin = excluded.origin",
+    )
+    .bind(entity)
+    .bind(&t.id)
+    .bind(t.deleted_at)
+    .bind(origin)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// 记下变更来自哪台设备（触发器写入的 origin 为空，表示本机产生）
+async fn tag_origin(
+    conn: &mut sqlx::SqliteConnection,
+    entity: &str,
+    id: &str,
+    origin: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    if origin.is_none() {
+        return Ok(());
+    }
+    sqlx::query("UPDATE change_log SET origin = ? WHERE entity = ? AND entity_id = ?")
+        .bind(origin)
+        .bind(entity)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// 合并对端的一个任务。group_id 指向本地不存在的分组（本地已删除该分组，或旧版本对端没有附上分组）时不采用：
+/// 已有任务保留本地的 group_id，新任务先不分组且该字段的修改时间记为 0，之后对端再发来时仍能胜出
+async fn merge_todo(
+    conn: &mut sqlx::SqliteConnection,
+    remote: &Todo,
+    remote_times: Option<&BTreeMap<String, i64>>,
+) -> Result<Merged, sqlx::Error> {
+    let group_missing = match &remote.group_id {
+        Some(g) => !group_known(conn, g).await?,
+        None => false,
+    };
+    let remote_side = Side {
+        row: serde_json::to_value(remote).unwrap_or_default(),
+        times: remote_times,
+        updated_at: remote.updated_at,
+    };
+    let (todo, times, outcome, sql) = match fetch_one_todo(&mut *conn, &remote.id).await? {
+        Some(local) => {
+            let local_times = local_field_times(conn, "todo", &local.id).await?;
+            let local_side = Side {
+                row: serde_json::to_value(&local).unwrap_or_default(),
+                times: Some(&local_times),
+                updated_at: local.updated_at,
+            };
+            let mut m = merge_fields(TODO_FIELDS, &local_side, &remote_side);
+            if group_missing {
+                if let Some(i) = m.taken.iter().position(|(f, _)| *f == "group_id") {
+                    m.taken.remove(i);
+                    m.merged
+                        .insert("group_id".to_string(), local_side.value("group_id").clone());
+                    m.diverged = true;
+                }
+            }
+            let outcome = m.outcome();
+            if outcome == Merged::Unchanged {
+                return Ok(outcome);
+            }
+            let mut todo: Todo = serde_json::from_value(serde_json::Value::Object(m.merged))
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            todo.updated_at = local.updated_at.max(remote.updated_at);
+            (
+                todo,
+                m.taken,
+                outcome,
+                "UPDATE todos SET content = ?2, status = ?3, reminder_time = ?4, start_time = ?5, end_time = ?6, \
+                 importance = ?7, group_id = ?8, sort_order = ?9, updated_at = ?10 WHERE id = ?1",
+            )
+        }
+        None => {
+            if let Some(deleted_at) = local_tombstone(conn, "todo", &remote.id).await? {
+                if remote.updated_at <= deleted_at {
+                    return Ok(Merged::Unchanged);
+                }
+            }
+            let mut todo = remote.clone();
+            let mut outcome = Merged::Taken;
+            let mut times: Vec<(&str, i64)> = TODO_FIELDS
+                .iter()
+                .map(|&f| (f, remote_side.time(f)))
+                .collect();
+            if group_missing {
+                todo.group_id = None;
+                outcome = Merged::Combined;
+                for (f, t) in times.iter_mut() {
+                    if *f == "group_id" {
+                        *t = 0;
+                    }
+                }
+            }
+            (
+                todo,
+                times,
+                outcome,
+                "INSERT INTO todos (id, content, status, reminder_time, start_time, end_time, importance, group_id, sort_order, updated_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )
+        }
+    };
+    sqlx::query(sql)
+        .bind(&todo.id)
+        .bind(&todo.content)
+        .bind(&todo.status)
+        .bind(&todo.reminder_time)
+        .bind(&todo.start_time)
+        .bind(&todo.end_time)
+        .bind(&todo.importance)
+        .bind(&todo.group_id)
+        .bind(todo.sort_order)
+        .bind(todo.updated_at)
+        .execute(&mut *conn)
+        .await?;
+    save_field_times(conn, "todo", &todo.id, &times).await?;
+    Ok(outcome)
+}
+
+async fn merge_group(
+    conn: &mut sqlx::SqliteConnection,
+    remote: &Group,
+    remote_times: Option<&BTreeMap<String, i64>>,
+) -> Result<Merged, sqlx::Error> {
+    let local: Option<(String, String, i64, i64)> = sqlx::query_as(
+        "SELECT id, name, COALESCE(sort_order, 0), updated_at FROM groups WHERE id = ?",
+    )
+    .bind(&remote.id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let remote_side = Side {
+        row: serde_json::to_value(remote).unwrap_or_default(),
+        times: remote_times,
+        updated_at: remote.updated_at,
+    };
+    let (group, times, outcome, sql) = match local.map(group_from_row) {
+        Some(local) => {
+            let local_times = local_field_times(conn, "group", &local.id).await?;
+            let local_side = Side {
+                row: serde_json::to_value(&local).unwrap_or_default(),
+                times: Some(&local_times),
+                updated_at: local.updated_at,
+            };
+            let m = merge_fields(GROUP_FIELDS, &local_side, &remote_side);
+            let outcome = m.outcome();
+            if outcome == Merged::Unchanged {
+                return Ok(outcome);
+            }
+            let mut group: Group = serde_json::from_value(serde_json::Value::Object(m.merged))
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            group.updated_at = local.updated_at.max(remote.updated_at);
+            (
+                group,
+                m.taken,
+                outcome,
+                "UPDATE groups SET name = ?2, sort_order = ?3, updated_at = ?4 WHERE id = ?1",
+            )
+        }
+        None => {
+            if let Some(deleted_at) = local_tombstone(conn, "group", &remote.id).await? {
+                if remote.updated_at <= deleted_at {
+                    return Ok(Merged::Unchanged);
+                }
+            }
+            let times = GROUP_FIELDS
+                .iter()
+                .map(|&f| (f, remote_side.time(f)))
+                .collect();
+            (
+                remote.clone(),
+                times,
+                Merged::Taken,
+                "INSERT INTO groups (id, name, sort_order, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            )
+        }
+    };
+    sqlx::query(sql)
+        .bind(&group.id)
+        .bind(&group.name)
+        .bind(group.sort_order)
+        .bind(group.updated_at)
+        .execute(&mut *conn)
+        .await?;
+    save_field_times(conn, "group", &group.id, &times).await?;
+    Ok(outcome)
+}
+
+/// 对端删除：删除时间不早于本地最后修改时才删除（删除优先于同一时刻的修改）
+async fn merge_todo_delete(
+    conn: &mut sqlx::SqliteConnection,
+    t: &Tombstone,
+    origin: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let local_ts: Option<i64> = sqlx::query_scalar("SELECT updated_at FROM todos WHERE id = ?")
+        .bind(&t.id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match local_ts {
+        Some(ts) if t.deleted_at >= ts => {
+            sqlx::query("DELETE FROM todos WHERE id = ?")
+                .bind(&t.id)
+                .execute(&mut *conn)
+                .await?;
+            set_tombstone_time(conn, "todo", t, origin).await?;
+            Ok(true)
+        }
+        Some(_) => Ok(false),
+        None => {
+            if local_tombstone(conn, "todo", &t.id).await?.is_none() {
+                set_tombstone_time(conn, "todo", t, origin).await?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+async fn merge_group_delete(
+    conn: &mut sqlx::SqliteConnection,
+    t: &Tombstone,
+    origin: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let local_ts: Option<i64> = sqlx::query_scalar("SELECT updated_at FROM groups WHERE id = ?")
+        .bind(&t.id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match local_ts {
+        Some(ts) if t.deleted_at >= ts => {
+            sqlx::query("UPDATE todos SET group_id = NULL, updated_at = MAX(updated_at, ?) WHERE group_id = ?")
+                .bind(t.deleted_at)
+                .bind(&t.id)
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("DELETE FROM groups WHERE id = ?")
+                .bind(&t.id)
+                .execute(&mut *conn)
+                .await?;
+            set_tombstone_time(conn, "group", t, origin).await?;
+            Ok(true)
+        }
+        Some(_) => Ok(false),
+        None => {
+            if local_tombstone(conn, "group", &t.id).await?.is_none() {
+                set_tombstone_time(conn, "group", t, origin).await?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// 在一个事务内合并对端变更，返回实际写入的任务与分组。先分组后任务，删除放在最后。
+/// set.origin 非空时，合并后与对端一致的实体在 change_log 中记下来源设备（server 的 sync::load_local_changes 据此跳过）。
+pub async fn apply_changes(
+    pool: &sqlx::SqlitePool,
+    set: &ChangeSet,
+) -> Result<Changed, sqlx::Error> {
+    let todo_fields: HashMap<&str, &BTreeMap<String, i64>> = set
+        .todo_fields
+        .iter()
+        .map(|f| (f.id.as_str(), &f.times))
+        .collect();
+    let group_fields: HashMap<&str, &BTreeMap<String, i64>> = set
+        .group_fields
+        .iter()
+        .map(|f| (f.id.as_str(), &f.times))
+        .collect();
+    let origin = set.origin.as_deref();
+    let mut tx = begin_write(pool).await?;
+    let mut changed = Changed::default();
+    for g in &set.groups {
+        let merged = merge_group(&mut tx, g, group_fields.get(g.id.as_str()).copied()).await?;
+        if merged == Merged::Taken {
+            tag_origin(&mut tx, "group", &g.id, origin).await?;
+        }
+        if merged != Merged::Unchanged {
+            changed.groups.push(g.id.clone());
+        }
+    }
+    for t in &set.todos {
+        let merged = merge_todo(&mut tx, t, todo_fields.get(t.id.as_str()).copied()).await?;
+        if merged == Merged::Taken {
+            tag_origin(&mut tx, "todo", &t.id, origin).await?;
+        }
+        if merged != Merged::Unchanged {
+            changed.todos.push(t.id.clone());
+        }
+    }
+    for t in &set.deleted_todos {
+        if merge_todo_delete(&mut tx, t, origin).await? {
+            changed.todos.push(t.id.clone());
+        }
+    }
+    for t in &set.deleted_groups {
+        if merge_group_delete(&mut tx, t, origin).await? {
+            changed.groups.push(t.id.clone());
+            changed.groups_deleted = true;
+        }
+    }
+    tx.commit().await?;
+    Ok(changed)
+}
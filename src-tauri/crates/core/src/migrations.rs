@@ -0,0 +1,739 @@
+//! 数据库结构版本管理：schema_version 表记录已执行的迁移，启动时按版本号顺序执行尚未执行的迁移。
+//! 每个迁移在独立事务中执行并写入版本号，任何一步出错都整体回滚并返回错误（不再忽略 ALTER TABLE 的失败）。
+//! 有待执行的迁移时，先用 VACUUM INTO 把现有 todos.db 备份到 `<应用数据目录>/backups/`。
+//!
+//! 新增结构变更时只在 MIGRATIONS 末尾追加新版本，已发布的迁移不要修改。
+
+use std::path::{Path, PathBuf};
+
+use crate::i18n::{tf, Msg};
+use crate::now_millis;
+
+/// 迁移中的一步
+enum Step {
+    Sql(&'static str),
+    /// 列不存在时才添加：引入版本号之前的旧库列集不一，只在基线迁移中使用
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        sql: &'static str,
+    },
+    /// 逐行用 Rust 函数改写某列的值，用于 SQLite 没有对应内置函数的转换（如计算摘要）
+    Rewrite {
+        table: &'static str,
+        column: &'static str,
+        rewrite: fn(&str) -> String,
+    },
+}
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: &'static [Step],
+}
+
+const CREATE_SCHEMA_VERSION_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY NOT NULL,
+    description TEXT NOT NULL,
+    applied_at INTEGER NOT NULL
+)"#;
+
+const CREATE_GROUPS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS groups (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    sort_order INTEGER DEFAULT 0,
+    updated_at INTEGER NOT NULL DEFAULT 0
+)"#;
+
+const CREATE_TODOS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS todos (
+    id TEXT PRIMARY KEY NOT NULL,
+    content TEXT NOT NULL,
+    status TEXT NOT NULL,
+    reminder_time TEXT,
+    start_time TEXT,
+    end_time TEXT,
+    importance TEXT NOT NULL DEFAULT 'normal',
+    group_id TEXT,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL DEFAULT 0
+)"#;
+
+const CREATE_TODOS_WITH_GROUP_FK_SQL: &str = r#"
+CREATE TABLE todos_new (
+    id TEXT PRIMARY KEY NOT NULL,
+    content TEXT NOT NULL,
+    status TEXT NOT NULL,
+    reminder_time TEXT,
+    start_time TEXT,
+    end_time TEXT,
+    importance TEXT NOT NULL DEFAULT 'normal',
+    group_id TEXT REFERENCES groups (id) ON DELETE SET NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL DEFAULT 0
+)"#;
+
+/// 变更日志：每个实体只保留一行，每次变更先删后插、重新分配自增 seq，作为增量同步的游标
+const CREATE_CHANGE_LOG_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS change_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    deleted INTEGER NOT NULL DEFAULT 0,
+    deleted_at INTEGER,
+    UNIQUE (entity, entity_id)
+)"#;
+
+/// 新增任务的幂等键：保存首次响应，客户端超时重试时原样返回
+const CREATE_IDEMPOTENCY_KEYS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY NOT NULL,
+    todo_id TEXT NOT NULL,
+    response TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+)"#;
+
+/// 同步元数据（本机 device_id 等键值）
+const CREATE_SYNC_META_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS sync_meta (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+)"#;
+
+/// 已配对的对端实例：分别记录拉取与推送的游标，断线后从游标处续传
+const CREATE_PEERS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS peers (
+    device_id TEXT PRIMARY KEY NOT NULL,
+    url TEXT NOT NULL,
+    pull_cursor INTEGER NOT NULL DEFAULT 0,
+    push_cursor INTEGER NOT NULL DEFAULT 0,
+    last_sync_at INTEGER,
+    last_error TEXT
+)"#;
+
+/// 远程模式设备的访问令牌（配对码换取）
+const CREATE_API_TOKENS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id TEXT PRIMARY KEY NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_used_at INTEGER
+)"#;
+
+/// 字段修改时间：每个任务/分组的每个字段一行，由触发器在字段值变化时写入所在行的 updated_at，
+/// 同步合并时据此逐字段比较（见 merge::apply_changes），两端改了不同字段时互不覆盖
+const CREATE_FIELD_CLOCK_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS field_clock (
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    field TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (entity, entity_id, field)
+)"#;
+
+/// 逐字段合并的任务列（与 Todo 序列化后的字段名相同）
+pub const TODO_FIELDS: &[&str] = &[
+    "content",
+    "status",
+    "reminder_time",
+    "start_time",
+    "end_time",
+    "importance",
+    "group_id",
+    "sort_order",
+];
+/// 逐字段合并的分组列
+pub const GROUP_FIELDS: &[&str] = &["name", "sort_order"];
+
+/// 所有迁移，按版本号递增排列
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "基线：任务、分组、变更日志与同步相关表",
+        steps: &[
+            Step::Sql(CREATE_GROUPS_SQL),
+            Step::Sql(CREATE_TODOS_SQL),
+            Step::AddColumn {
+                table: "todos",
+                column: "start_time",
+                sql: "ALTER TABLE todos ADD COLUMN start_time TEXT",
+            },
+            Step::AddColumn {
+                table: "todos",
+                column: "end_time",
+                sql: "ALTER TABLE todos ADD COLUMN end_time TEXT",
+            },
+            Step::AddColumn {
+                table: "todos",
+                column: "importance",
+                sql: "ALTER TABLE todos ADD COLUMN importance TEXT NOT NULL DEFAULT 'normal'",
+            },
+            Step::AddColumn {
+                table: "todos",
+                column: "group_id",
+                sql: "ALTER TABLE todos ADD COLUMN group_id TEXT",
+            },
+            Step::AddColumn {
+                table: "todos",
+                column: "sort_order",
+                sql: "ALTER TABLE todos ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::AddColumn {
+                table: "todos",
+                column: "updated_at",
+                sql: "ALTER TABLE todos ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::AddColumn {
+                table: "groups",
+                column: "sort_order",
+                sql: "ALTER TABLE groups ADD COLUMN sort_order INTEGER DEFAULT 0",
+            },
+            Step::AddColumn {
+                table: "groups",
+                column: "updated_at",
+                sql: "ALTER TABLE groups ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::Sql(CREATE_CHANGE_LOG_SQL),
+            // 为变更日志上线前已存在的数据补记录，使 since=0 的客户端能拿到全量
+            Step::Sql("INSERT OR IGNORE INTO change_log (entity, entity_id) SELECT 'group', id FROM groups"),
+            Step::Sql("INSERT OR IGNORE INTO change_log (entity, entity_id) SELECT 'todo', id FROM todos"),
+            Step::Sql(CREATE_IDEMPOTENCY_KEYS_SQL),
+            Step::Sql(CREATE_SYNC_META_SQL),
+            Step::Sql(CREATE_PEERS_SQL),
+            Step::Sql(CREATE_API_TOKENS_SQL),
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "todos.group_id 外键（分组删除时置空）",
+        // SQLite 不能给已有列加外键，只能重建表；指向不存在分组的 group_id 在复制时置空
+        steps: &[
+            Step::Sql(CREATE_TODOS_WITH_GROUP_FK_SQL),
+            Step::Sql(
+                "INSERT INTO todos_new (id, content, status, reminder_time, start_time, end_time, importance, group_id, sort_order, updated_at) \
+                 SELECT id, content, status, reminder_time, start_time, end_time, importance, \
+                 CASE WHEN group_id IN (SELECT id FROM groups) THEN group_id END, sort_order, updated_at FROM todos",
+            ),
+            Step::Sql("DROP TABLE todos"),
+            Step::Sql("ALTER TABLE todos_new RENAME TO todos"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_todos_group_id ON todos (group_id)"),
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "清空的时间字段由空串改为 NULL",
+        steps: &[
+            Step::Sql("UPDATE todos SET start_time = NULL WHERE TRIM(start_time) = ''"),
+            Step::Sql("UPDATE todos SET end_time = NULL WHERE TRIM(end_time) = ''"),
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "任务排序索引",
+        // 与 store::order_by_clause 的三种排序逐项一致（表达式须写法相同），列表查询按索引顺序读取、不再整表排序；
+        // sort_order 单列索引用于新增任务时取 MAX(sort_order)。按 group_id 过滤由 v2 的 idx_todos_group_id 负责
+        steps: &[
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_todos_sort_comprehensive ON todos (\
+                 (CASE WHEN end_time IS NULL THEN 1 ELSE 0 END), end_time, sort_order, \
+                 (CASE importance WHEN 'urgent' THEN 0 WHEN 'important' THEN 1 ELSE 2 END), id)",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_todos_sort_deadline ON todos (\
+                 (CASE WHEN end_time IS NULL THEN 1 ELSE 0 END), end_time, sort_order, id)",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_todos_sort_importance ON todos (\
+                 (CASE importance WHEN 'urgent' THEN 0 WHEN 'important' THEN 1 ELSE 2 END), sort_order, id)",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_todos_sort_order ON todos (sort_order)"),
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "幂等键记录请求摘要",
+        // 同一个键换了请求内容时拒绝而不是重放；旧记录的摘要为空串，视为不匹配任何请求
+        steps: &[Step::Sql(
+            "ALTER TABLE idempotency_keys ADD COLUMN request_hash TEXT NOT NULL DEFAULT ''",
+        )],
+    },
+    Migration {
+        version: 6,
+        description: "对端访问令牌",
+        // 配对时用对端的配对码换取；之前配对的对端没有令牌，需要重新配对
+        steps: &[Step::Sql("ALTER TABLE peers ADD COLUMN token TEXT")],
+    },
+    Migration {
+        version: 7,
+        description: "字段修改时间",
+        // 已有数据的各字段按所在行的 updated_at 补记录，与升级前整行比较的结论一致
+        steps: &[
+            Step::Sql(CREATE_FIELD_CLOCK_SQL),
+            Step::Sql(
+                "INSERT OR IGNORE INTO field_clock (entity, entity_id, field, updated_at) \
+                 SELECT 'todo', t.id, f.value, t.updated_at FROM todos t, \
+                 json_each('[\"content\",\"status\",\"reminder_time\",\"start_time\",\"end_time\",\"importance\",\"group_id\",\"sort_order\"]') f",
+            ),
+            Step::Sql(
+                "INSERT OR IGNORE INTO field_clock (entity, entity_id, field, updated_at) \
+                 SELECT 'group', g.id, f.value, g.updated_at FROM groups g, json_each('[\"name\",\"sort_order\"]') f",
+            ),
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "变更来源设备",
+        // 从共享目录导入的变更记下写出它的设备，导出本机日志时跳过，避免原样写回；本机产生的变更为 NULL
+        steps: &[Step::Sql("ALTER TABLE change_log ADD COLUMN origin TEXT")],
+    },
+    Migration {
+        version: 9,
+        description: "访问令牌只保存摘要",
+        // 已发放的令牌原地换成摘要，已配对的设备不必重新配对；手机扫码页面的令牌明文改存 SecretStore，
+        // 升级后首次生成二维码时重新发放（见 server 的 pairing::mobile_token）
+        steps: &[
+            Step::Rewrite {
+                table: "api_tokens",
+                column: "token",
+                rewrite: crate::token_hash,
+            },
+            Step::Sql("ALTER TABLE api_tokens RENAME COLUMN token TO token_hash"),
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "移除排序表达式索引",
+        // v4 的三个表达式索引让列表按索引顺序读取，但每行都要回表，10 万条时反而比整表排序慢
+        // （见 examples/bench_list_todos.rs）；保留 idx_todos_sort_order，新增任务取 MAX(sort_order) 仍需要它
+        steps: &[
+            Step::Sql("DROP INDEX IF EXISTS idx_todos_sort_comprehensive"),
+            Step::Sql("DROP INDEX IF EXISTS idx_todos_sort_deadline"),
+            Step::Sql("DROP INDEX IF EXISTS idx_todos_sort_importance"),
+        ],
+    },
+];
+
+/// 由触发器记录变更，HTTP、同步回放等任何写入路径都不会遗漏。
+/// 先删旧记录再插入以重新分配自增 seq；不用 INSERT OR REPLACE，因为外键动作（ON DELETE SET NULL）
+/// 触发的 UPDATE 会用外层语句的冲突策略覆盖触发器内的 OR REPLACE，导致唯一约束失败。
+/// 不属于版本化迁移：每次启动先删后建，触发器定义调整后旧库也能更新。
+const CHANGE_LOG_TRIGGERS: &[&str] = &[
+    "DROP TRIGGER IF EXISTS todos_change_insert",
+    "CREATE TRIGGER todos_change_insert AFTER INSERT ON todos BEGIN \
+     DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.id; \
+     INSERT INTO change_log (entity, entity_id, deleted) VALUES ('todo', NEW.id, 0); END",
+    "DROP TRIGGER IF EXISTS todos_change_update",
+    "CREATE TRIGGER todos_change_update AFTER UPDATE ON todos BEGIN \
+     DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.id; \
+     INSERT INTO change_log (entity, entity_id, deleted) VALUES ('todo', NEW.id, 0); END",
+    "DROP TRIGGER IF EXISTS todos_change_delete",
+    "CREATE TRIGGER todos_change_delete AFTER DELETE ON todos BEGIN \
+     DELETE FROM change_log WHERE entity = 'todo' AND entity_id = OLD.id; \
+     INSERT INTO change_log (entity, entity_id, deleted, deleted_at) \
+     VALUES ('todo', OLD.id, 1, CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)); END",
+    "DROP TRIGGER IF EXISTS groups_change_insert",
+    "CREATE TRIGGER groups_change_insert AFTER INSERT ON groups BEGIN \
+     DELETE FROM change_log WHERE entity = 'group' AND entity_id = NEW.id; \
+     INSERT INTO change_log (entity, entity_id, deleted) VALUES ('group', NEW.id, 0); END",
+    "DROP TRIGGER IF EXISTS groups_change_update",
+    "CREATE TRIGGER groups_change_update AFTER UPDATE ON groups BEGIN \
+     DELETE FROM change_log WHERE entity = 'group' AND entity_id = NEW.id; \
+     INSERT INTO change_log (entity, entity_id, deleted) VALUES ('group', NEW.id, 0); END",
+    "DROP TRIGGER IF EXISTS groups_change_delete",
+    "CREATE TRIGGER groups_change_delete AFTER DELETE ON groups BEGIN \
+     DELETE FROM change_log WHERE entity = 'group' AND entity_id = OLD.id; \
+     INSERT INTO change_log (entity, entity_id, deleted, deleted_at) \
+     VALUES ('group', OLD.id, 1, CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)); END",
+];
+
+/// 字段修改时间的触发器：新增时记下全部字段，修改时只记值有变化的字段，删除时清掉。
+/// 与变更日志触发器一样先删后插、每次启动重建；字段列表来自 TODO_FIELDS / GROUP_FIELDS，因此在运行时拼接
+fn field_clock_triggers(table: &str, entity: &str, fields: &[&str]) -> Vec<String> {
+    let field_list = fields
+        .iter()
+        .map(|f| format!("\"{}\"", f))
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut update = String::new();
+    for f in fields {
+        update.push_str(&format!(
+            "DELETE FROM field_clock WHERE entity = '{entity}' AND entity_id = NEW.id AND field = '{f}' AND OLD.{f} IS NOT NEW.{f}; \
+             INSERT INTO field_clock (entity, entity_id, field, updated_at) \
+             SELECT '{entity}', NEW.id, '{f}', NEW.updated_at WHERE OLD.{f} IS NOT NEW.{f}; "
+        ));
+    }
+    vec![
+        format!("DROP TRIGGER IF EXISTS {table}_clock_insert"),
+        format!(
+            "CREATE TRIGGER {table}_clock_insert AFTER INSERT ON {table} BEGIN \
+             DELETE FROM field_clock WHERE entity = '{entity}' AND entity_id = NEW.id; \
+             INSERT INTO field_clock (entity, entity_id, field, updated_at) \
+             SELECT '{entity}', NEW.id, value, NEW.updated_at FROM json_each('[{field_list}]'); END"
+        ),
+        format!("DROP TRIGGER IF EXISTS {table}_clock_update"),
+        format!("CREATE TRIGGER {table}_clock_update AFTER UPDATE ON {table} BEGIN {update}END"),
+        format!("DROP TRIGGER IF EXISTS {table}_clock_delete"),
+        format!(
+            "CREATE TRIGGER {table}_clock_delete AFTER DELETE ON {table} BEGIN \
+             DELETE FROM field_clock WHERE entity = '{entity}' AND entity_id = OLD.id; END"
+        ),
+    ]
+}
+
+/// 当前代码支持的最新结构版本
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// 数据库当前的结构版本（未执行过任何迁移为 0）
+pub async fn current_version(pool: &sqlx::SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query(CREATE_SCHEMA_VERSION_SQL).execute(pool).await?;
+    let v: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(v.unwrap_or(0))
+}
+
+/// 迁移前备份：VACUUM INTO 生成一致的快照，文件名带原版本号与时间
+async fn backup_before_migrate(
+    pool: &sqlx::SqlitePool,
+    backup_dir: &Path,
+    from_version: i64,
+) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(backup_dir).map_err(|e| tf(Msg::MigrationBackupDirFailed, &[&e]))?;
+    let path = backup_dir.join(format!(
+        "todos-pre-migrate-v{}-{}.db",
+        from_version,
+        now_millis()
+    ));
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| tf(Msg::MigrationBackupFailed, &[&e]))?;
+    Ok(path)
+}
+
+async fn apply(pool: &sqlx::SqlitePool, migration: &Migration) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for step in migration.steps {
+        match step {
+            Step::Sql(sql) => {
+                sqlx::query(sql).execute(&mut *tx).await?;
+            }
+            Step::AddColumn { table, column, sql } => {
+                let exists: i64 =
+                    sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+                        .bind(table)
+                        .bind(column)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                if exists == 0 {
+                    sqlx::query(sql).execute(&mut *tx).await?;
+                }
+            }
+            Step::Rewrite {
+                table,
+                column,
+                rewrite,
+            } => {
+                let rows: Vec<(i64, String)> =
+                    sqlx::query_as(&format!("SELECT rowid, {} FROM {}", column, table))
+                        .fetch_all(&mut *tx)
+                        .await?;
+                let sql = format!("UPDATE {} SET {} = ? WHERE rowid = ?", table, column);
+                for (rowid, value) in rows {
+                    sqlx::query(&sql)
+                        .bind(rewrite(&value))
+                        .bind(rowid)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+    }
+    sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(now_millis() as i64)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// 执行所有未执行的迁移并重建变更触发器。
+/// 已有数据且有待执行迁移时先备份到 backup_dir；数据库版本高于本程序时拒绝启动，避免旧版本写坏新结构。
+pub async fn migrate(pool: &sqlx::SqlitePool, backup_dir: &Path) -> Result<(), String> {
+    let current = current_version(pool).await.map_err(|e| e.to_string())?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(tf(Msg::DbSchemaTooNew, &[&current, &latest]));
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+    if !pending.is_empty() {
+        let has_data: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('todos', 'groups')",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if has_data > 0 {
+            let path = backup_before_migrate(pool, backup_dir, current).await?;
+            eprintln!("migrate: backup saved to {}", path.display());
+        }
+    }
+    for migration in pending {
+        apply(pool, migration).await.map_err(|e| {
+            tf(
+                Msg::MigrationFailed,
+                &[&migration.version, &migration.description, &e],
+            )
+        })?;
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for sql in CHANGE_LOG_TRIGGERS {
+        sqlx::query(sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| tf(Msg::CreateTriggersFailed, &[&"change_log", &e]))?;
+    }
+    let clock_triggers = field_clock_triggers("todos", "todo", TODO_FIELDS)
+        .into_iter()
+        .chain(field_clock_triggers("groups", "group", GROUP_FIELDS));
+    for sql in clock_triggers {
+        sqlx::query(&sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| tf(Msg::CreateTriggersFailed, &[&"field_clock", &e]))?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 临时目录下的数据库文件；VACUUM INTO 在内存库上不会落盘，备份相关的断言需要真实文件
+    async fn file_pool(dir: &Path) -> sqlx::SqlitePool {
+        let opts = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(dir.join("todos.db"))
+            .create_if_missing(true)
+            .foreign_keys(true);
+        sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(opts)
+            .await
+            .unwrap()
+    }
+
+    async fn exec(pool: &sqlx::SqlitePool, sql: &str) {
+        sqlx::query(sql).execute(pool).await.unwrap();
+    }
+
+    async fn columns(pool: &sqlx::SqlitePool, table: &str) -> Vec<String> {
+        sqlx::query_scalar("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+            .bind(table)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn applied_versions(pool: &sqlx::SqlitePool) -> Vec<i64> {
+        sqlx::query_scalar("SELECT version FROM schema_version ORDER BY version")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    fn backup_count(dir: &Path) -> usize {
+        std::fs::read_dir(dir).map(|d| d.count()).unwrap_or(0)
+    }
+
+    /// 引入版本号之前的旧库：groups 只有 id/name，todos 没有 updated_at
+    async fn legacy_pool(dir: &Path) -> sqlx::SqlitePool {
+        let pool = file_pool(dir).await;
+        exec(
+            &pool,
+            "CREATE TABLE groups (id TEXT PRIMARY KEY NOT NULL, name TEXT NOT NULL)",
+        )
+        .await;
+        exec(
+            &pool,
+            "CREATE TABLE todos (id TEXT PRIMARY KEY NOT NULL, content TEXT NOT NULL, status TEXT NOT NULL, \
+             reminder_time TEXT, start_time TEXT, end_time TEXT, importance TEXT NOT NULL DEFAULT 'normal', \
+             group_id TEXT, sort_order INTEGER NOT NULL DEFAULT 0)",
+        )
+        .await;
+        exec(&pool, "INSERT INTO groups (id, name) VALUES ('g1', '工作')").await;
+        exec(
+            &pool,
+            "INSERT INTO todos (id, content, status, start_time, end_time, group_id) VALUES \
+             ('t1', '写周报', 'pending', '', '2024-01-02T10:00', 'g1'), \
+             ('t2', '买菜', 'completed', ' ', '', 'missing')",
+        )
+        .await;
+        pool
+    }
+
+    #[tokio::test]
+    async fn fresh_database_reaches_latest_version_without_backup() {
+        let data = tempfile::tempdir().unwrap();
+        let dir = data.path().join(crate::store::DB_BACKUP_DIRNAME);
+        let pool = file_pool(data.path()).await;
+        migrate(&pool, &dir).await.unwrap();
+
+        let expected: Vec<i64> = (1..=latest_version()).collect();
+        assert_eq!(applied_versions(&pool).await, expected);
+        assert_eq!(current_version(&pool).await.unwrap(), latest_version());
+        assert_eq!(backup_count(&dir), 0);
+        assert!(columns(&pool, "change_log")
+            .await
+            .contains(&"deleted_at".to_string()));
+    }
+
+    #[tokio::test]
+    async fn legacy_database_is_upgraded_and_backed_up() {
+        let data = tempfile::tempdir().unwrap();
+        let dir = data.path().join(crate::store::DB_BACKUP_DIRNAME);
+        let pool = legacy_pool(data.path()).await;
+        migrate(&pool, &dir).await.unwrap();
+
+        assert_eq!(current_version(&pool).await.unwrap(), latest_version());
+        assert_eq!(backup_count(&dir), 1);
+        let backup = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+        assert!(backup
+            .file_name()
+            .to_string_lossy()
+            .starts_with("todos-pre-migrate-v0-"));
+
+        // v1：补齐缺失的列，已有数据补记变更日志
+        assert!(columns(&pool, "todos")
+            .await
+            .contains(&"updated_at".to_string()));
+        assert!(columns(&pool, "groups")
+            .await
+            .contains(&"sort_order".to_string()));
+        let logged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM change_log")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(logged, 3);
+
+        // v2：指向不存在分组的 group_id 置空，外键生效
+        type Row = (String, Option<String>, Option<String>, Option<String>);
+        let rows: Vec<Row> =
+            sqlx::query_as("SELECT id, group_id, start_time, end_time FROM todos ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "t1".to_string(),
+                    Some("g1".to_string()),
+                    None,
+                    Some("2024-01-02T10:00".to_string())
+                ),
+                ("t2".to_string(), None, None, None),
+            ]
+        );
+        exec(&pool, "DELETE FROM groups WHERE id = 'g1'").await;
+        let group_id: Option<String> =
+            sqlx::query_scalar("SELECT group_id FROM todos WHERE id = 't1'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(group_id, None);
+
+        // v4 建立、v10 移除表达式索引后只剩 sort_order 索引
+        let indexes: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_todos_sort_%'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(indexes, vec!["idx_todos_sort_order"]);
+    }
+
+    #[tokio::test]
+    async fn issued_tokens_are_replaced_by_their_hash() {
+        let data = tempfile::tempdir().unwrap();
+        let dir = data.path().join(crate::store::DB_BACKUP_DIRNAME);
+        let pool = file_pool(data.path()).await;
+        current_version(&pool).await.unwrap();
+        for m in MIGRATIONS.iter().filter(|m| m.version < 9) {
+            apply(&pool, m).await.unwrap();
+        }
+        exec(
+            &pool,
+            "INSERT INTO api_tokens (id, token, name, created_at) VALUES ('c1', 'secret-token', 'laptop', 0)",
+        )
+        .await;
+        migrate(&pool, &dir).await.unwrap();
+
+        let stored: String =
+            sqlx::query_scalar("SELECT token_hash FROM api_tokens WHERE id = 'c1'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(stored, crate::token_hash("secret-token"));
+        assert!(!columns(&pool, "api_tokens")
+            .await
+            .contains(&"token".to_string()));
+    }
+
+    #[tokio::test]
+    async fn rerun_is_a_no_op() {
+        let data = tempfile::tempdir().unwrap();
+        let dir = data.path().join(crate::store::DB_BACKUP_DIRNAME);
+        let pool = legacy_pool(data.path()).await;
+        migrate(&pool, &dir).await.unwrap();
+        migrate(&pool, &dir).await.unwrap();
+
+        let expected: Vec<i64> = (1..=latest_version()).collect();
+        assert_eq!(applied_versions(&pool).await, expected);
+        assert_eq!(backup_count(&dir), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_migration_rolls_back_and_keeps_earlier_versions() {
+        let data = tempfile::tempdir().unwrap();
+        let dir = data.path().join(crate::store::DB_BACKUP_DIRNAME);
+        let pool = legacy_pool(data.path()).await;
+        // v2 重建表时会 CREATE TABLE todos_new，预先占用这个名字使其失败
+        exec(&pool, "CREATE TABLE todos_new (id TEXT)").await;
+        let err = migrate(&pool, &dir).await.unwrap_err();
+        assert!(err.contains("v2"), "{}", err);
+
+        assert_eq!(applied_versions(&pool).await, vec![1]);
+        let group_id: Option<String> =
+            sqlx::query_scalar("SELECT group_id FROM todos WHERE id = 't2'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(group_id.as_deref(), Some("missing"));
+    }
+
+    #[tokio::test]
+    async fn newer_schema_is_refused() {
+        let data = tempfile::tempdir().unwrap();
+        let dir = data.path().join(crate::store::DB_BACKUP_DIRNAME);
+        let pool = file_pool(data.path()).await;
+        migrate(&pool, &dir).await.unwrap();
+        exec(
+            &pool,
+            &format!(
+                "INSERT INTO schema_version (version, description, applied_at) VALUES ({}, 'future', 0)",
+                latest_version() + 1
+            ),
+        )
+        .await;
+        assert!(migrate(&pool, &dir).await.is_err());
+    }
+}
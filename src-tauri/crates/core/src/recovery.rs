@@ -0,0 +1,255 @@
+//! 备份目录与数据库文件的替换：列出备份、数据库无法打开时把损坏的文件移到一旁、用备份替换或重新建库。
+//! 这里只做文件操作，替换前须确认没有连接池仍在使用该数据库（open_database 失败时已关闭连接池）。
+//! 生成备份与定时轮转见 xy-todo-server 的 backup 模块。
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::i18n::{tf, Msg};
+use crate::now_millis;
+use crate::store::DB_BACKUP_DIRNAME;
+
+/// SQLite 在 WAL 模式下与数据库文件配套的文件后缀
+const SIDECAR_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+/// 备份目录中的一个数据库快照
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    pub path: PathBuf,
+    pub file_name: String,
+    /// 由文件名前缀判断：auto | manual | pre-restore | pre-migrate，其他来源为 other
+    pub kind: &'static str,
+    pub size_bytes: u64,
+    /// 文件修改时间（毫秒）
+    pub modified_at: i64,
+}
+
+/// 备份文件名前缀与对应的 kind，文件名形如 todos-auto-<毫秒>.db
+const BACKUP_KINDS: [(&str, &str); 4] = [
+    ("todos-auto-", "auto"),
+    ("todos-manual-", "manual"),
+    ("todos-pre-restore-", "pre-restore"),
+    ("todos-pre-migrate-", "pre-migrate"),
+];
+
+/// 新备份的文件名；kind 须为 BACKUP_KINDS 中的一种
+pub fn backup_file_name(kind: &str) -> String {
+    let prefix = BACKUP_KINDS
+        .iter()
+        .find(|(_, k)| *k == kind)
+        .map_or("todos-", |(prefix, _)| prefix);
+    format!("{}{}.db", prefix, now_millis())
+}
+
+fn backup_kind(file_name: &str) -> &'static str {
+    BACKUP_KINDS
+        .iter()
+        .find(|(prefix, _)| file_name.starts_with(prefix))
+        .map_or("other", |(_, kind)| kind)
+}
+
+/// 数据库所在目录下的备份目录
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path.with_file_name(DB_BACKUP_DIRNAME)
+}
+
+/// 备份目录中的全部 .db 快照，最新的在前；目录不存在时为空
+pub fn list_backups(backup_dir: &Path) -> Vec<BackupFile> {
+    let Ok(entries) = std::fs::read_dir(backup_dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("db") {
+                return None;
+            }
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified_at = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as i64);
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            Some(BackupFile {
+                kind: backup_kind(&file_name),
+                file_name,
+                path,
+                size_bytes: meta.len(),
+                modified_at,
+            })
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.modified_at));
+    backups
+}
+
+/// 把数据库及其 -wal / -shm 文件改名为 todos-broken-<毫秒>.db（同目录），保留以便事后排查或手动抢救。
+/// 返回改名后的数据库路径；数据库文件不存在时返回 None
+pub fn set_aside(db_path: &Path) -> Result<Option<PathBuf>, String> {
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let stem = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("todos");
+    let aside = db_path.with_file_name(format!("{}-broken-{}.db", stem, now_millis()));
+    std::fs::rename(db_path, &aside).map_err(|e| tf(Msg::MoveCorruptDbFailed, &[&e]))?;
+    for suffix in SIDECAR_SUFFIXES {
+        let sidecar = sidecar_path(db_path, suffix);
+        if sidecar.exists() {
+            std::fs::rename(&sidecar, sidecar_path(&aside, suffix))
+                .map_err(|e| tf(Msg::MoveFileFailed, &[&sidecar.display(), &e]))?;
+        }
+    }
+    Ok(Some(aside))
+}
+
+/// 用备份替换数据库，现有文件改名保留（见 set_aside）。返回被移走的旧数据库路径
+pub fn restore_from(db_path: &Path, backup: &Path) -> Result<Option<PathBuf>, String> {
+    let staged = stage_copy(db_path, backup)?;
+    restore_staged(db_path, &staged)
+}
+
+/// 同 restore_from，但换入的是 stage_copy 已准备好的文件，便于调用方在两步之间调整它（如同步序号）
+pub fn restore_staged(db_path: &Path, staged: &Path) -> Result<Option<PathBuf>, String> {
+    let aside = match set_aside(db_path) {
+        Ok(aside) => aside,
+        Err(e) => {
+            let _ = std::fs::remove_file(staged);
+            return Err(e);
+        }
+    };
+    swap_in(db_path, staged)?;
+    Ok(aside)
+}
+
+/// 把备份复制到数据库旁的临时文件，供 swap_in 替换。先复制再替换，复制中断不会留下半个数据库；
+/// 复制是最容易失败的一步，可以在关闭连接池之前完成
+pub fn stage_copy(db_path: &Path, backup: &Path) -> Result<PathBuf, String> {
+    if !backup.is_file() {
+        return Err(tf(Msg::BackupFileMissing, &[&backup.display()]));
+    }
+    let staged = db_path.with_extension("db.restoring");
+    std::fs::copy(backup, &staged).map_err(|e| tf(Msg::CopyBackupFailed, &[&e]))?;
+    Ok(staged)
+}
+
+/// 用 stage_copy 准备好的文件替换数据库，并删除旧库残留的 -wal / -shm（否则 SQLite 会把旧日志回放到新库上）。
+/// 调用前须已关闭连接池
+pub fn swap_in(db_path: &Path, staged: &Path) -> Result<(), String> {
+    for suffix in SIDECAR_SUFFIXES {
+        let sidecar = sidecar_path(db_path, suffix);
+        if sidecar.exists() {
+            std::fs::remove_file(&sidecar)
+                .map_err(|e| tf(Msg::DeleteFileFailed, &[&sidecar.display(), &e]))?;
+        }
+    }
+    std::fs::rename(staged, db_path).map_err(|e| tf(Msg::ReplaceDbFailed, &[&e]))
+}
+
+fn sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 写一个数据库文件及其 -wal / -shm，内容分别为 "<tag>"、"<tag>-wal"、"<tag>-shm"
+    fn write_db(path: &Path, tag: &str) {
+        std::fs::write(path, tag).unwrap();
+        for suffix in SIDECAR_SUFFIXES {
+            std::fs::write(sidecar_path(path, suffix), format!("{}{}", tag, suffix)).unwrap();
+        }
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn set_aside_moves_the_database_with_its_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.db");
+        assert_eq!(set_aside(&db).unwrap(), None);
+
+        write_db(&db, "old");
+        let aside = set_aside(&db).unwrap().unwrap();
+        assert!(!db.exists());
+        assert_eq!(read(&aside), "old");
+        for suffix in SIDECAR_SUFFIXES {
+            assert!(!sidecar_path(&db, suffix).exists());
+            assert_eq!(
+                read(&sidecar_path(&aside, suffix)),
+                format!("old{}", suffix)
+            );
+        }
+    }
+
+    #[test]
+    fn swap_in_drops_the_old_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.db");
+        write_db(&db, "old");
+        let staged = dir.path().join("staged");
+        std::fs::write(&staged, "new").unwrap();
+
+        swap_in(&db, &staged).unwrap();
+        assert_eq!(read(&db), "new");
+        assert!(!staged.exists());
+        for suffix in SIDECAR_SUFFIXES {
+            assert!(!sidecar_path(&db, suffix).exists());
+        }
+    }
+
+    #[test]
+    fn restore_keeps_the_old_database_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.db");
+        let backup = dir.path().join("backup.db");
+        write_db(&db, "old");
+        std::fs::write(&backup, "backup").unwrap();
+
+        let aside = restore_from(&db, &backup).unwrap().unwrap();
+        assert_eq!(read(&db), "backup");
+        assert_eq!(read(&aside), "old");
+        assert_eq!(read(&backup), "backup");
+        assert!(!db.with_extension("db.restoring").exists());
+    }
+
+    #[test]
+    fn failed_copy_leaves_the_database_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.db");
+        write_db(&db, "old");
+
+        let missing = dir.path().join("missing.db");
+        assert!(stage_copy(&db, &missing).is_err());
+        assert!(restore_from(&db, &missing).is_err());
+        // 临时文件的位置被目录占住时复制本身失败
+        let backup = dir.path().join("backup.db");
+        std::fs::write(&backup, "backup").unwrap();
+        let staged = db.with_extension("db.restoring");
+        std::fs::create_dir(&staged).unwrap();
+        assert!(restore_from(&db, &backup).is_err());
+        std::fs::remove_dir(&staged).unwrap();
+
+        assert_eq!(read(&db), "old");
+        for suffix in SIDECAR_SUFFIXES {
+            assert_eq!(read(&sidecar_path(&db, suffix)), format!("old{}", suffix));
+        }
+        let names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|n| n.contains("broken") || n.contains("restoring"))
+            .collect();
+        assert!(names.is_empty(), "{:?}", names);
+    }
+}
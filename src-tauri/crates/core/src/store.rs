@@ -0,0 +1,966 @@
+//! 任务与分组的存储层：所有读写 todos / groups 表的 SQL 都在这里，不依赖 Tauri 与 axum。
+//! HTTP 接口、IPC 命令、命令行与 /api/v1/sync 回放、/api/v1/sync/merge 合并都通过 TodoStore 写入；
+//! 后台同步（点对点、共享文件夹、WebDAV）按 change_log 读取本机变更，本身依赖 SQLite，直接使用 SqliteStore。
+//! SqliteStore 可直接打开文件，也可用 SqliteStore::in_memory() 在内存中建库，方便在不启动应用的情况下使用与测试。
+
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::i18n::{tf, Msg};
+use crate::merge::{self, ChangeSet, Changed};
+use crate::validation::{self, FieldErrors, NewTodo};
+use crate::{migrations, now_millis, recovery, GroupItem, Todo, UpdateTodoBody};
+
+/// 数据库备份目录（位于应用数据目录下），迁移前的自动备份也放在这里
+pub const DB_BACKUP_DIRNAME: &str = "backups";
+/// 内容联想最多返回的条数
+const MAX_SUGGESTIONS: i64 = 20;
+/// 幂等键保留时长（毫秒），过期后同一个键按新请求处理
+const IDEMPOTENCY_KEY_TTL_MS: i64 = 24 * 60 * 60 * 1000;
+/// 连接池上限：WAL 下读互不阻塞，写入仍是串行的，连接多了只会排队等锁
+const MAX_CONNECTIONS: u32 = 4;
+/// 从连接池取连接的最长等待时间
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 每个连接的页缓存（KiB，对应 PRAGMA cache_size 的负值）。默认 2 MiB 装不下 10 万条任务的表，
+/// 按索引顺序读取时回表频繁缺页，比不用索引还慢；按需增长，小库不会占满
+const CACHE_SIZE_KIB: i64 = 16 * 1024;
+/// 写锁被占用时的等待时间，超时才报 database is locked；手机与桌面同时写入时由它兜底
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 启动时完整性检查最多报告的问题条数
+const MAX_INTEGRITY_PROBLEMS: i64 = 10;
+
+/// 打开数据库失败的原因，桌面端据此决定恢复窗口提供哪些操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenDbErrorKind {
+    /// 文件损坏或不是 SQLite 数据库，可从备份恢复或重新建库
+    Corrupt,
+    /// 被其他程序锁住（如另一个实例正在迁移），稍后重试即可，不应替换文件
+    Locked,
+    /// 其他原因：无权限、磁盘已满、迁移失败、数据库版本高于本程序等
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenDbError {
+    pub kind: OpenDbErrorKind,
+    /// SQLite 或迁移返回的原始错误
+    pub detail: String,
+}
+
+impl OpenDbError {
+    fn other(detail: impl Into<String>) -> Self {
+        Self {
+            kind: OpenDbErrorKind::Other,
+            detail: detail.into(),
+        }
+    }
+
+    /// 按 SQLite 主错误码归类：SQLITE_CORRUPT(11) / SQLITE_NOTADB(26) 为损坏，SQLITE_BUSY(5) / SQLITE_LOCKED(6) 为被占用
+    fn from_sqlx(e: sqlx::Error) -> Self {
+        let code = e
+            .as_database_error()
+            .and_then(|d| d.code())
+            .and_then(|c| c.parse::<i32>().ok());
+        let kind = match code.map(|c| c & 0xff) {
+            Some(11 | 26) => OpenDbErrorKind::Corrupt,
+            Some(5 | 6) => OpenDbErrorKind::Locked,
+            _ => OpenDbErrorKind::Other,
+        };
+        Self {
+            kind,
+            detail: e.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for OpenDbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            OpenDbErrorKind::Corrupt => f.write_str(&tf(Msg::DbCorrupt, &[&self.detail])),
+            OpenDbErrorKind::Locked => f.write_str(&tf(Msg::DbLocked, &[&self.detail])),
+            OpenDbErrorKind::Other => f.write_str(&self.detail),
+        }
+    }
+}
+
+impl std::error::Error for OpenDbError {}
+
+impl From<OpenDbError> for String {
+    fn from(e: OpenDbError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 打开（必要时创建）数据库，检查完整性后执行迁移；桌面端、命令行与无界面模式共用。
+/// 使用 WAL 日志：读写互不阻塞，同目录下会多出 todos.db-wal / todos.db-shm 两个文件，
+/// 复制数据库须用 VACUUM INTO 或先关闭连接池，不能只复制 todos.db。
+/// 失败时连接池已关闭，调用方可以直接移动或替换数据库文件（见 recovery）
+pub async fn open_database(db_path: &Path) -> Result<sqlx::SqlitePool, OpenDbError> {
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| OpenDbError::other(e.to_string()))?;
+    }
+    let backup_dir = recovery::backup_dir(db_path);
+    let opts = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        // WAL 下 NORMAL 不会损坏数据库，断电时最多丢失最后几次提交
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .pragma("cache_size", format!("-{}", CACHE_SIZE_KIB));
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .acquire_timeout(ACQUIRE_TIMEOUT)
+        .connect_with(opts)
+        .await
+        .map_err(OpenDbError::from_sqlx)?;
+    let checked = match integrity_problems(&pool, false, MAX_INTEGRITY_PROBLEMS).await {
+        Ok(problems) if problems.is_empty() => Ok(()),
+        Ok(problems) => Err(OpenDbError {
+            kind: OpenDbErrorKind::Corrupt,
+            detail: problems.join("; "),
+        }),
+        Err(e) => Err(OpenDbError::from_sqlx(e)),
+    };
+    let migrated = match checked {
+        Ok(()) => migrations::migrate(&pool, &backup_dir)
+            .await
+            .map_err(OpenDbError::other),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = migrated {
+        pool.close().await;
+        return Err(e);
+    }
+    Ok(pool)
+}
+
+/// 完整性检查，返回发现的问题（为空表示正常），最多 limit 条。
+/// full 为 false 时执行 PRAGMA quick_check，只检查页结构，不比对索引与表内容，启动时使用；
+/// 为 true 时执行 PRAGMA integrity_check
+pub async fn integrity_problems(
+    pool: &sqlx::SqlitePool,
+    full: bool,
+    limit: i64,
+) -> Result<Vec<String>, sqlx::Error> {
+    let pragma = if full {
+        "integrity_check"
+    } else {
+        "quick_check"
+    };
+    let rows: Vec<String> = sqlx::query_scalar(&format!("PRAGMA {}({})", pragma, limit.max(1)))
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().filter(|r| r != "ok").collect())
+}
+
+/// 任务与分组的读写接口
+#[async_trait]
+pub trait TodoStore: Send + Sync {
+    /// 按排序规则读取全部任务；sort 为 comprehensive（默认）| importance | deadline
+    async fn list_todos(&self, sort: &str) -> Result<Vec<Todo>, sqlx::Error>;
+    /// 读取单个任务，不存在时为 None
+    async fn get_todo(&self, id: &str) -> Result<Option<Todo>, sqlx::Error>;
+    /// 写入一条已校验的新任务，排序值追加到末尾
+    async fn create_todo(&self, id: &str, new: &NewTodo) -> Result<Todo, sqlx::Error>;
+    /// 按客户端 id 与可选的幂等键新增任务，重试不会重复创建；见 IdempotentCreate
+    async fn create_todo_idempotent(
+        &self,
+        id: &str,
+        new: &NewTodo,
+        key: Option<IdempotencyKey<'_>>,
+    ) -> Result<IdempotentCreate, sqlx::Error>;
+    /// 任务不存在时写入，已存在时不改动；返回是否写入（回放离线的新建操作）
+    async fn create_todo_if_absent(&self, id: &str, new: &NewTodo) -> Result<bool, sqlx::Error>;
+    /// 在同一个写事务内读取当前任务、校验并写入更新
+    async fn update_todo(&self, id: &str, body: &UpdateTodoBody)
+        -> Result<TodoUpdate, sqlx::Error>;
+    /// 删除任务；返回任务是否存在
+    async fn delete_todo(&self, id: &str) -> Result<bool, sqlx::Error>;
+    /// 按给定顺序重写任务排序值，不存在的 id 忽略
+    async fn reorder_todos(&self, ordered_ids: &[String]) -> Result<(), sqlx::Error>;
+    /// 按输入联想历史任务内容
+    async fn content_suggestions(&self, q: &str) -> Result<Vec<String>, sqlx::Error>;
+    /// 按排序值读取全部分组
+    async fn list_groups(&self) -> Result<Vec<GroupItem>, sqlx::Error>;
+    /// 写入一个新分组，排序值追加到末尾
+    async fn create_group(&self, id: &str, name: &str) -> Result<(), sqlx::Error>;
+    /// 分组不存在时写入，已存在时不改动；返回是否写入
+    async fn create_group_if_absent(&self, id: &str, name: &str) -> Result<bool, sqlx::Error>;
+    /// 重命名分组；返回分组是否存在
+    async fn rename_group(&self, id: &str, name: &str) -> Result<bool, sqlx::Error>;
+    /// 删除分组，其下任务变为未分组；返回分组是否存在
+    async fn delete_group(&self, id: &str) -> Result<bool, sqlx::Error>;
+    /// 按给定顺序重写分组排序值
+    async fn reorder_groups(&self, ordered_ids: &[String]) -> Result<(), sqlx::Error>;
+    /// 逐字段合并对端的变更集，返回实际写入的任务与分组（见 merge 模块）
+    async fn apply_changes(&self, set: &ChangeSet) -> Result<Changed, sqlx::Error>;
+}
+
+/// 新增任务时客户端提供的幂等键与请求内容的摘要
+#[derive(Debug, Clone, Copy)]
+pub struct IdempotencyKey<'a> {
+    pub key: &'a str,
+    pub request_hash: &'a str,
+}
+
+/// 幂等新增的结果
+#[derive(Debug)]
+pub enum IdempotentCreate {
+    /// 新写入的任务
+    Created(Todo),
+    /// 命中幂等键，或客户端 id 已存在且内容与分组相同，返回首次创建的任务
+    Replayed(Todo),
+    /// 客户端 id 已被内容或分组不同的任务占用
+    IdConflict,
+    /// 同一个幂等键再次提交时请求内容不同
+    KeyReused,
+}
+
+/// 基于 SQLite 的实现
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 打开数据库文件并执行迁移
+    pub async fn open(db_path: &Path) -> Result<Self, String> {
+        open_database(db_path)
+            .await
+            .map(Self::new)
+            .map_err(String::from)
+    }
+
+    /// 内存数据库，结构与正式库相同；只用一个连接，保证所有操作看到同一个库
+    pub async fn in_memory() -> Result<Self, String> {
+        let opts = sqlx::sqlite::SqliteConnectOptions::new()
+            .in_memory(true)
+            .foreign_keys(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(opts)
+            .await
+            .map_err(|e| e.to_string())?;
+        let backup_dir = std::env::temp_dir().join(DB_BACKUP_DIRNAME);
+        migrations::migrate(&pool, &backup_dir).await?;
+        Ok(Self::new(pool))
+    }
+
+    pub fn pool(&self) -> &sqlx::SqlitePool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl TodoStore for SqliteStore {
+    async fn list_todos(&self, sort: &str) -> Result<Vec<Todo>, sqlx::Error> {
+        query_todos(&self.pool, sort).await
+    }
+
+    async fn get_todo(&self, id: &str) -> Result<Option<Todo>, sqlx::Error> {
+        fetch_one_todo(&self.pool, id).await
+    }
+
+    async fn create_todo(&self, id: &str, new: &NewTodo) -> Result<Todo, sqlx::Error> {
+        insert_todo(&self.pool, id, new).await
+    }
+
+    async fn create_todo_idempotent(
+        &self,
+        id: &str,
+        new: &NewTodo,
+        key: Option<IdempotencyKey<'_>>,
+    ) -> Result<IdempotentCreate, sqlx::Error> {
+        insert_todo_idempotent(&self.pool, id, new, key).await
+    }
+
+    async fn create_todo_if_absent(&self, id: &str, new: &NewTodo) -> Result<bool, sqlx::Error> {
+        insert_todo_if_absent(&self.pool, id, new).await
+    }
+
+    async fn update_todo(
+        &self,
+        id: &str,
+        body: &UpdateTodoBody,
+    ) -> Result<TodoUpdate, sqlx::Error> {
+        patch_todo(&self.pool, id, body).await
+    }
+
+    async fn delete_todo(&self, id: &str) -> Result<bool, sqlx::Error> {
+        remove_todo(&self.pool, id).await
+    }
+
+    async fn reorder_todos(&self, ordered_ids: &[String]) -> Result<(), sqlx::Error> {
+        reorder_todo_ids(&self.pool, ordered_ids).await
+    }
+
+    async fn content_suggestions(&self, q: &str) -> Result<Vec<String>, sqlx::Error> {
+        query_content_suggestions(&self.pool, q).await
+    }
+
+    async fn list_groups(&self) -> Result<Vec<GroupItem>, sqlx::Error> {
+        query_groups(&self.pool).await
+    }
+
+    async fn create_group(&self, id: &str, name: &str) -> Result<(), sqlx::Error> {
+        insert_group(&self.pool, id, name).await
+    }
+
+    async fn create_group_if_absent(&self, id: &str, name: &str) -> Result<bool, sqlx::Error> {
+        insert_group_if_absent(&self.pool, id, name).await
+    }
+
+    async fn rename_group(&self, id: &str, name: &str) -> Result<bool, sqlx::Error> {
+        rename_group(&self.pool, id, name).await
+    }
+
+    async fn delete_group(&self, id: &str) -> Result<bool, sqlx::Error> {
+        remove_group(&self.pool, id).await
+    }
+
+    async fn reorder_groups(&self, ordered_ids: &[String]) -> Result<(), sqlx::Error> {
+        reorder_group_ids(&self.pool, ordered_ids).await
+    }
+
+    async fn apply_changes(&self, set: &ChangeSet) -> Result<Changed, sqlx::Error> {
+        merge::apply_changes(&self.pool, set).await
+    }
+}
+
+pub type TodoRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+    i64,
+    i64,
+);
+
+pub fn todo_from_row(row: TodoRow) -> Todo {
+    let (
+        id,
+        content,
+        status,
+        reminder_time,
+        start_time,
+        end_time,
+        importance,
+        group_id,
+        sort_order,
+        updated_at,
+    ) = row;
+    Todo {
+        id,
+        content,
+        status,
+        reminder_time,
+        start_time,
+        end_time,
+        importance,
+        group_id,
+        sort_order,
+        updated_at,
+    }
+}
+
+/// 读取单个任务，不存在时为 None；可在事务内使用
+pub async fn fetch_one_todo<'e, E>(executor: E, id: &str) -> Result<Option<Todo>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let row: Option<TodoRow> = sqlx::query_as(
+        "SELECT id, content, status, reminder_time, start_time, end_time, importance, group_id, sort_order, updated_at FROM todos WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(row.map(todo_from_row))
+}
+
+/// 排序不走索引：按表达式建索引后每行都要回表，整表读取反而比排序慢（见迁移 v10 与 examples/bench_list_todos.rs）
+fn order_by_clause(sort: &str) -> &'static str {
+    match sort {
+        "importance" => "ORDER BY CASE importance WHEN 'urgent' THEN 0 WHEN 'important' THEN 1 ELSE 2 END ASC, sort_order ASC, id ASC",
+        "deadline" => "ORDER BY CASE WHEN end_time IS NULL THEN 1 ELSE 0 END ASC, end_time ASC, sort_order ASC, id ASC",
+        _ => "ORDER BY CASE WHEN end_time IS NULL THEN 1 ELSE 0 END ASC, end_time ASC, sort_order ASC, CASE importance WHEN 'urgent' THEN 0 WHEN 'important' THEN 1 ELSE 2 END ASC, id ASC",
+    }
+}
+
+/// 列表查询的 SQL，基准测试用它查看查询计划
+pub fn list_todos_sql(sort: &str) -> String {
+    format!(
+        "SELECT id, content, status, reminder_time, start_time, end_time, importance, group_id, sort_order, updated_at FROM todos {}",
+        order_by_clause(sort)
+    )
+}
+
+/// 按排序规则读取全部任务；sort 为 comprehensive（默认）| importance | deadline
+pub async fn query_todos(pool: &sqlx::SqlitePool, sort: &str) -> Result<Vec<Todo>, sqlx::Error> {
+    let rows: Vec<TodoRow> = sqlx::query_as(&list_todos_sql(sort))
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(todo_from_row).collect())
+}
+
+/// 内容中包含 q 的历史任务内容（去重），q 中的 % 与 _ 按字面匹配
+pub async fn query_content_suggestions(
+    pool: &sqlx::SqlitePool,
+    q: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    let pattern = format!("%{}%", q.replace('%', "\\%").replace('_', "\\_"));
+    sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT content FROM todos WHERE content LIKE ?1 ESCAPE '\\' ORDER BY content LIMIT ?2",
+    )
+    .bind(&pattern)
+    .bind(MAX_SUGGESTIONS)
+    .fetch_all(pool)
+    .await
+}
+
+/// 开启写事务并立即取得写锁（BEGIN IMMEDIATE）。默认的 BEGIN 先读后写时才升级为写锁，
+/// WAL 下若期间有其他连接提交，升级会直接报 database is locked 而不经过 busy_timeout 等待
+pub async fn begin_write(
+    pool: &sqlx::SqlitePool,
+) -> Result<sqlx::Transaction<'static, sqlx::Sqlite>, sqlx::Error> {
+    pool.begin_with("BEGIN IMMEDIATE").await
+}
+
+/// 写入一条已校验的新任务，排序值追加到末尾。供 create_todo 与 /api/sync 共用。
+/// 取排序值与插入在同一事务内，并发新增不会拿到相同的排序值。
+pub async fn insert_todo(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    new: &NewTodo,
+) -> Result<Todo, sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    let todo = insert_todo_in(&mut tx, id, new).await?;
+    tx.commit().await?;
+    Ok(todo)
+}
+
+/// 任务不存在时写入，已存在时不改动；返回是否写入。供 /api/sync 回放新建操作：
+/// 检查与写入在同一个写事务内，同一条操作被并发重放时后到的一方看到已存在，而不是撞上主键冲突
+pub async fn insert_todo_if_absent(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    new: &NewTodo,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    if fetch_one_todo(&mut *tx, id).await?.is_some() {
+        return Ok(false);
+    }
+    insert_todo_in(&mut tx, id, new).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// 按客户端 id 与可选的幂等键新增任务。幂等键的占用、id 是否已存在的检查与写入在同一个写事务内，
+/// 失败时整体回滚，键不会被占住；并发的重试只会看到完整的首次结果
+pub async fn insert_todo_idempotent(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    new: &NewTodo,
+    key: Option<IdempotencyKey<'_>>,
+) -> Result<IdempotentCreate, sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    if let Some(key) = key {
+        match reserve_idempotency_key(&mut tx, key, id).await? {
+            Reserved::New => {}
+            Reserved::Saved(todo) => return Ok(IdempotentCreate::Replayed(*todo)),
+            Reserved::Reused => return Ok(IdempotentCreate::KeyReused),
+        }
+    }
+    let todo = match fetch_one_todo(&mut *tx, id).await? {
+        Some(todo) if todo.content != new.content || todo.group_id != new.group_id => {
+            return Ok(IdempotentCreate::IdConflict);
+        }
+        Some(todo) => IdempotentCreate::Replayed(todo),
+        None => IdempotentCreate::Created(insert_todo_in(&mut tx, id, new).await?),
+    };
+    if let (Some(key), IdempotentCreate::Created(t) | IdempotentCreate::Replayed(t)) = (key, &todo)
+    {
+        save_idempotent_response(&mut tx, key.key, t).await?;
+    }
+    tx.commit().await?;
+    Ok(todo)
+}
+
+/// 占用幂等键的结果
+enum Reserved {
+    /// 键未被使用（或已过期），已记下请求摘要
+    New,
+    /// 键已被同样的请求使用过，返回首次的结果
+    Saved(Box<Todo>),
+    /// 键已被内容不同的请求使用
+    Reused,
+}
+
+/// 在写事务内占用幂等键，过期的键先清理掉
+async fn reserve_idempotency_key(
+    conn: &mut sqlx::SqliteConnection,
+    key: IdempotencyKey<'_>,
+    todo_id: &str,
+) -> Result<Reserved, sqlx::Error> {
+    let now = now_millis() as i64;
+    sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?")
+        .bind(now - IDEMPOTENCY_KEY_TTL_MS)
+        .execute(&mut *conn)
+        .await?;
+    let reserved = sqlx::query(
+        "INSERT INTO idempotency_keys (key, todo_id, response, request_hash, created_at) VALUES (?, ?, '', ?, ?) \
+         ON CONFLICT (key) DO NOTHING",
+    )
+    .bind(key.key)
+    .bind(todo_id)
+    .bind(key.request_hash)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+    if reserved {
+        return Ok(Reserved::New);
+    }
+    let (saved_hash, response): (String, String) =
+        sqlx::query_as("SELECT request_hash, response FROM idempotency_keys WHERE key = ?")
+            .bind(key.key)
+            .fetch_one(&mut *conn)
+            .await?;
+    if saved_hash != key.request_hash {
+        return Ok(Reserved::Reused);
+    }
+    serde_json::from_str(&response)
+        .map(|todo| Reserved::Saved(Box::new(todo)))
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+/// 保存幂等键对应的结果
+async fn save_idempotent_response(
+    conn: &mut sqlx::SqliteConnection,
+    key: &str,
+    todo: &Todo,
+) -> Result<(), sqlx::Error> {
+    let response = serde_json::to_string(todo).unwrap_or_default();
+    sqlx::query("UPDATE idempotency_keys SET todo_id = ?, response = ? WHERE key = ?")
+        .bind(&todo.id)
+        .bind(&response)
+        .bind(key)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// 同 insert_todo，但在调用方已开启的写事务内执行，便于与其他写入一起提交
+pub async fn insert_todo_in(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    new: &NewTodo,
+) -> Result<Todo, sqlx::Error> {
+    let sort_order: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(sort_order), -1) + 1 FROM todos")
+        .fetch_one(&mut *conn)
+        .await?;
+    let todo = Todo {
+        id: id.to_string(),
+        content: new.content.clone(),
+        status: new.status.to_string(),
+        reminder_time: None,
+        start_time: new.start_time.clone(),
+        end_time: new.end_time.clone(),
+        importance: new.importance.to_string(),
+        group_id: new.group_id.clone(),
+        sort_order,
+        updated_at: now_millis() as i64,
+    };
+    sqlx::query(
+        "INSERT INTO todos (id, content, status, reminder_time, start_time, end_time, importance, group_id, sort_order, updated_at) VALUES (?, ?, ?, NULL, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&todo.id)
+    .bind(&todo.content)
+    .bind(&todo.status)
+    .bind(&todo.start_time)
+    .bind(&todo.end_time)
+    .bind(&todo.importance)
+    .bind(&todo.group_id)
+    .bind(todo.sort_order)
+    .bind(todo.updated_at)
+    .execute(&mut *conn)
+    .await?;
+    Ok(todo)
+}
+
+/// 任务更新的结果
+#[derive(Debug)]
+pub enum TodoUpdate {
+    /// 更新后的任务（没有要修改的字段时为当前任务）
+    Updated(Todo),
+    NotFound,
+    /// 校验未通过，未写入
+    Invalid(FieldErrors),
+}
+
+/// 写入更新中出现的字段，清空的字段写入 NULL。供 update_todo 与 /api/sync 共用。
+/// 读取当前任务、校验（结束不早于开始要结合未修改的一端）与写入在同一个 BEGIN IMMEDIATE 事务内，
+/// 校验所依据的任务不会在写入前被其他请求改掉。
+pub async fn patch_todo(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    body: &UpdateTodoBody,
+) -> Result<TodoUpdate, sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    let Some(current) = fetch_one_todo(&mut *tx, id).await? else {
+        return Ok(TodoUpdate::NotFound);
+    };
+    let patch = match validation::todo_patch(body, &current) {
+        Ok(patch) => patch,
+        Err(errors) => return Ok(TodoUpdate::Invalid(errors)),
+    };
+
+    let mut updates: Vec<String> = Vec::new();
+    let mut binds: Vec<Option<String>> = Vec::new();
+
+    if let Some(ref c) = patch.content {
+        updates.push("content = ?".to_string());
+        binds.push(Some(c.clone()));
+    }
+    if let Some(s) = patch.status {
+        updates.push("status = ?".to_string());
+        binds.push(Some(s.to_string()));
+    }
+    if let Some(ref t) = patch.start_time {
+        updates.push("start_time = ?".to_string());
+        binds.push(t.clone());
+    }
+    if let Some(ref t) = patch.end_time {
+        updates.push("end_time = ?".to_string());
+        binds.push(t.clone());
+    }
+    if let Some(imp) = patch.importance {
+        updates.push("importance = ?".to_string());
+        binds.push(Some(imp.to_string()));
+    }
+    if let Some(ref g) = patch.group_id {
+        updates.push("group_id = ?".to_string());
+        binds.push(g.clone());
+    }
+
+    if updates.is_empty() {
+        return Ok(TodoUpdate::Updated(current));
+    }
+
+    let sql = format!(
+        "UPDATE todos SET {}, updated_at = ? WHERE id = ?",
+        updates.join(", ")
+    );
+    let mut q = sqlx::query(&sql);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    q = q.bind(now_millis() as i64).bind(id);
+    q.execute(&mut *tx).await?;
+    let updated = fetch_one_todo(&mut *tx, id).await?;
+    tx.commit().await?;
+    Ok(updated.map_or(TodoUpdate::NotFound, TodoUpdate::Updated))
+}
+
+/// 删除任务；返回任务是否存在
+pub async fn remove_todo(pool: &sqlx::SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let r = sqlx::query("DELETE FROM todos WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// 在一个事务内按给定顺序重写任务排序值（不存在的 id 忽略），失败时整体回滚
+pub async fn reorder_todo_ids(
+    pool: &sqlx::SqlitePool,
+    ordered_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    let now = now_millis() as i64;
+    let mut tx = begin_write(pool).await?;
+    for (idx, id) in ordered_ids.iter().enumerate() {
+        sqlx::query("UPDATE todos SET sort_order = ?, updated_at = ? WHERE id = ?")
+            .bind(idx as i64)
+            .bind(now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// 按排序值读取全部分组
+pub async fn query_groups(pool: &sqlx::SqlitePool) -> Result<Vec<GroupItem>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT id, name FROM groups ORDER BY COALESCE(sort_order, 999999), id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, name)| GroupItem { id, name })
+        .collect())
+}
+
+/// 写入一个新分组，排序值追加到末尾
+pub async fn insert_group(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    insert_group_in(&mut tx, id, name).await?;
+    tx.commit().await
+}
+
+/// 分组不存在时写入，已存在时不改动；返回是否写入。检查与写入在同一个写事务内，见 insert_todo_if_absent
+pub async fn insert_group_if_absent(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    let exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM groups WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if exists.is_some() {
+        return Ok(false);
+    }
+    insert_group_in(&mut tx, id, name).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+async fn insert_group_in(
+    conn: &mut sqlx::SqliteConnection,
+    id: &str,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    let next_order: (Option<i64>,) = sqlx::query_as("SELECT MAX(sort_order) FROM groups")
+        .fetch_one(&mut *conn)
+        .await?;
+    let sort_order = next_order.0.unwrap_or(-1) + 1;
+    sqlx::query("INSERT INTO groups (id, name, sort_order, updated_at) VALUES (?, ?, ?, ?)")
+        .bind(id)
+        .bind(name)
+        .bind(sort_order)
+        .bind(now_millis() as i64)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// 重命名分组；返回分组是否存在
+pub async fn rename_group(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let r = sqlx::query("UPDATE groups SET name = ?, updated_at = ? WHERE id = ?")
+        .bind(name)
+        .bind(now_millis() as i64)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// 在一个事务内清空分组下任务的 group_id 并删除分组；返回分组是否存在。
+/// 外键的 ON DELETE SET NULL 只是兜底，这里显式更新是为了同时写入 updated_at 供同步比较。
+pub async fn remove_group(pool: &sqlx::SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = begin_write(pool).await?;
+    sqlx::query("UPDATE todos SET group_id = NULL, updated_at = ? WHERE group_id = ?")
+        .bind(now_millis() as i64)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let r = sqlx::query("DELETE FROM groups WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// 在一个事务内按给定顺序重写分组排序值，失败时整体回滚
+pub async fn reorder_group_ids(
+    pool: &sqlx::SqlitePool,
+    ordered_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    let now = now_millis() as i64;
+    let mut tx = begin_write(pool).await?;
+    for (i, id) in ordered_ids.iter().enumerate() {
+        sqlx::query("UPDATE groups SET sort_order = ?, updated_at = ? WHERE id = ?")
+            .bind(i as i64)
+            .bind(now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CreateTodoBody;
+
+    fn body(content: &str) -> CreateTodoBody {
+        CreateTodoBody {
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn invalid_fields(errors: &FieldErrors) -> Vec<&'static str> {
+        errors.fields().iter().map(|e| e.field).collect()
+    }
+
+    async fn create(store: &SqliteStore, id: &str, body: &CreateTodoBody) -> Todo {
+        let new = validation::new_todo(body).unwrap();
+        store.create_todo(id, &new).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn new_todos_are_validated_and_normalized() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        let errors = validation::new_todo(&CreateTodoBody {
+            content: "  ".into(),
+            status: Some("done".into()),
+            importance: Some("high".into()),
+            start_time: Some("2024-05-02T10:00:00".into()),
+            end_time: Some("2024-05-01T10:00:00".into()),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(
+            invalid_fields(&errors),
+            ["content", "status", "importance", "end_time"]
+        );
+        let too_long = "x".repeat(validation::MAX_CONTENT_CHARS + 1);
+        assert!(validation::new_todo(&body(&too_long)).is_err());
+
+        let todo = create(
+            &store,
+            "t1",
+            &CreateTodoBody {
+                content: "  写周报 ".into(),
+                importance: Some("紧急".into()),
+                start_time: Some(" ".into()),
+                group_id: Some("".into()),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(todo.content, "写周报");
+        assert_eq!(todo.status, "pending");
+        assert_eq!(todo.importance, "urgent");
+        assert_eq!(todo.start_time, None);
+        assert_eq!(todo.group_id, None);
+    }
+
+    #[tokio::test]
+    async fn unknown_group_is_rejected_by_the_foreign_key() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        let new = validation::new_todo(&CreateTodoBody {
+            content: "x".into(),
+            group_id: Some("missing".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let err = store.create_todo("t1", &new).await.unwrap_err();
+        assert!(matches!(&err, sqlx::Error::Database(db) if db.is_foreign_key_violation()));
+        assert!(store.get_todo("t1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn update_checks_the_range_against_the_stored_todo() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        create(
+            &store,
+            "t1",
+            &CreateTodoBody {
+                content: "x".into(),
+                start_time: Some("2024-05-02T10:00:00".into()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        // 只改结束时间，与库中的开始时间比较
+        let early_end = UpdateTodoBody {
+            end_time: Some(Some("2024-05-01T10:00:00".into())),
+            ..Default::default()
+        };
+        match store.update_todo("t1", &early_end).await.unwrap() {
+            TodoUpdate::Invalid(errors) => assert_eq!(invalid_fields(&errors), ["end_time"]),
+            _ => panic!("end before the stored start must be rejected"),
+        }
+        assert_eq!(store.get_todo("t1").await.unwrap().unwrap().end_time, None);
+
+        // 同时清空开始时间则不再冲突
+        let clear_start = UpdateTodoBody {
+            start_time: Some(None),
+            ..early_end
+        };
+        match store.update_todo("t1", &clear_start).await.unwrap() {
+            TodoUpdate::Updated(todo) => {
+                assert_eq!(todo.start_time, None);
+                assert_eq!(todo.end_time.as_deref(), Some("2024-05-01T10:00:00"));
+            }
+            _ => panic!("clearing the start makes the range valid"),
+        }
+
+        let status = UpdateTodoBody {
+            status: Some("archived".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            store.update_todo("t1", &status).await.unwrap(),
+            TodoUpdate::Invalid(_)
+        ));
+        assert!(matches!(
+            store
+                .update_todo("nope", &UpdateTodoBody::default())
+                .await
+                .unwrap(),
+            TodoUpdate::NotFound
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleting_a_group_ungroups_its_todos() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store.create_group("g1", "工作").await.unwrap();
+        create(
+            &store,
+            "t1",
+            &CreateTodoBody {
+                content: "x".into(),
+                group_id: Some("g1".into()),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(store.delete_group("g1").await.unwrap());
+        assert_eq!(store.get_todo("t1").await.unwrap().unwrap().group_id, None);
+        assert!(!store.delete_group("g1").await.unwrap());
+    }
+}
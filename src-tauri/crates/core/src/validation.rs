@@ -0,0 +1,221 @@
+//! 任务写入的参数校验：非法的状态/重要性、无法解析的时间、结束早于开始、超长内容一律拒绝，
+//! 以 `validation_failed` 返回 400 并在 `fields` 中逐项列出出错字段，不再静默改成默认值。时间接受 RFC3339（带时区）或
+//! 前端日期选择器的本地时间 `YYYY-MM-DDTHH:mm:ss`，原样保存；PATCH 中显式传 null 或空串表示清空，写入 NULL。
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+
+use crate::i18n::{t, tf, Msg};
+use crate::{CreateTodoBody, Todo, UpdateTodoBody};
+
+/// 任务内容最大字符数
+pub const MAX_CONTENT_CHARS: usize = 1000;
+/// 前端日期选择器（value-format）输出的本地时间格式
+const LOCAL_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    #[schema(value_type = String, example = "end_time")]
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// 一次请求中所有字段的校验错误
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn push(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field,
+            message: message.into(),
+        });
+    }
+
+    fn into_result<T>(self, value: T) -> Result<T, FieldErrors> {
+        if self.0.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn fields(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    /// 合并为一句话，用于只能返回单条消息的场景（如 /api/sync 的逐条结果）
+    pub fn message(&self) -> String {
+        self.0
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// 区分“未提供”与“显式清空”：字段缺省为 None，null 或空串为 Some(None)，其余为 Some(Some(..))
+pub fn nullable<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v: Option<String> = Option::deserialize(deserializer)?;
+    Ok(Some(
+        v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+    ))
+}
+
+/// 重要性：兼容旧客户端提交的中文值
+pub fn parse_importance(raw: &str) -> Option<&'static str> {
+    match raw.trim() {
+        "normal" | "普通" => Some("normal"),
+        "important" | "重要" => Some("important"),
+        "urgent" | "紧急" => Some("urgent"),
+        _ => None,
+    }
+}
+
+pub fn parse_status(raw: &str) -> Option<&'static str> {
+    match raw.trim() {
+        "pending" => Some("pending"),
+        "completed" => Some("completed"),
+        _ => None,
+    }
+}
+
+/// 解析时间为毫秒时间戳（仅用于比较先后）；本地时间按本机时区换算
+pub fn parse_time(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(raw) {
+        return Some(t.timestamp_millis());
+    }
+    let naive = NaiveDateTime::parse_from_str(raw, LOCAL_TIME_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.timestamp_millis())
+}
+
+fn check_content(errors: &mut FieldErrors, content: &str) -> String {
+    let content = content.trim();
+    if content.is_empty() {
+        errors.push("content", t(Msg::ContentEmpty));
+    } else if content.chars().count() > MAX_CONTENT_CHARS {
+        errors.push("content", tf(Msg::ContentTooLong, &[&MAX_CONTENT_CHARS]));
+    }
+    content.to_string()
+}
+
+fn check_time(errors: &mut FieldErrors, field: &'static str, raw: Option<&str>) -> Option<i64> {
+    let raw = raw?;
+    let parsed = parse_time(raw);
+    if parsed.is_none() {
+        errors.push(field, t(Msg::InvalidTime));
+    }
+    parsed
+}
+
+fn check_range(errors: &mut FieldErrors, start: Option<i64>, end: Option<i64>) {
+    if let (Some(s), Some(e)) = (start, end) {
+        if e < s {
+            errors.push("end_time", t(Msg::EndBeforeStart));
+        }
+    }
+}
+
+fn blank_to_none(raw: Option<&str>) -> Option<String> {
+    raw.map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// 校验通过的新任务
+pub struct NewTodo {
+    pub content: String,
+    pub status: &'static str,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub importance: &'static str,
+    pub group_id: Option<String>,
+}
+
+/// 校验通过的任务更新；外层 None 表示不修改，Some(None) 表示清空
+pub struct TodoPatch {
+    pub content: Option<String>,
+    pub status: Option<&'static str>,
+    pub start_time: Option<Option<String>>,
+    pub end_time: Option<Option<String>>,
+    pub importance: Option<&'static str>,
+    pub group_id: Option<Option<String>>,
+}
+
+pub fn new_todo(body: &CreateTodoBody) -> Result<NewTodo, FieldErrors> {
+    let mut errors = FieldErrors::default();
+    let content = check_content(&mut errors, &body.content);
+    let status = match blank_to_none(body.status.as_deref()) {
+        None => "pending",
+        Some(s) => parse_status(&s).unwrap_or_else(|| {
+            errors.push("status", t(Msg::InvalidStatus));
+            "pending"
+        }),
+    };
+    let importance = match blank_to_none(body.importance.as_deref()) {
+        None => "normal",
+        Some(s) => parse_importance(&s).unwrap_or_else(|| {
+            errors.push("importance", t(Msg::InvalidImportance));
+            "normal"
+        }),
+    };
+    let start_time = blank_to_none(body.start_time.as_deref());
+    let end_time = blank_to_none(body.end_time.as_deref());
+    let start = check_time(&mut errors, "start_time", start_time.as_deref());
+    let end = check_time(&mut errors, "end_time", end_time.as_deref());
+    check_range(&mut errors, start, end);
+    errors.into_result(NewTodo {
+        content,
+        status,
+        start_time,
+        end_time,
+        importance,
+        group_id: blank_to_none(body.group_id.as_deref()),
+    })
+}
+
+/// 校验任务更新；结束不早于开始的检查结合 current 中未修改的一端
+pub fn todo_patch(body: &UpdateTodoBody, current: &Todo) -> Result<TodoPatch, FieldErrors> {
+    let mut errors = FieldErrors::default();
+    let content = body
+        .content
+        .as_deref()
+        .map(|c| check_content(&mut errors, c));
+    let status = body.status.as_deref().map(|s| {
+        parse_status(s).unwrap_or_else(|| {
+            errors.push("status", t(Msg::InvalidStatus));
+            "pending"
+        })
+    });
+    let importance = body.importance.as_deref().map(|s| {
+        parse_importance(s).unwrap_or_else(|| {
+            errors.push("importance", t(Msg::InvalidImportance));
+            "normal"
+        })
+    });
+    let start = match &body.start_time {
+        Some(v) => check_time(&mut errors, "start_time", v.as_deref()),
+        None => current.start_time.as_deref().and_then(parse_time),
+    };
+    let end = match &body.end_time {
+        Some(v) => check_time(&mut errors, "end_time", v.as_deref()),
+        None => current.end_time.as_deref().and_then(parse_time),
+    };
+    check_range(&mut errors, start, end);
+    errors.into_result(TodoPatch {
+        content,
+        status,
+        start_time: body.start_time.clone(),
+        end_time: body.end_time.clone(),
+        importance,
+        group_id: body.group_id.clone(),
+    })
+}
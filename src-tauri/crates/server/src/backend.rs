@@ -0,0 +1,114 @@
+//! 本机后端的生命周期：打开数据库、启动后台同步与局域网服务，退出时按顺序关闭。
+//! 所有任务都跑在调用方所在的 tokio 运行时上（桌面端为 Tauri 的 async runtime，无界面模式为 run_headless 创建的运行时），
+//! 不再为数据库或 axum 单独创建运行时。
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use xy_todo_core::recovery;
+use xy_todo_core::store::{self, OpenDbError};
+
+use crate::events::EventSink;
+use crate::secrets::SecretStore;
+use crate::{backup, folder_sync, peer, server, webdav, AppState};
+
+/// 退出时等待处理中的请求结束的最长时间，超时后直接中止服务
+const SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct LocalBackend {
+    pub state: AppState,
+    /// 置为 true 时局域网服务停止接收新连接
+    shutdown: watch::Sender<bool>,
+    server: Option<JoinHandle<Result<(), String>>>,
+    /// 点对点、共享文件夹与 WebDAV 的后台同步，以及定时备份
+    sync_tasks: Vec<JoinHandle<()>>,
+}
+
+impl LocalBackend {
+    /// 打开 todos.db、检查完整性并执行迁移，随后启动后台同步与定时备份；须在 tokio 运行时内调用。
+    /// 失败时返回原因分类，由调用方决定如何提示与恢复
+    pub async fn open(
+        db_path: &Path,
+        events: Arc<dyn EventSink>,
+        secrets: Arc<dyn SecretStore>,
+    ) -> Result<Self, OpenDbError> {
+        let pool = store::open_database(db_path).await?;
+        let state = AppState::new(pool, events, secrets);
+        let sync_tasks = vec![
+            tokio::spawn(peer::run_sync_loop(
+                state.pool.clone(),
+                state.events.clone(),
+                state.secrets.clone(),
+            )),
+            tokio::spawn(folder_sync::run_folder_sync_loop(
+                state.pool.clone(),
+                state.events.clone(),
+            )),
+            tokio::spawn(webdav::run_webdav_sync_loop(
+                state.pool.clone(),
+                state.events.clone(),
+                state.secrets.clone(),
+            )),
+            tokio::spawn(backup::run_backup_loop(
+                state.pool.clone(),
+                recovery::backup_dir(db_path),
+            )),
+        ];
+        Ok(Self {
+            state,
+            shutdown: watch::channel(false).0,
+            server: None,
+            sync_tasks,
+        })
+    }
+
+    /// 绑定端口并启动局域网服务。绑定失败时返回错误，数据库与后台同步不受影响
+    pub async fn start_server(&mut self, config: server::ServerConfig) -> Result<(), String> {
+        let listener = server::bind(&config).await?;
+        let mut stop = self.shutdown.subscribe();
+        let shutdown = async move {
+            let _ = stop.wait_for(|stop| *stop).await;
+        };
+        let state = self.state.clone();
+        self.server = Some(tokio::spawn(server::serve(
+            state, config, listener, shutdown,
+        )));
+        Ok(())
+    }
+
+    /// 局域网服务意外退出（如监听出错）时返回其结果；未启动服务时一直挂起
+    pub async fn server_stopped(&mut self) -> Result<(), String> {
+        let Some(server) = self.server.as_mut() else {
+            return std::future::pending().await;
+        };
+        let result = server.await.map_err(|e| e.to_string()).and_then(|r| r);
+        self.server = None;
+        result
+    }
+
+    /// 按顺序关闭：先停止接收请求并等处理中的请求结束，再停止后台同步，最后关闭连接池，
+    /// 保证退出时没有写入还在使用数据库
+    pub async fn shutdown(mut self) {
+        self.shutdown.send_replace(true);
+        if let Some(mut server) = self.server.take() {
+            match tokio::time::timeout(SERVER_SHUTDOWN_TIMEOUT, &mut server).await {
+                Ok(Ok(Err(e))) => eprintln!("{}", e),
+                Ok(_) => {}
+                Err(_) => {
+                    eprintln!("server shutdown timed out");
+                    server.abort();
+                }
+            }
+        }
+        for task in &self.sync_tasks {
+            task.abort();
+        }
+        for task in self.sync_tasks.drain(..) {
+            let _ = task.await;
+        }
+        self.state.pool.close().await;
+    }
+}
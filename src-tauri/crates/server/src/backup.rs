@@ -0,0 +1,433 @@
+//! 本机定时备份：按设置的间隔用 VACUUM INTO 把 todos.db 快照写入应用数据目录的 `backups/`，
+//! 自动备份、手动备份、还原前的安全快照与迁移前备份各自只保留最近若干份（保留份数共用一个设置）。
+//! 设置记在 sync_meta，随数据库一起备份，还原旧备份时设置也会回到当时的状态。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use xy_todo_core::i18n::{t, tf, Msg};
+use xy_todo_core::recovery::{self, BackupFile};
+use xy_todo_core::{now_millis, store};
+
+use crate::sync::{meta_get, meta_set};
+
+/// 后台检查是否到了备份时间的间隔
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const META_CONFIG: &str = "backup_config";
+const DEFAULT_INTERVAL_HOURS: u32 = 24;
+const DEFAULT_KEEP: u32 = 7;
+const MAX_INTERVAL_HOURS: u32 = 30 * 24;
+const MAX_KEEP: u32 = 100;
+/// 校验备份时最多报告的问题条数
+const MAX_VERIFY_PROBLEMS: i64 = 5;
+
+/// 同一时刻只生成一份快照，避免定时备份与手动备份、还原前快照同时写
+static BACKUP_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 定时备份设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// 两次自动备份的间隔（小时），1–720
+    pub interval_hours: u32,
+    /// 每种备份各保留的份数，1–100
+    pub keep: u32,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: DEFAULT_INTERVAL_HOURS,
+            keep: DEFAULT_KEEP,
+        }
+    }
+}
+
+/// 当前设置；从未设置过时为默认值（每天一次，保留 7 份）
+pub async fn get_settings(pool: &sqlx::SqlitePool) -> Result<BackupSettings, String> {
+    let raw = meta_get(pool, META_CONFIG)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(raw
+        .and_then(|r| serde_json::from_str(&r).ok())
+        .unwrap_or_default())
+}
+
+/// 保存设置，并按新的保留份数立即清理多余的备份
+pub async fn set_settings(
+    pool: &sqlx::SqlitePool,
+    backup_dir: &Path,
+    settings: BackupSettings,
+) -> Result<BackupSettings, String> {
+    if !(1..=MAX_INTERVAL_HOURS).contains(&settings.interval_hours)
+        || !(1..=MAX_KEEP).contains(&settings.keep)
+    {
+        return Err(t(Msg::InvalidBackupSettings).to_string());
+    }
+    let raw = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    meta_set(pool, META_CONFIG, Some(&raw))
+        .await
+        .map_err(|e| e.to_string())?;
+    prune(backup_dir, settings.keep);
+    Ok(settings)
+}
+
+/// 立即生成一份快照并按保留份数轮转；kind 为 auto | manual | pre-restore，决定文件名前缀
+pub async fn create_backup(
+    pool: &sqlx::SqlitePool,
+    backup_dir: &Path,
+    kind: &str,
+) -> Result<BackupFile, String> {
+    let _guard = BACKUP_LOCK.lock().await;
+    std::fs::create_dir_all(backup_dir).map_err(|e| e.to_string())?;
+    let name = recovery::backup_file_name(kind);
+    let path = backup_dir.join(&name);
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| tf(Msg::SnapshotFailed, &[&e]))?;
+    let keep = get_settings(pool).await.map_or(DEFAULT_KEEP, |s| s.keep);
+    prune(backup_dir, keep);
+    recovery::list_backups(backup_dir)
+        .into_iter()
+        .find(|b| b.file_name == name)
+        .ok_or_else(|| t(Msg::BackupNotFound).to_string())
+}
+
+/// 每种备份（auto / manual / pre-restore / pre-migrate）各只保留最近 keep 份，返回删除的份数。
+/// 文件名不是本程序生成的（kind 为 other）不删除
+pub fn prune(backup_dir: &Path, keep: u32) -> usize {
+    let mut seen: HashMap<&str, u32> = HashMap::new();
+    let mut removed = 0;
+    for old in recovery::list_backups(backup_dir) {
+        if old.kind == "other" {
+            continue;
+        }
+        let count = seen.entry(old.kind).or_default();
+        *count += 1;
+        if *count <= keep {
+            continue;
+        }
+        match std::fs::remove_file(&old.path) {
+            Ok(()) => removed += 1,
+            Err(e) => eprintln!("prune backup {}: {}", old.path.display(), e),
+        }
+    }
+    removed
+}
+
+/// 以只读方式打开备份并做快速完整性检查，确认它是一个可用的任务库
+pub async fn verify_backup(path: &Path) -> Result<(), String> {
+    let opts = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true);
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(opts)
+        .await
+        .map_err(|e| tf(Msg::BackupCorrupt, &[&e]))?;
+    let checked = async {
+        let problems = store::integrity_problems(&pool, false, MAX_VERIFY_PROBLEMS)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !problems.is_empty() {
+            return Err(problems.join("; "));
+        }
+        let has_todos: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'todos'",
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if has_todos == 0 {
+            return Err(t(Msg::BackupNoTodosTable).to_string());
+        }
+        Ok(())
+    }
+    .await;
+    pool.close().await;
+    checked.map_err(|e| tf(Msg::BackupCorrupt, &[&e]))
+}
+
+/// 最近一份能通过校验的备份，供数据库损坏时恢复。还原前快照是用户放弃的那份数据，不作为候选；
+/// 打不开或检查不通过的备份跳过
+pub async fn latest_usable_backup(backup_dir: &Path) -> Option<BackupFile> {
+    for backup in recovery::list_backups(backup_dir) {
+        if backup.kind == "pre-restore" {
+            continue;
+        }
+        match verify_backup(&backup.path).await {
+            Ok(()) => return Some(backup),
+            Err(e) => eprintln!("skip backup {}: {}", backup.file_name, e),
+        }
+    }
+    None
+}
+
+/// 还原的准备工作，全部在关闭连接池之前完成
+pub struct PreparedRestore {
+    /// 已复制到数据库旁的备份，关闭连接池后先交给 carry_change_seq，再交给 recovery::swap_in
+    pub staged: PathBuf,
+    /// 还原前的安全快照，还原结果不对时可以再还原回去
+    pub safety: BackupFile,
+}
+
+/// 按文件名找到 backups/ 中的备份，校验后先把它复制到数据库旁，再给当前数据库做一份安全快照。
+/// 先复制是因为生成快照会按保留份数轮转，要还原的恰好是最旧的一份还原前快照时会被删掉。
+/// 文件名必须是 list_backups 返回的名称之一，不接受路径
+pub async fn prepare_restore(
+    pool: &sqlx::SqlitePool,
+    db_path: &Path,
+    name: &str,
+) -> Result<PreparedRestore, String> {
+    let backup_dir = recovery::backup_dir(db_path);
+    let backup = recovery::list_backups(&backup_dir)
+        .into_iter()
+        .find(|b| b.file_name == name)
+        .ok_or_else(|| t(Msg::BackupNotFound).to_string())?;
+    verify_backup(&backup.path).await?;
+    let staged = recovery::stage_copy(db_path, &backup.path)?;
+    match create_backup(pool, &backup_dir, "pre-restore").await {
+        Ok(safety) => Ok(PreparedRestore { staged, safety }),
+        Err(e) => {
+            let _ = std::fs::remove_file(&staged);
+            Err(e)
+        }
+    }
+}
+
+/// 只读打开数据库，读出本机发出过的最大同步序号：change_log 的 AUTOINCREMENT 计数，缺失时退回 MAX(seq)
+async fn change_seq(path: &Path) -> Result<i64, sqlx::Error> {
+    let opts = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true);
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(opts)
+        .await?;
+    let seq = sqlx::query_scalar(
+        "SELECT MAX(COALESCE((SELECT seq FROM sqlite_sequence WHERE name = 'change_log'), 0), \
+         COALESCE((SELECT MAX(seq) FROM change_log), 0))",
+    )
+    .fetch_one(&pool)
+    .await;
+    pool.close().await;
+    seq
+}
+
+/// 换入备份前调用：备份中 change_log 的序号计数停在备份当时，直接换入的话之后的新变更会重用
+/// 对端、共享目录与 WebDAV 游标已经越过的序号而被跳过（WebDAV 的分页文件名也会重名）。
+/// 把 staged 的计数推到当前数据库与各备份中的最大值，新变更从更大的序号继续编号。
+/// 读不出的文件（如已损坏的数据库）跳过；须在连接池关闭后调用，此后当前数据库不会再有新变更
+pub async fn carry_change_seq(db_path: &Path, staged: &Path) -> Result<(), String> {
+    let backups = recovery::list_backups(&recovery::backup_dir(db_path));
+    let mut high = 0;
+    for path in std::iter::once(db_path).chain(backups.iter().map(|b| b.path.as_path())) {
+        match change_seq(path).await {
+            Ok(seq) => high = high.max(seq),
+            Err(e) => eprintln!("carry_change_seq: skip {}: {}", path.display(), e),
+        }
+    }
+    let opts = sqlx::sqlite::SqliteConnectOptions::new().filename(staged);
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(opts)
+        .await
+        .map_err(|e| tf(Msg::CopyBackupFailed, &[&e]))?;
+    let carried = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO sqlite_sequence (name, seq) SELECT 'change_log', 0 \
+             WHERE NOT EXISTS (SELECT 1 FROM sqlite_sequence WHERE name = 'change_log')",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE sqlite_sequence SET seq = MAX(seq, ?) WHERE name = 'change_log'")
+            .bind(high)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+    pool.close().await;
+    carried.map_err(|e| tf(Msg::CopyBackupFailed, &[&e]))
+}
+
+/// 到了间隔就生成一份自动备份；最近一份自动备份的时间取自文件本身，重启与还原后依然准确。
+/// 未到间隔时也按保留份数轮转一次，启动时迁移生成的 pre-migrate 备份由此清理
+async fn backup_if_due(pool: &sqlx::SqlitePool, backup_dir: &Path) -> Result<(), String> {
+    let settings = get_settings(pool).await?;
+    let interval_ms = i64::from(settings.interval_hours) * 60 * 60 * 1000;
+    let last = recovery::list_backups(backup_dir)
+        .into_iter()
+        .find(|b| b.kind == "auto")
+        .map(|b| b.modified_at);
+    let due = !last.is_some_and(|at| now_millis() as i64 - at < interval_ms);
+    if settings.enabled && due {
+        create_backup(pool, backup_dir, "auto").await?;
+    } else {
+        prune(backup_dir, settings.keep);
+    }
+    Ok(())
+}
+
+/// 后台定时备份，随应用生命周期运行；启动时若已到间隔会立即备份一次
+pub async fn run_backup_loop(pool: sqlx::SqlitePool, backup_dir: PathBuf) {
+    let mut ticker = tokio::time::interval(BACKUP_CHECK_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = backup_if_due(&pool, &backup_dir).await {
+            eprintln!("backup: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按给定的修改时间写一个假备份；list_backups 按文件修改时间排序
+    fn touch(dir: &Path, name: &str, age_secs: u64) {
+        let path = dir.join(name);
+        let file = std::fs::File::create(&path).unwrap();
+        let modified = std::time::SystemTime::now() - Duration::from_secs(age_secs);
+        file.set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_of_every_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        for (i, prefix) in [
+            "todos-auto-",
+            "todos-manual-",
+            "todos-pre-restore-",
+            "todos-pre-migrate-v3-",
+        ]
+        .iter()
+        .enumerate()
+        {
+            for age in 0..4u64 {
+                touch(dir.path(), &format!("{}{}{}.db", prefix, i, age), 100 + age);
+            }
+        }
+        touch(dir.path(), "hand-made.db", 1000);
+
+        assert_eq!(prune(dir.path(), 2), 8);
+        let mut left: Vec<String> = recovery::list_backups(dir.path())
+            .into_iter()
+            .map(|b| b.file_name)
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "hand-made.db",
+                "todos-auto-00.db",
+                "todos-auto-01.db",
+                "todos-manual-10.db",
+                "todos-manual-11.db",
+                "todos-pre-migrate-v3-30.db",
+                "todos-pre-migrate-v3-31.db",
+                "todos-pre-restore-20.db",
+                "todos-pre-restore-21.db",
+            ]
+        );
+    }
+
+    /// 在 backups/ 中生成一份真实快照，改名为 name 并设定修改时间
+    async fn snapshot(pool: &sqlx::SqlitePool, dir: &Path, name: &str, age_secs: u64) {
+        let made = create_backup(pool, dir, "manual").await.unwrap();
+        let path = dir.join(name);
+        std::fs::rename(&made.path, &path).unwrap();
+        let modified = std::time::SystemTime::now() - Duration::from_secs(age_secs);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn latest_usable_backup_skips_pre_restore_and_corrupt_files() {
+        let data = tempfile::tempdir().unwrap();
+        let pool = store::open_database(&data.path().join("todos.db"))
+            .await
+            .unwrap();
+        let dir = data.path().join(store::DB_BACKUP_DIRNAME);
+        assert!(latest_usable_backup(&dir).await.is_none());
+
+        snapshot(&pool, &dir, "todos-auto-1.db", 300).await;
+        snapshot(&pool, &dir, "todos-pre-restore-2.db", 10).await;
+        // 比可用的备份新，但不是数据库 / 内容被截断
+        std::fs::write(dir.join("todos-manual-3.db"), "not a database").unwrap();
+        let valid = std::fs::read(dir.join("todos-auto-1.db")).unwrap();
+        std::fs::write(dir.join("todos-auto-4.db"), &valid[..valid.len() / 2]).unwrap();
+        for name in ["todos-manual-3.db", "todos-auto-4.db"] {
+            std::fs::File::options()
+                .write(true)
+                .open(dir.join(name))
+                .unwrap()
+                .set_modified(std::time::SystemTime::now() - Duration::from_secs(20))
+                .unwrap();
+        }
+
+        let found = latest_usable_backup(&dir).await.unwrap();
+        assert_eq!(found.file_name, "todos-auto-1.db");
+    }
+
+    async fn add(pool: &sqlx::SqlitePool, id: &str) {
+        let new = xy_todo_core::validation::NewTodo {
+            content: id.to_string(),
+            status: "pending",
+            start_time: None,
+            end_time: None,
+            importance: "normal",
+            group_id: None,
+        };
+        store::insert_todo(pool, id, &new).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn changes_after_a_restore_still_reach_a_synced_peer() {
+        use crate::events::NoopEventSink;
+        use crate::sync::{load_changes, merge_changes};
+        use xy_todo_core::store::{fetch_one_todo, SqliteStore};
+
+        let data = tempfile::tempdir().unwrap();
+        let db_path = data.path().join("todos.db");
+        let pool = store::open_database(&db_path).await.unwrap();
+        let dir = data.path().join(store::DB_BACKUP_DIRNAME);
+        add(&pool, "t1").await;
+        let backup = create_backup(&pool, &dir, "manual").await.unwrap();
+        add(&pool, "t2").await;
+        add(&pool, "t3").await;
+
+        // 对端在还原前已同步到最新，游标越过了 t2、t3 的序号
+        let peer = SqliteStore::in_memory().await.unwrap();
+        let set = load_changes(&pool, 0, 500).await.unwrap();
+        merge_changes(&peer, &NoopEventSink, &set).await.unwrap();
+        let cursor = set.cursor;
+
+        let prepared = prepare_restore(&pool, &db_path, &backup.file_name)
+            .await
+            .unwrap();
+        pool.close().await;
+        carry_change_seq(&db_path, &prepared.staged).await.unwrap();
+        recovery::swap_in(&db_path, &prepared.staged).unwrap();
+        let pool = store::open_database(&db_path).await.unwrap();
+        assert!(fetch_one_todo(&pool, "t2").await.unwrap().is_none());
+
+        add(&pool, "t4").await;
+        let set = load_changes(&pool, cursor, 500).await.unwrap();
+        assert!(set.cursor > cursor);
+        assert!(set.todos.iter().any(|t| t.id == "t4"));
+        merge_changes(&peer, &NoopEventSink, &set).await.unwrap();
+        assert!(fetch_one_todo(peer.pool(), "t4").await.unwrap().is_some());
+    }
+}
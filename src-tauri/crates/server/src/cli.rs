@@ -0,0 +1,921 @@
+//! 命令行模式：`douko-todo add/list/done/edit/rm/groups`，`--headless` 以无界面模式运行服务，
+//! 不带子命令时照常启动图形界面。不含图形界面的 `douko-todo-server` 接受同样的子命令，不带子命令时直接运行服务。
+//! 默认直接读写本机应用数据目录下的 todos.db（与桌面端同一份数据，打开时同样执行迁移）；
+//! 本机处于远程模式时改为请求主机，也可用 --api 指定任意实例的地址、--db 指定数据库文件。
+//! 输出默认为表格，--json 输出与接口相同结构的 JSON，便于脚本处理。
+//! 直接读写数据库时不经过运行中的应用：变更照常记入 change_log（由触发器维护，同步能取到），
+//! 但不会向桌面端推送 todos-changed，界面要到下次重新拉取时才看到；需要实时刷新时用 --api 走接口。
+
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+use uuid::Uuid;
+use xy_todo_core::i18n::{self, t, tf, Msg};
+use xy_todo_core::store::{SqliteStore, TodoStore, TodoUpdate};
+use xy_todo_core::{validation, Todo};
+
+use crate::client::{ClientError, CreateTodoBody, GroupItem, TodoClient, UpdateTodoBody};
+use crate::error::ApiError;
+use crate::server::ServerConfig;
+use crate::{
+    default_app_data_dir, remote, HeadlessOptions, APP_SETTINGS_FILENAME, DB_FILENAME, DEFAULT_PORT,
+};
+
+/// 表格中显示的 id 长度；命令中可以只输入 id 的前几位
+const SHORT_ID_LEN: usize = 8;
+
+#[derive(Parser)]
+#[command(
+    name = "douko-todo",
+    version,
+    about = "Douko Todo 命令行，不带子命令时启动图形界面"
+)]
+struct Cli {
+    /// 输出 JSON 而不是表格
+    #[arg(long, global = true)]
+    json: bool,
+    /// 通过接口操作运行中的实例，如 http://192.168.1.10:8080
+    #[arg(long, global = true, env = "DOUKO_TODO_API", conflicts_with = "db")]
+    api: Option<String>,
+    /// 接口访问令牌（远程模式配对获得），配合 --api 使用
+    #[arg(long, global = true, env = "DOUKO_TODO_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// 直接读写指定的数据库文件，默认为应用数据目录下的 todos.db
+    #[arg(long, global = true)]
+    db: Option<PathBuf>,
+    /// 应用数据目录（todos.db、设置与备份），默认与桌面端相同
+    #[arg(long, global = true, env = "DOUKO_TODO_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    /// 不打开窗口，只运行数据库、局域网接口与 /mobile 页面，适合作为系统服务
+    #[arg(long)]
+    headless: bool,
+    /// 无界面模式监听的端口
+    #[arg(long, env = "DOUKO_TODO_PORT", default_value_t = DEFAULT_PORT, requires = "headless")]
+    port: u16,
+    /// 无界面模式下前端构建产物（dist）所在目录，/mobile 页面从这里读取
+    #[arg(long, env = "DOUKO_TODO_STATIC_DIR", requires = "headless")]
+    static_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 新增任务
+    Add(AddArgs),
+    /// 列出任务
+    #[command(visible_alias = "ls")]
+    List(ListArgs),
+    /// 将任务标记为已完成
+    Done {
+        /// 任务 id（可只输入前几位）
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// 修改任务
+    Edit(EditArgs),
+    /// 删除任务
+    Rm {
+        /// 任务 id（可只输入前几位）
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// 列出分组
+    Groups,
+}
+
+#[derive(Args)]
+struct AddArgs {
+    /// 任务内容
+    content: String,
+    /// 开始时间，YYYY-MM-DD 或 YYYY-MM-DD HH:MM
+    #[arg(long)]
+    start: Option<String>,
+    /// 截止时间，YYYY-MM-DD（当天结束）或 YYYY-MM-DD HH:MM
+    #[arg(long)]
+    due: Option<String>,
+    /// 标记为重要
+    #[arg(long, conflicts_with = "urgent")]
+    important: bool,
+    /// 标记为紧急
+    #[arg(long)]
+    urgent: bool,
+    /// 分组名称或 id，分组不存在时自动新建
+    #[arg(long)]
+    group: Option<String>,
+}
+
+#[derive(Args)]
+struct ListArgs {
+    /// 排序规则
+    #[arg(long, default_value = "comprehensive", value_parser = ["comprehensive", "importance", "deadline"])]
+    sort: String,
+    /// 只列出某个状态的任务
+    #[arg(long, value_parser = ["pending", "completed"])]
+    status: Option<String>,
+    /// 只列出某个分组（名称或 id）的任务
+    #[arg(long)]
+    group: Option<String>,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    /// 任务 id（可只输入前几位）
+    id: String,
+    /// 新的任务内容
+    #[arg(long)]
+    content: Option<String>,
+    /// 开始时间，YYYY-MM-DD 或 YYYY-MM-DD HH:MM
+    #[arg(long, conflicts_with = "clear_start")]
+    start: Option<String>,
+    /// 清空开始时间
+    #[arg(long)]
+    clear_start: bool,
+    /// 截止时间，YYYY-MM-DD（当天结束）或 YYYY-MM-DD HH:MM
+    #[arg(long, conflicts_with = "clear_due")]
+    due: Option<String>,
+    /// 清空截止时间
+    #[arg(long)]
+    clear_due: bool,
+    /// 重要性
+    #[arg(long, value_parser = ["normal", "important", "urgent"])]
+    importance: Option<String>,
+    /// 状态，可用 pending 重新打开已完成的任务
+    #[arg(long, value_parser = ["pending", "completed"])]
+    status: Option<String>,
+    /// 移到分组（名称或 id），分组不存在时自动新建
+    #[arg(long, conflicts_with = "ungroup")]
+    group: Option<String>,
+    /// 移出分组
+    #[arg(long)]
+    ungroup: bool,
+}
+
+/// 按命令行参数执行；返回 None 表示没有子命令，应启动图形界面，否则返回进程退出码
+pub fn run_from_env() -> Option<i32> {
+    run_args(std::env::args_os().collect())
+}
+
+/// douko-todo-server 的入口：没有图形界面，不带子命令时按 --headless 运行服务
+pub fn run_server_from_env() -> i32 {
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let has_subcommand = args
+        .iter()
+        .skip(1)
+        .any(|a| Cli::is_subcommand(&a.to_string_lossy()));
+    if !has_subcommand && !args.iter().any(|a| a == "--headless") {
+        args.insert(1.min(args.len()), "--headless".into());
+    }
+    run_args(args).unwrap_or(0)
+}
+
+fn run_args(args: Vec<std::ffi::OsString>) -> Option<i32> {
+    if args.len() <= 1 {
+        return None;
+    }
+    let cli = match Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            use clap::error::ErrorKind;
+            // 系统或桌面环境启动时可能附带无关参数，只有看起来像命令行调用时才报错
+            let is_cli = matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion)
+                || args
+                    .iter()
+                    .skip(1)
+                    .any(|a| Cli::is_own_arg(&a.to_string_lossy()));
+            if !is_cli {
+                return None;
+            }
+            attach_parent_console();
+            let _ = e.print();
+            return Some(e.exit_code());
+        }
+    };
+    if cli.headless {
+        attach_parent_console();
+        return Some(run_headless(&cli));
+    }
+    let command = cli.command.as_ref()?;
+    attach_parent_console();
+    let app_data = cli.data_dir.clone().or_else(default_app_data_dir);
+    if let Some(ref dir) = app_data {
+        i18n::load_app_locale(&dir.join(APP_SETTINGS_FILENAME));
+    }
+    let rt = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("{}", tf(Msg::CliRuntimeFailed, &[&e]));
+            return Some(1);
+        }
+    };
+    let result = rt.block_on(async {
+        let backend = Backend::open(&cli, app_data).await?;
+        execute(&backend, command, cli.json, &mut std::io::stdout().lock()).await
+    });
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+fn run_headless(cli: &Cli) -> i32 {
+    if cli.command.is_some() {
+        eprintln!("--headless cannot be combined with a subcommand");
+        return 2;
+    }
+    let Some(data_dir) = cli.data_dir.clone().or_else(default_app_data_dir) else {
+        eprintln!("{}", t(Msg::NoAppDataDir));
+        return 1;
+    };
+    let mut server = ServerConfig {
+        port: cli.port,
+        ..Default::default()
+    };
+    if let Some(ref dir) = cli.static_dir {
+        server.static_dir = dir.clone();
+    }
+    match crate::run_headless(HeadlessOptions { data_dir, server }) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+impl Cli {
+    /// 是否为本程序的子命令或长选项（如 list、--headless、--port=9000）
+    fn is_own_arg(arg: &str) -> bool {
+        use clap::CommandFactory;
+        let command = Cli::command();
+        if let Some(long) = arg.strip_prefix("--") {
+            let long = long.split('=').next().unwrap_or(long);
+            return command.get_arguments().any(|a| a.get_long() == Some(long));
+        }
+        Self::is_subcommand(arg)
+    }
+
+    fn is_subcommand(arg: &str) -> bool {
+        use clap::CommandFactory;
+        Cli::command()
+            .get_subcommands()
+            .any(|c| c.get_name() == arg || c.get_all_aliases().any(|a| a == arg))
+    }
+}
+
+/// Windows 发布版是 GUI 子系统程序，没有控制台；从终端运行时挂到父进程的控制台上以便输出
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+/// 数据来源：本机数据库或运行中实例的接口
+enum Backend {
+    Local(SqliteStore),
+    Api(TodoClient),
+}
+
+fn api_message(e: ApiError) -> String {
+    e.message()
+}
+
+fn client_message(e: ClientError) -> String {
+    match e {
+        ClientError::Api { ref message, .. } if !message.is_empty() => message.clone(),
+        e => e.to_string(),
+    }
+}
+
+impl Backend {
+    /// --api 优先，其次 --db；都未指定时本机处于远程模式则请求主机，否则打开本机数据库
+    async fn open(cli: &Cli, app_data: Option<PathBuf>) -> Result<Self, String> {
+        let locale = i18n::app_locale().tag();
+        if let Some(ref url) = cli.api {
+            let url = remote::normalize_url(url, remote::UrlKind::AppServer, Msg::InvalidHostUrl)?;
+            let mut client = TodoClient::new(url)
+                .map_err(client_message)?
+                .with_locale(locale);
+            if let Some(ref token) = cli.token {
+                client = client.with_token(token);
+            }
+            return Ok(Backend::Api(client));
+        }
+        let db_path = match (&cli.db, &app_data) {
+            (Some(path), _) => path.clone(),
+            (None, Some(dir)) => {
+                if let Some(config) = remote::load_config(dir) {
+                    let client = TodoClient::new(config.url)
+                        .map_err(client_message)?
+                        .with_token(config.token)
+                        .with_locale(locale);
+                    return Ok(Backend::Api(client));
+                }
+                dir.join(DB_FILENAME)
+            }
+            (None, None) => return Err(t(Msg::NoAppDataDir).to_string()),
+        };
+        let store = SqliteStore::open(&db_path)
+            .await
+            .map_err(|e| tf(Msg::CliOpenDbFailed, &[&e]))?;
+        Ok(Backend::Local(store))
+    }
+
+    async fn todos(&self, sort: &str) -> Result<Vec<Todo>, String> {
+        match self {
+            Backend::Local(store) => store
+                .list_todos(sort)
+                .await
+                .map_err(|e| api_message(ApiError::db("cli list", Msg::ListTodosFailed, e))),
+            Backend::Api(client) => client.list_todos(Some(sort)).await.map_err(client_message),
+        }
+    }
+
+    async fn groups(&self) -> Result<Vec<GroupItem>, String> {
+        match self {
+            Backend::Local(store) => store
+                .list_groups()
+                .await
+                .map_err(|e| api_message(ApiError::db("cli groups", Msg::ListGroupsFailed, e))),
+            Backend::Api(client) => client.list_groups().await.map_err(client_message),
+        }
+    }
+
+    async fn create(&self, body: &CreateTodoBody) -> Result<Todo, String> {
+        match self {
+            Backend::Local(store) => {
+                let new = validation::new_todo(body).map_err(|e| api_message(e.into()))?;
+                store
+                    .create_todo(&Uuid::new_v4().to_string(), &new)
+                    .await
+                    .map_err(|e| api_message(ApiError::db("cli add", Msg::CreateTodoFailed, e)))
+            }
+            Backend::Api(client) => client.create_todo(body).await.map_err(client_message),
+        }
+    }
+
+    async fn update(&self, id: &str, body: &UpdateTodoBody) -> Result<Todo, String> {
+        match self {
+            Backend::Local(store) => {
+                let update = store
+                    .update_todo(id, body)
+                    .await
+                    .map_err(|e| api_message(ApiError::db("cli edit", Msg::UpdateTodoFailed, e)))?;
+                match update {
+                    TodoUpdate::Updated(todo) => Ok(todo),
+                    TodoUpdate::NotFound => Err(api_message(ApiError::TodoNotFound)),
+                    TodoUpdate::Invalid(errors) => Err(api_message(errors.into())),
+                }
+            }
+            Backend::Api(client) => client.update_todo(id, body).await.map_err(client_message),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        match self {
+            Backend::Local(store) => match store.delete_todo(id).await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(api_message(ApiError::TodoNotFound)),
+                Err(e) => Err(api_message(ApiError::db(
+                    "cli rm",
+                    Msg::DeleteTodoFailed,
+                    e,
+                ))),
+            },
+            Backend::Api(client) => client.delete_todo(id).await.map_err(client_message),
+        }
+    }
+
+    /// 按完整 id 或唯一的 id 前缀找到各个任务；任务列表只加载一次
+    async fn resolve_all(&self, prefixes: &[String]) -> Result<Vec<Todo>, String> {
+        let todos = self.todos("comprehensive").await?;
+        prefixes
+            .iter()
+            .map(|prefix| resolve(&todos, prefix).cloned())
+            .collect()
+    }
+
+    /// 新增或修改任务时指定的分组不存在则按该名称新建
+    async fn resolve_or_create_group(&self, name: &str) -> Result<String, String> {
+        if let Ok(id) = resolve_group(&self.groups().await?, name) {
+            return Ok(id);
+        }
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(api_message(ApiError::invalid("group", Msg::GroupNameEmpty)));
+        }
+        match self {
+            Backend::Local(store) => {
+                let id = Uuid::new_v4().to_string();
+                store.create_group(&id, name).await.map_err(|e| {
+                    api_message(ApiError::db("cli group", Msg::CreateGroupFailed, e))
+                })?;
+                Ok(id)
+            }
+            Backend::Api(client) => client
+                .create_group(name)
+                .await
+                .map(|g| g.id)
+                .map_err(client_message),
+        }
+    }
+}
+
+/// 按完整 id 或唯一的 id 前缀找到任务；完整 id 优先于以它为前缀的其他 id
+fn resolve<'a>(todos: &'a [Todo], prefix: &str) -> Result<&'a Todo, String> {
+    let prefix = prefix.trim();
+    if let Some(todo) = todos.iter().find(|t| t.id == prefix) {
+        return Ok(todo);
+    }
+    let mut matches = todos
+        .iter()
+        .filter(|t| !prefix.is_empty() && t.id.starts_with(prefix));
+    match (matches.next(), matches.next()) {
+        (None, _) => Err(tf(Msg::CliTodoNotFound, &[&prefix])),
+        (Some(todo), None) => Ok(todo),
+        (Some(_), Some(_)) => Err(tf(Msg::CliAmbiguousId, &[&prefix])),
+    }
+}
+
+/// 按名称或 id 找到分组，返回分组 id
+fn resolve_group(groups: &[GroupItem], name: &str) -> Result<String, String> {
+    let name = name.trim();
+    groups
+        .iter()
+        .find(|g| g.name == name || g.id == name)
+        .map(|g| g.id.clone())
+        .ok_or_else(|| tf(Msg::CliGroupNotFound, &[&name]))
+}
+
+/// 执行子命令，结果（表格或 --json 的 JSON）写入 out
+async fn execute(
+    backend: &Backend,
+    command: &Command,
+    json: bool,
+    out: &mut impl std::io::Write,
+) -> Result<(), String> {
+    match command {
+        Command::Add(args) => {
+            let importance = if args.urgent {
+                "urgent"
+            } else if args.important {
+                "important"
+            } else {
+                "normal"
+            };
+            let group_id = match args.group {
+                Some(ref g) => Some(backend.resolve_or_create_group(g).await?),
+                None => None,
+            };
+            let body = CreateTodoBody {
+                content: args.content.clone(),
+                start_time: args
+                    .start
+                    .as_deref()
+                    .map(|s| parse_time(s, false))
+                    .transpose()?,
+                end_time: args
+                    .due
+                    .as_deref()
+                    .map(|s| parse_time(s, true))
+                    .transpose()?,
+                importance: Some(importance.to_string()),
+                group_id,
+                ..Default::default()
+            };
+            let todo = backend.create(&body).await?;
+            print_todo_result(out, &todo, Msg::CliAdded, json);
+        }
+        Command::List(args) => {
+            let groups = backend.groups().await?;
+            let group_id = match args.group {
+                Some(ref g) => Some(resolve_group(&groups, g)?),
+                None => None,
+            };
+            let todos: Vec<Todo> = backend
+                .todos(&args.sort)
+                .await?
+                .into_iter()
+                .filter(|t| args.status.iter().all(|s| t.status == *s))
+                .filter(|t| group_id.is_none() || t.group_id == group_id)
+                .collect();
+            if json {
+                print_json(out, &todos);
+            } else {
+                print_todos(out, &todos, &groups);
+            }
+        }
+        Command::Done { ids } => {
+            let body = UpdateTodoBody {
+                status: Some("completed".to_string()),
+                ..Default::default()
+            };
+            let mut done = Vec::new();
+            for todo in backend.resolve_all(ids).await? {
+                done.push(backend.update(&todo.id, &body).await?);
+            }
+            print_todos_result(out, &done, Msg::CliCompleted, json);
+        }
+        Command::Edit(args) => {
+            let todo = backend
+                .resolve_all(std::slice::from_ref(&args.id))
+                .await?
+                .remove(0);
+            let group_id = if args.ungroup {
+                Some(None)
+            } else {
+                match args.group {
+                    Some(ref g) => Some(Some(backend.resolve_or_create_group(g).await?)),
+                    None => None,
+                }
+            };
+            let start_time = if args.clear_start {
+                Some(None)
+            } else {
+                args.start
+                    .as_deref()
+                    .map(|s| parse_time(s, false).map(Some))
+                    .transpose()?
+            };
+            let end_time = if args.clear_due {
+                Some(None)
+            } else {
+                args.due
+                    .as_deref()
+                    .map(|s| parse_time(s, true).map(Some))
+                    .transpose()?
+            };
+            let body = UpdateTodoBody {
+                content: args.content.clone(),
+                status: args.status.clone(),
+                start_time,
+                end_time,
+                importance: args.importance.clone(),
+                group_id,
+            };
+            if body.content.is_none()
+                && body.status.is_none()
+                && body.start_time.is_none()
+                && body.end_time.is_none()
+                && body.importance.is_none()
+                && body.group_id.is_none()
+            {
+                return Err(t(Msg::CliNothingToEdit).to_string());
+            }
+            let todo = backend.update(&todo.id, &body).await?;
+            print_todo_result(out, &todo, Msg::CliUpdated, json);
+        }
+        Command::Rm { ids } => {
+            let mut removed = Vec::new();
+            for todo in backend.resolve_all(ids).await? {
+                backend.delete(&todo.id).await?;
+                removed.push(todo);
+            }
+            if json {
+                let ids: Vec<&str> = removed.iter().map(|t| t.id.as_str()).collect();
+                print_json(out, &serde_json::json!({ "deleted": ids }));
+            } else {
+                for todo in &removed {
+                    emit(out, tf(Msg::CliDeleted, &[&todo_label(todo)]));
+                }
+            }
+        }
+        Command::Groups => {
+            let groups = backend.groups().await?;
+            if json {
+                print_json(out, &groups);
+            } else if groups.is_empty() {
+                emit(out, t(Msg::CliNoGroups));
+            } else {
+                let rows = groups
+                    .iter()
+                    .map(|g| vec![short_id(&g.id).to_string(), g.name.clone()])
+                    .collect();
+                print_table(out, &[t(Msg::CliColId), t(Msg::CliColName)], rows);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 解析命令行输入的时间，转为前端日期选择器使用的本地时间格式 YYYY-MM-DDTHH:mm:ss；
+/// 只给日期时，截止时间取当天结束，开始时间取当天开始。RFC3339 原样保留。
+fn parse_time(raw: &str, end_of_day: bool) -> Result<String, String> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+    let raw = raw.trim();
+    if DateTime::parse_from_rfc3339(raw).is_ok() {
+        return Ok(raw.to_string());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        let time = if end_of_day { "23:59:59" } else { "00:00:00" };
+        return Ok(format!("{}T{}", date.format("%Y-%m-%d"), time));
+    }
+    let normalized = raw.replacen(' ', "T", 1);
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(&normalized, format) {
+            return Ok(t.format("%Y-%m-%dT%H:%M:%S").to_string());
+        }
+    }
+    Err(tf(Msg::CliInvalidDate, &[&raw]))
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..SHORT_ID_LEN).unwrap_or(id)
+}
+
+fn todo_label(todo: &Todo) -> String {
+    format!("{} {}", short_id(&todo.id), todo.content)
+}
+
+/// 时间只显示到分钟
+fn display_time(raw: Option<&str>) -> String {
+    let Some(raw) = raw else { return String::new() };
+    let shown = raw.replacen('T', " ", 1);
+    shown.get(..16).unwrap_or(&shown).to_string()
+}
+
+fn display_status(status: &str) -> &'static str {
+    match status {
+        "completed" => t(Msg::CliStatusCompleted),
+        _ => t(Msg::CliStatusPending),
+    }
+}
+
+fn display_importance(importance: &str) -> &'static str {
+    match importance {
+        "urgent" => t(Msg::CliImportanceUrgent),
+        "important" => t(Msg::CliImportanceImportant),
+        _ => t(Msg::CliImportanceNormal),
+    }
+}
+
+/// 输出一行；管道被关闭（如接了 head）时静默忽略，不 panic
+fn emit(out: &mut impl std::io::Write, line: impl std::fmt::Display) {
+    let _ = writeln!(out, "{}", line);
+}
+
+fn print_json<T: serde::Serialize + ?Sized>(out: &mut impl std::io::Write, value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(s) => emit(out, s),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn print_todo_result(out: &mut impl std::io::Write, todo: &Todo, message: Msg, json: bool) {
+    if json {
+        print_json(out, todo);
+    } else {
+        emit(out, tf(message, &[&todo_label(todo)]));
+    }
+}
+
+fn print_todos_result(out: &mut impl std::io::Write, todos: &[Todo], message: Msg, json: bool) {
+    if json {
+        print_json(out, todos);
+    } else {
+        for todo in todos {
+            emit(out, tf(message, &[&todo_label(todo)]));
+        }
+    }
+}
+
+fn print_todos(out: &mut impl std::io::Write, todos: &[Todo], groups: &[GroupItem]) {
+    if todos.is_empty() {
+        emit(out, t(Msg::CliNoTodos));
+        return;
+    }
+    let rows = todos
+        .iter()
+        .map(|todo| {
+            let group = todo
+                .group_id
+                .as_deref()
+                .and_then(|id| groups.iter().find(|g| g.id == id))
+                .map(|g| g.name.clone())
+                .unwrap_or_default();
+            vec![
+                short_id(&todo.id).to_string(),
+                display_status(&todo.status).to_string(),
+                display_importance(&todo.importance).to_string(),
+                display_time(todo.end_time.as_deref()),
+                group,
+                todo.content.clone(),
+            ]
+        })
+        .collect();
+    print_table(
+        out,
+        &[
+            t(Msg::CliColId),
+            t(Msg::CliColStatus),
+            t(Msg::CliColImportance),
+            t(Msg::CliColDue),
+            t(Msg::CliColGroup),
+            t(Msg::CliColContent),
+        ],
+        rows,
+    );
+}
+
+/// 按显示宽度对齐（中日文字符占两列），最后一列不补空格
+fn print_table(out: &mut impl std::io::Write, headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.width()).collect();
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.width());
+        }
+    }
+    let mut line = |cells: Vec<&str>| {
+        let last = cells.len().saturating_sub(1);
+        let mut text = String::new();
+        for (i, cell) in cells.into_iter().enumerate() {
+            text.push_str(cell);
+            if i < last {
+                text.push_str(&" ".repeat(widths[i] - cell.width() + 2));
+            }
+        }
+        emit(out, text.trim_end());
+    };
+    line(headers.to_vec());
+    for row in &rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str) -> Todo {
+        Todo {
+            id: id.to_string(),
+            content: id.to_string(),
+            status: "pending".to_string(),
+            reminder_time: None,
+            start_time: None,
+            end_time: None,
+            importance: "normal".to_string(),
+            group_id: None,
+            sort_order: 0,
+            updated_at: 0,
+        }
+    }
+
+    /// 解析命令行并在给定的本机数据库上执行，返回标准输出的内容
+    async fn run(backend: &Backend, args: &[&str]) -> Result<String, String> {
+        let cli =
+            Cli::try_parse_from(std::iter::once("douko-todo").chain(args.iter().copied())).unwrap();
+        let mut out = Vec::new();
+        execute(backend, cli.command.as_ref().unwrap(), cli.json, &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn dates_are_expanded_to_local_times() {
+        assert_eq!(
+            parse_time("2026-03-01", true).unwrap(),
+            "2026-03-01T23:59:59"
+        );
+        assert_eq!(
+            parse_time("2026-03-01", false).unwrap(),
+            "2026-03-01T00:00:00"
+        );
+        assert_eq!(
+            parse_time(" 2026-03-01 09:30 ", true).unwrap(),
+            "2026-03-01T09:30:00"
+        );
+        assert_eq!(
+            parse_time("2026-03-01T09:30:15", false).unwrap(),
+            "2026-03-01T09:30:15"
+        );
+        assert_eq!(
+            parse_time("2026-03-01T09:30:00+08:00", true).unwrap(),
+            "2026-03-01T09:30:00+08:00"
+        );
+        for raw in ["tomorrow", "2026-13-01", "2026-02-30", "03/01/2026", ""] {
+            assert_eq!(
+                parse_time(raw, true).unwrap_err(),
+                tf(Msg::CliInvalidDate, &[&raw.trim()]),
+                "{:?}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn ids_resolve_by_unique_prefix() {
+        let todos = vec![todo("abc123"), todo("abd456"), todo("abc")];
+        assert_eq!(resolve(&todos, "abd").unwrap().id, "abd456");
+        assert_eq!(resolve(&todos, " abc1 ").unwrap().id, "abc123");
+        // 完整 id 优先，即使它同时是别的 id 的前缀
+        assert_eq!(resolve(&todos, "abc").unwrap().id, "abc");
+        assert_eq!(
+            resolve(&todos, "ab").unwrap_err(),
+            tf(Msg::CliAmbiguousId, &[&"ab"])
+        );
+        assert_eq!(
+            resolve(&todos, "zz").unwrap_err(),
+            tf(Msg::CliTodoNotFound, &[&"zz"])
+        );
+        assert_eq!(
+            resolve(&todos, "  ").unwrap_err(),
+            tf(Msg::CliTodoNotFound, &[&""])
+        );
+    }
+
+    #[test]
+    fn groups_resolve_by_name_or_id() {
+        let groups = vec![
+            GroupItem {
+                id: "g1".into(),
+                name: "工作".into(),
+            },
+            GroupItem {
+                id: "g2".into(),
+                name: "家".into(),
+            },
+        ];
+        assert_eq!(resolve_group(&groups, " 工作 ").unwrap(), "g1");
+        assert_eq!(resolve_group(&groups, "g2").unwrap(), "g2");
+        assert_eq!(
+            resolve_group(&groups, "g").unwrap_err(),
+            tf(Msg::CliGroupNotFound, &[&"g"])
+        );
+    }
+
+    #[tokio::test]
+    async fn json_output_matches_the_api_shapes() {
+        let backend = Backend::Local(SqliteStore::in_memory().await.unwrap());
+        let added: Todo = serde_json::from_str(
+            &run(
+                &backend,
+                &[
+                    "--json",
+                    "add",
+                    "写周报",
+                    "--due",
+                    "2026-03-01",
+                    "--group",
+                    "工作",
+                    "--urgent",
+                ],
+            )
+            .await
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(added.end_time.as_deref(), Some("2026-03-01T23:59:59"));
+        assert_eq!(added.importance, "urgent");
+
+        let groups: Vec<GroupItem> =
+            serde_json::from_str(&run(&backend, &["groups", "--json"]).await.unwrap()).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(added.group_id.as_deref(), Some(groups[0].id.as_str()));
+
+        let prefix = &added.id[..SHORT_ID_LEN];
+        let done: Vec<Todo> =
+            serde_json::from_str(&run(&backend, &["--json", "done", prefix]).await.unwrap())
+                .unwrap();
+        assert_eq!(done[0].status, "completed");
+
+        let listed: Vec<Todo> = serde_json::from_str(
+            &run(&backend, &["--json", "ls", "--group", "工作"])
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, added.id);
+
+        let removed: serde_json::Value =
+            serde_json::from_str(&run(&backend, &["--json", "rm", prefix]).await.unwrap()).unwrap();
+        assert_eq!(removed, serde_json::json!({ "deleted": [added.id] }));
+        assert_eq!(
+            run(&backend, &["--json", "list"]).await.unwrap().trim(),
+            "[]"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_ids_fail_without_partial_changes() {
+        let backend = Backend::Local(SqliteStore::in_memory().await.unwrap());
+        run(&backend, &["add", "a"]).await.unwrap();
+        let id = backend.todos("comprehensive").await.unwrap()[0].id.clone();
+        assert_eq!(
+            run(&backend, &["done", &id, "missing"]).await.unwrap_err(),
+            tf(Msg::CliTodoNotFound, &[&"missing"])
+        );
+        assert_eq!(
+            backend.todos("comprehensive").await.unwrap()[0].status,
+            "pending"
+        );
+    }
+}
@@ -0,0 +1,492 @@
+//! 局域网接口的 Rust 客户端，供内部工具调用 Axum 服务（/api/v1）。
+//! 请求/响应类型直接复用 server.rs 中的定义，接口变化时两端一起编译检查；
+//! 出错时返回 ClientError，服务端的错误响应解析为 ClientError::Api，可按 `code` 分支处理（错误码见 README）。
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, PercentEncode, NON_ALPHANUMERIC};
+use reqwest::{header, Method, RequestBuilder, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::fmt;
+use std::time::Duration;
+
+use crate::pairing::PairBody;
+use crate::server::{
+    CreateGroupBody, MobileUrlResponse, OkResponse, ReorderBody, ReorderGroupsBody,
+    UpdateGroupBody, API_PREFIX,
+};
+
+pub use crate::pairing::PairResponse;
+pub use crate::server::{CreateTodoBody, GroupItem, UpdateTodoBody, VersionInfo};
+pub use xy_todo_core::Todo;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// 路径片段中原样保留的字符（RFC 3986 非保留字符），其余一律转义
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// 转义放进 URL 路径的 id，避免 `/`、`?`、`#` 等改变请求的路径
+fn segment(id: &str) -> PercentEncode<'_> {
+    utf8_percent_encode(id, PATH_SEGMENT)
+}
+
+/// 校验失败时服务端逐项列出的出错字段
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// 连接失败、超时或响应体无法解析
+    Request(reqwest::Error),
+    /// 服务端返回的错误响应
+    Api {
+        status: StatusCode,
+        /// 稳定的错误码，如 validation_failed、todo_not_found、invalid_token
+        code: String,
+        message: String,
+        fields: Vec<ApiFieldError>,
+    },
+}
+
+impl ClientError {
+    /// 服务端错误码；网络错误时为 None
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => Some(code),
+            ClientError::Request(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Request(e) => write!(f, "request failed: {}", e),
+            ClientError::Api {
+                status,
+                code,
+                message,
+                ..
+            } => write!(f, "{} {}: {}", status.as_u16(), code, message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Request(e) => Some(e),
+            ClientError::Api { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Request(e)
+    }
+}
+
+/// 错误响应体；旧版本服务端可能只有 error 字段
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    error: String,
+    #[serde(default)]
+    fields: Vec<ApiFieldError>,
+}
+
+#[derive(Clone)]
+pub struct TodoClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    locale: Option<String>,
+}
+
+impl TodoClient {
+    /// base_url 为服务地址，如 `http://192.168.1.10:8080`
+    pub fn new(base_url: impl Into<String>) -> Result<Self, ClientError> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self::with_http_client(http, base_url))
+    }
+
+    /// 使用调用方配置好的 reqwest::Client（自定义超时、代理等）
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+            locale: None,
+        }
+    }
+
+    /// 远程模式下的访问令牌，之后的请求都带 `Authorization: Bearer`
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// 错误提示使用的语言（zh-CN | en | ja），作为 Accept-Language 发送
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut req = self
+            .http
+            .request(method, format!("{}{}{}", self.base_url, API_PREFIX, path));
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+        if let Some(locale) = &self.locale {
+            req = req.header(header::ACCEPT_LANGUAGE, locale);
+        }
+        req
+    }
+
+    async fn send<T: DeserializeOwned>(req: RequestBuilder) -> Result<T, ClientError> {
+        let resp = req.send().await?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp.json().await?);
+        }
+        let text = resp.text().await.unwrap_or_default();
+        let err = match serde_json::from_str::<ErrorResponse>(&text) {
+            Ok(body) => ClientError::Api {
+                status,
+                code: body.code,
+                message: body.error,
+                fields: body.fields,
+            },
+            Err(_) => ClientError::Api {
+                status,
+                code: String::new(),
+                message: text,
+                fields: Vec::new(),
+            },
+        };
+        Err(err)
+    }
+
+    async fn send_ok(req: RequestBuilder) -> Result<(), ClientError> {
+        Self::send::<OkResponse>(req).await.map(|_| ())
+    }
+
+    /// 版本握手
+    pub async fn version(&self) -> Result<VersionInfo, ClientError> {
+        Self::send(self.request(Method::GET, "/version")).await
+    }
+
+    /// 用主机上显示的配对码换取访问令牌，成功后本客户端即带上该令牌
+    pub async fn pair(&mut self, code: &str, name: &str) -> Result<PairResponse, ClientError> {
+        let body = PairBody {
+            code: code.trim().to_string(),
+            name: name.to_string(),
+        };
+        let paired: PairResponse =
+            Self::send(self.request(Method::POST, "/pair").json(&body)).await?;
+        self.token = Some(paired.token.clone());
+        Ok(paired)
+    }
+
+    /// 手机扫码地址
+    pub async fn mobile_url(&self) -> Result<String, ClientError> {
+        let resp: MobileUrlResponse = Self::send(self.request(Method::GET, "/mobile-url")).await?;
+        Ok(resp.url)
+    }
+
+    /// sort：comprehensive（默认）| importance | deadline
+    pub async fn list_todos(&self, sort: Option<&str>) -> Result<Vec<Todo>, ClientError> {
+        let mut req = self.request(Method::GET, "/todo");
+        if let Some(sort) = sort {
+            req = req.query(&[("sort", sort)]);
+        }
+        Self::send(req).await
+    }
+
+    /// 按输入联想历史任务内容
+    pub async fn content_suggestions(&self, q: &str) -> Result<Vec<String>, ClientError> {
+        Self::send(
+            self.request(Method::GET, "/todo/content-suggestions")
+                .query(&[("q", q)]),
+        )
+        .await
+    }
+
+    pub async fn create_todo(&self, body: &CreateTodoBody) -> Result<Todo, ClientError> {
+        Self::send(self.request(Method::POST, "/todo").json(body)).await
+    }
+
+    /// 带 Idempotency-Key 新增任务，重试时传同一个 key 不会重复创建
+    pub async fn create_todo_idempotent(
+        &self,
+        body: &CreateTodoBody,
+        idempotency_key: &str,
+    ) -> Result<Todo, ClientError> {
+        Self::send(
+            self.request(Method::POST, "/todo")
+                .header(IDEMPOTENCY_KEY_HEADER, idempotency_key)
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn update_todo(&self, id: &str, body: &UpdateTodoBody) -> Result<Todo, ClientError> {
+        Self::send(
+            self.request(Method::PATCH, &format!("/todo/{}", segment(id)))
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn delete_todo(&self, id: &str) -> Result<(), ClientError> {
+        Self::send_ok(self.request(Method::DELETE, &format!("/todo/{}", segment(id)))).await
+    }
+
+    /// 按给定顺序重写任务排序值
+    pub async fn reorder_todos(&self, ordered_ids: &[String]) -> Result<(), ClientError> {
+        let body = ReorderBody {
+            ordered_ids: ordered_ids.to_vec(),
+        };
+        Self::send_ok(self.request(Method::POST, "/todo/reorder").json(&body)).await
+    }
+
+    pub async fn list_groups(&self) -> Result<Vec<GroupItem>, ClientError> {
+        Self::send(self.request(Method::GET, "/groups")).await
+    }
+
+    pub async fn create_group(&self, name: &str) -> Result<GroupItem, ClientError> {
+        let body = CreateGroupBody {
+            name: name.to_string(),
+        };
+        Self::send(self.request(Method::POST, "/groups").json(&body)).await
+    }
+
+    pub async fn update_group(&self, id: &str, name: &str) -> Result<GroupItem, ClientError> {
+        let body = UpdateGroupBody {
+            name: name.to_string(),
+        };
+        Self::send(
+            self.request(Method::PATCH, &format!("/groups/{}", segment(id)))
+                .json(&body),
+        )
+        .await
+    }
+
+    /// 删除分组，其下任务变为未分组
+    pub async fn delete_group(&self, id: &str) -> Result<(), ClientError> {
+        Self::send_ok(self.request(Method::DELETE, &format!("/groups/{}", segment(id)))).await
+    }
+
+    /// 按给定顺序重写分组排序值
+    pub async fn reorder_groups(&self, ordered_ids: &[String]) -> Result<(), ClientError> {
+        let body = ReorderGroupsBody {
+            ordered_ids: ordered_ids.to_vec(),
+        };
+        Self::send_ok(self.request(Method::POST, "/groups/reorder").json(&body)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pairing::create_pairing_code;
+    use crate::server::spawn_test_server;
+    use xy_todo_core::store::{insert_group, insert_todo};
+    use xy_todo_core::validation::NewTodo;
+
+    /// 启动测试服务并完成配对，返回带令牌的客户端
+    async fn paired_client() -> (crate::AppState, TodoClient, tempfile::TempDir) {
+        let (state, base, dir) = spawn_test_server().await;
+        let code = create_pairing_code(&state.pool).await.unwrap().code;
+        let mut client = TodoClient::new(base).unwrap();
+        client.pair(&code, "test").await.unwrap();
+        (state, client, dir)
+    }
+
+    fn new_todo(content: &str) -> NewTodo {
+        NewTodo {
+            content: content.to_string(),
+            status: "pending",
+            start_time: None,
+            end_time: None,
+            importance: "normal",
+            group_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn todos_round_trip() {
+        let (_state, client, _dir) = paired_client().await;
+        assert_eq!(client.version().await.unwrap().api_version, 1);
+        let a = client
+            .create_todo(&CreateTodoBody {
+                content: "a".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let body = CreateTodoBody {
+            content: "b".into(),
+            ..Default::default()
+        };
+        let b = client.create_todo_idempotent(&body, "key-1").await.unwrap();
+        let replayed = client.create_todo_idempotent(&body, "key-1").await.unwrap();
+        assert_eq!(b.id, replayed.id);
+
+        client
+            .reorder_todos(&[b.id.clone(), a.id.clone()])
+            .await
+            .unwrap();
+        let ids: Vec<String> = client
+            .list_todos(Some("comprehensive"))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, vec![b.id.clone(), a.id.clone()]);
+
+        let updated = client
+            .update_todo(
+                &a.id,
+                &UpdateTodoBody {
+                    status: Some("completed".into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.status, "completed");
+
+        client.delete_todo(&a.id).await.unwrap();
+        let left = client.list_todos(None).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, b.id);
+    }
+
+    #[tokio::test]
+    async fn groups_round_trip() {
+        let (_state, client, _dir) = paired_client().await;
+        let work = client.create_group("工作").await.unwrap();
+        let home = client.create_group("家").await.unwrap();
+        let renamed = client.update_group(&work.id, "项目").await.unwrap();
+        assert_eq!(renamed.name, "项目");
+        client
+            .reorder_groups(&[home.id.clone(), work.id.clone()])
+            .await
+            .unwrap();
+        let names: Vec<String> = client
+            .list_groups()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|g| g.name)
+            .collect();
+        assert_eq!(names, vec!["家", "项目"]);
+        client.delete_group(&home.id).await.unwrap();
+        assert_eq!(client.list_groups().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn error_responses_map_to_api_codes() {
+        let (state, base, _dir) = spawn_test_server().await;
+        let anonymous = TodoClient::new(&base).unwrap();
+        let err = anonymous.list_todos(None).await.unwrap_err();
+        assert_eq!(err.code(), Some("missing_token"));
+        assert!(
+            matches!(err, ClientError::Api { status, .. } if status == StatusCode::UNAUTHORIZED)
+        );
+
+        let bad = TodoClient::new(&base).unwrap().with_token("nope");
+        assert_eq!(
+            bad.list_todos(None).await.unwrap_err().code(),
+            Some("invalid_token")
+        );
+
+        let mut client = TodoClient::new(&base).unwrap().with_locale("en");
+        assert_eq!(
+            client.pair("000000", "x").await.unwrap_err().code(),
+            Some("invalid_pairing_code")
+        );
+        let code = create_pairing_code(&state.pool).await.unwrap().code;
+        client.pair(&code, "x").await.unwrap();
+
+        let err = client
+            .update_todo("missing", &UpdateTodoBody::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("todo_not_found"));
+        let err = client
+            .create_todo(&CreateTodoBody {
+                content: "  ".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        match err {
+            ClientError::Api {
+                status,
+                code,
+                fields,
+                ..
+            } => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(code, "validation_failed");
+                assert_eq!(fields[0].field, "content");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert_eq!(
+            client.delete_group("missing").await.unwrap_err().code(),
+            Some("group_not_found")
+        );
+    }
+
+    #[tokio::test]
+    async fn ids_are_escaped_as_single_path_segments() {
+        let (state, client, _dir) = paired_client().await;
+        // 旧数据或其他客户端写入的 id 可能含有 /、?、# 与空格
+        let id = "a/b?c#d e%";
+        insert_todo(&state.pool, id, &new_todo("odd"))
+            .await
+            .unwrap();
+        let updated = client
+            .update_todo(
+                id,
+                &UpdateTodoBody {
+                    content: Some("odd id".into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.id, id);
+        client.delete_todo(id).await.unwrap();
+        assert!(client.list_todos(None).await.unwrap().is_empty());
+
+        insert_group(&state.pool, id, "odd").await.unwrap();
+        assert_eq!(client.update_group(id, "renamed").await.unwrap().id, id);
+        client.delete_group(id).await.unwrap();
+        assert!(client.list_groups().await.unwrap().is_empty());
+    }
+}
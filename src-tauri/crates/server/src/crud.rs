@@ -0,0 +1,354 @@
+//! 任务与分组的增删改查。/api/v1/todo、/api/v1/groups 的 axum 处理函数与桌面端的 IPC 命令共用这里的逻辑，
+//! 两条通路的校验规则、错误码与返回结构保持一致；HTTP 相关的部分（请求头、状态码）留在 server.rs。
+//! 写入成功后通过 AppState.events 推送 todos-changed / groups-changed，无论请求来自手机还是桌面窗口。
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use xy_todo_core::i18n::Msg;
+use xy_todo_core::store::{IdempotencyKey, IdempotentCreate, TodoUpdate};
+use xy_todo_core::{validation, CreateTodoBody, GroupItem, Todo, UpdateTodoBody};
+
+use crate::error::ApiError;
+use crate::AppState;
+
+/// 客户端 id 与幂等键的最大长度
+const MAX_CLIENT_KEY_LEN: usize = 128;
+
+/// 校验客户端提供的 id / 幂等键：去空白后非空、不超长，且只含字母、数字、`-` 与 `_`（UUID 即符合），
+/// 可以原样放进 URL 路径与请求头
+pub(crate) fn client_key(raw: &str) -> Option<&str> {
+    let key = raw.trim();
+    let valid = !key.is_empty()
+        && key.len() <= MAX_CLIENT_KEY_LEN
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    valid.then_some(key)
+}
+
+/// 请求内容的摘要（SHA-256 十六进制），用于识别同一个幂等键是否换了请求内容
+fn request_hash(body: &CreateTodoBody) -> String {
+    let json = serde_json::to_vec(body).unwrap_or_default();
+    Sha256::digest(&json)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 新增任务的结果
+pub struct CreatedTodo {
+    pub todo: Todo,
+    /// 命中幂等键或客户端 id 已存在，返回的是首次创建的任务
+    pub replayed: bool,
+}
+
+/// 全部任务；sort 为 comprehensive（默认）| importance | deadline
+pub async fn list_todos(state: &AppState, sort: &str) -> Result<Vec<Todo>, ApiError> {
+    state
+        .store
+        .list_todos(sort)
+        .await
+        .map_err(|e| ApiError::db("list_todos", Msg::ListTodosFailed, e))
+}
+
+/// 按输入联想历史任务内容；输入为空时返回空列表
+pub async fn content_suggestions(state: &AppState, q: &str) -> Result<Vec<String>, ApiError> {
+    let q = q.trim();
+    if q.is_empty() {
+        return Ok(Vec::new());
+    }
+    state
+        .store
+        .content_suggestions(q)
+        .await
+        .map_err(|e| ApiError::db("content_suggestions", Msg::ListTodosFailed, e))
+}
+
+/// 新增任务。idempotency_key 须已通过 client_key 校验；body.id 已存在时视为重放，
+/// 但已存在任务的内容或分组与请求不同时报 TodoIdConflict，不把别的任务当作创建结果返回。
+/// 幂等键的占用、是否已存在的检查与写入在同一个写事务内（见 TodoStore::create_todo_idempotent）
+pub async fn create_todo(
+    state: &AppState,
+    body: &CreateTodoBody,
+    idempotency_key: Option<&str>,
+) -> Result<CreatedTodo, ApiError> {
+    let new = validation::new_todo(body)?;
+    let id = match body.id.as_deref() {
+        Some(raw) => client_key(raw)
+            .ok_or_else(|| ApiError::invalid("id", Msg::InvalidTodoId))?
+            .to_string(),
+        None => Uuid::new_v4().to_string(),
+    };
+
+    let hash = idempotency_key.map(|_| request_hash(body));
+    let key = idempotency_key
+        .zip(hash.as_deref())
+        .map(|(key, request_hash)| IdempotencyKey { key, request_hash });
+    let created = match state
+        .store
+        .create_todo_idempotent(&id, &new, key)
+        .await
+        .map_err(|e| ApiError::db("create_todo", Msg::CreateTodoFailed, e))?
+    {
+        IdempotentCreate::Created(todo) => CreatedTodo {
+            todo,
+            replayed: false,
+        },
+        IdempotentCreate::Replayed(todo) => CreatedTodo {
+            todo,
+            replayed: true,
+        },
+        IdempotentCreate::IdConflict => return Err(ApiError::TodoIdConflict),
+        IdempotentCreate::KeyReused => return Err(ApiError::IdempotencyKeyReused),
+    };
+    if !created.replayed {
+        state.events.todos_changed(vec![created.todo.id.clone()]);
+    }
+    Ok(created)
+}
+
+/// 更新任务中出现的字段，返回更新后的任务
+pub async fn update_todo(
+    state: &AppState,
+    id: &str,
+    body: &UpdateTodoBody,
+) -> Result<Todo, ApiError> {
+    let update = state
+        .store
+        .update_todo(id, body)
+        .await
+        .map_err(|e| ApiError::db("update_todo", Msg::UpdateTodoFailed, e))?;
+    match update {
+        TodoUpdate::Updated(todo) => {
+            state.events.todos_changed(vec![id.to_string()]);
+            Ok(todo)
+        }
+        TodoUpdate::NotFound => Err(ApiError::TodoNotFound),
+        TodoUpdate::Invalid(errors) => Err(errors.into()),
+    }
+}
+
+pub async fn delete_todo(state: &AppState, id: &str) -> Result<(), ApiError> {
+    match state.store.delete_todo(id).await {
+        Ok(true) => {
+            state.events.todos_changed(vec![id.to_string()]);
+            Ok(())
+        }
+        Ok(false) => Err(ApiError::TodoNotFound),
+        Err(e) => Err(ApiError::db("delete_todo", Msg::DeleteTodoFailed, e)),
+    }
+}
+
+/// 按给定顺序重写排序值，不存在的 id 忽略
+pub async fn reorder_todos(state: &AppState, ordered_ids: &[String]) -> Result<(), ApiError> {
+    state
+        .store
+        .reorder_todos(ordered_ids)
+        .await
+        .map_err(|e| ApiError::db("reorder_todos", Msg::ReorderTodosFailed, e))?;
+    state.events.todos_changed(ordered_ids.to_vec());
+    Ok(())
+}
+
+/// 全部分组（按排序值）
+pub async fn list_groups(state: &AppState) -> Result<Vec<GroupItem>, ApiError> {
+    state
+        .store
+        .list_groups()
+        .await
+        .map_err(|e| ApiError::db("list_groups", Msg::ListGroupsFailed, e))
+}
+
+/// 分组名称去掉首尾空白后不能为空
+fn group_name(raw: &str) -> Result<&str, ApiError> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err(ApiError::invalid("name", Msg::GroupNameEmpty));
+    }
+    Ok(name)
+}
+
+pub async fn create_group(state: &AppState, name: &str) -> Result<GroupItem, ApiError> {
+    let name = group_name(name)?;
+    let id = Uuid::new_v4().to_string();
+    state
+        .store
+        .create_group(&id, name)
+        .await
+        .map_err(|e| ApiError::db("create_group", Msg::CreateGroupFailed, e))?;
+    state.events.groups_changed(vec![id.clone()]);
+    Ok(GroupItem {
+        id,
+        name: name.to_string(),
+    })
+}
+
+pub async fn update_group(state: &AppState, id: &str, name: &str) -> Result<GroupItem, ApiError> {
+    let name = group_name(name)?;
+    match state.store.rename_group(id, name).await {
+        Ok(true) => {
+            state.events.groups_changed(vec![id.to_string()]);
+            Ok(GroupItem {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
+        }
+        Ok(false) => Err(ApiError::GroupNotFound),
+        Err(e) => Err(ApiError::db("update_group", Msg::UpdateGroupFailed, e)),
+    }
+}
+
+/// 删除分组，其下任务变为未分组；受影响的任务不逐条列出，todos-changed 的 ids 留空
+pub async fn delete_group(state: &AppState, id: &str) -> Result<(), ApiError> {
+    match state.store.delete_group(id).await {
+        Ok(true) => {
+            state.events.groups_changed(vec![id.to_string()]);
+            state.events.todos_changed(Vec::new());
+            Ok(())
+        }
+        Ok(false) => Err(ApiError::GroupNotFound),
+        Err(e) => Err(ApiError::db("delete_group", Msg::DeleteGroupFailed, e)),
+    }
+}
+
+/// 按给定顺序重写分组排序值
+pub async fn reorder_groups(state: &AppState, ordered_ids: &[String]) -> Result<(), ApiError> {
+    state
+        .store
+        .reorder_groups(ordered_ids)
+        .await
+        .map_err(|e| ApiError::db("reorder_groups", Msg::ReorderGroupsFailed, e))?;
+    state.events.groups_changed(ordered_ids.to_vec());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use xy_todo_core::store::SqliteStore;
+
+    async fn state(dir: &tempfile::TempDir) -> AppState {
+        let store = SqliteStore::in_memory().await.unwrap();
+        AppState::new(
+            store.pool().clone(),
+            Arc::new(crate::events::NoopEventSink),
+            Arc::new(crate::secrets::FileSecretStore::new(dir.path())),
+        )
+    }
+
+    fn body(content: &str) -> CreateTodoBody {
+        CreateTodoBody {
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn todo_count(state: &AppState) -> usize {
+        list_todos(state, "comprehensive").await.unwrap().len()
+    }
+
+    #[test]
+    fn client_keys_are_url_safe() {
+        assert_eq!(client_key(" abc-DEF_123 "), Some("abc-DEF_123"));
+        assert_eq!(client_key("  "), None);
+        assert_eq!(client_key("a/b"), None);
+        assert_eq!(client_key("键"), None);
+        assert_eq!(client_key(&"k".repeat(MAX_CLIENT_KEY_LEN + 1)), None);
+    }
+
+    #[tokio::test]
+    async fn same_idempotency_key_replays_the_first_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(&dir).await;
+        let first = create_todo(&state, &body("买牛奶"), Some("k1"))
+            .await
+            .unwrap();
+        assert!(!first.replayed);
+        let again = create_todo(&state, &body("买牛奶"), Some("k1"))
+            .await
+            .unwrap();
+        assert!(again.replayed);
+        assert_eq!(again.todo.id, first.todo.id);
+        assert_eq!(todo_count(&state).await, 1);
+
+        // 不带幂等键时同样的内容是新任务
+        let other = create_todo(&state, &body("买牛奶"), None).await.unwrap();
+        assert!(!other.replayed);
+        assert_eq!(todo_count(&state).await, 2);
+    }
+
+    #[tokio::test]
+    async fn reusing_a_key_with_another_body_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(&dir).await;
+        create_todo(&state, &body("买牛奶"), Some("k1"))
+            .await
+            .unwrap();
+        let err = create_todo(&state, &body("买面包"), Some("k1"))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ApiError::IdempotencyKeyReused));
+        assert_eq!(todo_count(&state).await, 1);
+    }
+
+    #[tokio::test]
+    async fn failed_create_does_not_hold_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(&dir).await;
+        let orphan = CreateTodoBody {
+            group_id: Some("missing".into()),
+            ..body("买牛奶")
+        };
+        assert!(matches!(
+            create_todo(&state, &orphan, Some("k1"))
+                .await
+                .err()
+                .unwrap(),
+            ApiError::UnknownGroup
+        ));
+        let created = create_todo(&state, &body("买面包"), Some("k1"))
+            .await
+            .unwrap();
+        assert!(!created.replayed);
+    }
+
+    #[tokio::test]
+    async fn existing_client_id_is_a_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state(&dir).await;
+        let with_id = CreateTodoBody {
+            id: Some("client-1".into()),
+            ..body("买牛奶")
+        };
+        let first = create_todo(&state, &with_id, None).await.unwrap();
+        assert_eq!(first.todo.id, "client-1");
+        let again = create_todo(&state, &with_id, None).await.unwrap();
+        assert!(again.replayed);
+        assert_eq!(again.todo.content, "买牛奶");
+        assert_eq!(todo_count(&state).await, 1);
+
+        let other_content = CreateTodoBody {
+            id: Some("client-1".into()),
+            ..body("买面包")
+        };
+        assert!(matches!(
+            create_todo(&state, &other_content, None)
+                .await
+                .err()
+                .unwrap(),
+            ApiError::TodoIdConflict
+        ));
+        assert_eq!(todo_count(&state).await, 1);
+
+        let bad_id = CreateTodoBody {
+            id: Some("a b".into()),
+            ..body("x")
+        };
+        assert!(matches!(
+            create_todo(&state, &bad_id, None).await.err().unwrap(),
+            ApiError::Validation(_)
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+//! HTTP 接口与桌面端 IPC 命令统一的错误类型。响应体固定为 `{ "code": "...", "error": "...", "fields"?: [...] }`：
+//! `code` 是稳定的机器可读错误码，客户端据此分支处理；`error` 是按请求语言（见 i18n）生成的提示，可能调整措辞；
+//! `fields` 仅在参数校验失败时出现，逐项列出出错字段。IPC 命令失败时前端收到的是同样结构的对象。
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use xy_todo_core::i18n::{t, tf, Msg};
+use xy_todo_core::validation::{FieldError, FieldErrors};
+
+/// 错误响应体
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// 稳定的错误码，如 validation_failed、todo_not_found、db_error
+    #[schema(value_type = String, example = "todo_not_found")]
+    pub code: &'static str,
+    /// 按请求语言生成的提示文案
+    pub error: String,
+    /// 参数校验失败时逐项列出的出错字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+}
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// 请求参数不合法，附带逐字段的错误
+    Validation(FieldErrors),
+    /// Idempotency-Key 请求头格式不合法
+    InvalidIdempotencyKey,
+    /// 同一个 Idempotency-Key 再次提交时请求内容不同
+    IdempotencyKeyReused,
+    /// 客户端 id 已存在，但内容或分组与请求不同
+    TodoIdConflict,
+    TodoNotFound,
+    GroupNotFound,
+    /// 写入的 group_id 指向不存在的分组
+    UnknownGroup,
+    /// 配对码错误、过期或尝试次数用尽
+    InvalidPairingCode,
+    /// 请求未携带访问令牌
+    MissingToken,
+    /// 携带的访问令牌无效或已吊销
+    InvalidToken,
+    /// 经 /api 兼容别名访问且未携带令牌：多为手机上缓存的旧页面，需要刷新
+    ClientOutdated,
+    /// 数据库读写失败；细节只写日志，响应中只给出操作层面的提示
+    Db(Msg),
+    /// 桌面端处于远程模式，本机没有数据库（仅 IPC 命令会返回）
+    RemoteModeUnavailable,
+}
+
+impl ApiError {
+    /// 记录数据库错误并转换为接口错误。外键约束失败说明 group_id 指向不存在的分组
+    /// （todos.group_id 外键），按 400 返回而不是当作内部错误。
+    pub(crate) fn db(context: &str, message: Msg, e: sqlx::Error) -> Self {
+        if matches!(&e, sqlx::Error::Database(db) if db.is_foreign_key_violation()) {
+            return ApiError::UnknownGroup;
+        }
+        eprintln!("{}: {}", context, e);
+        ApiError::Db(message)
+    }
+
+    /// 单个字段的校验错误
+    pub(crate) fn invalid(field: &'static str, message: Msg) -> Self {
+        let mut errors = FieldErrors::default();
+        errors.push(field, t(message));
+        ApiError::Validation(errors)
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::InvalidIdempotencyKey => "invalid_idempotency_key",
+            ApiError::IdempotencyKeyReused => "idempotency_key_reused",
+            ApiError::TodoIdConflict => "todo_id_conflict",
+            ApiError::TodoNotFound => "todo_not_found",
+            ApiError::GroupNotFound => "group_not_found",
+            ApiError::UnknownGroup => "unknown_group",
+            ApiError::InvalidPairingCode => "invalid_pairing_code",
+            ApiError::MissingToken => "missing_token",
+            ApiError::InvalidToken => "invalid_token",
+            ApiError::ClientOutdated => "client_outdated",
+            ApiError::Db(_) => "db_error",
+            ApiError::RemoteModeUnavailable => "remote_mode_unavailable",
+        }
+    }
+
+    pub(crate) fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Validation(_) | ApiError::InvalidIdempotencyKey | ApiError::UnknownGroup => {
+                StatusCode::BAD_REQUEST
+            }
+            ApiError::TodoNotFound | ApiError::GroupNotFound => StatusCode::NOT_FOUND,
+            ApiError::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::InvalidPairingCode => StatusCode::FORBIDDEN,
+            ApiError::MissingToken | ApiError::InvalidToken | ApiError::ClientOutdated => {
+                StatusCode::UNAUTHORIZED
+            }
+            ApiError::TodoIdConflict | ApiError::RemoteModeUnavailable => StatusCode::CONFLICT,
+            ApiError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::Validation(errors) => tf(Msg::ValidationFailed, &[&errors.message()]),
+            ApiError::InvalidIdempotencyKey => t(Msg::InvalidIdempotencyKey).to_string(),
+            ApiError::IdempotencyKeyReused => t(Msg::IdempotencyKeyReused).to_string(),
+            ApiError::TodoIdConflict => t(Msg::TodoIdConflict).to_string(),
+            ApiError::TodoNotFound => t(Msg::TodoNotFound).to_string(),
+            ApiError::GroupNotFound | ApiError::UnknownGroup => t(Msg::GroupNotFound).to_string(),
+            ApiError::InvalidPairingCode => t(Msg::InvalidPairingCode).to_string(),
+            ApiError::MissingToken => t(Msg::MissingToken).to_string(),
+            ApiError::InvalidToken => t(Msg::InvalidToken).to_string(),
+            ApiError::ClientOutdated => t(Msg::ClientOutdated).to_string(),
+            ApiError::Db(message) => t(*message).to_string(),
+            ApiError::RemoteModeUnavailable => t(Msg::RemoteModeUnavailable).to_string(),
+        }
+    }
+}
+
+impl From<FieldErrors> for ApiError {
+    fn from(errors: FieldErrors) -> Self {
+        ApiError::Validation(errors)
+    }
+}
+
+impl From<&ApiError> for ErrorBody {
+    fn from(e: &ApiError) -> Self {
+        let fields = match e {
+            ApiError::Validation(errors) => Some(errors.fields().to_vec()),
+            _ => None,
+        };
+        ErrorBody {
+            code: e.code(),
+            error: e.message(),
+            fields,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(ErrorBody::from(&self))).into_response()
+    }
+}
+
+/// IPC 命令直接返回 `Result<T, ApiError>`，错误按 ErrorBody 序列化
+impl Serialize for ApiError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorBody::from(self).serialize(serializer)
+    }
+}
@@ -0,0 +1,40 @@
+//! 向界面推送事件的抽象。桌面端的实现转发给 Tauri 窗口（见桌面 crate 的 events.rs），
+//! 无界面模式与命令行没有窗口，事件直接丢弃；服务端只依赖 EventSink，不直接持有 AppHandle。
+
+use serde::{Deserialize, Serialize};
+
+/// 任务新增、修改、删除或重新排序后推送，负载为 DataChanged
+pub const TODOS_CHANGED: &str = "todos-changed";
+/// 分组新增、重命名、删除或重新排序后推送，负载为 DataChanged
+pub const GROUPS_CHANGED: &str = "groups-changed";
+
+/// todos-changed / groups-changed 的负载。ids 为空表示范围未知（如删除分组连带的任务），界面应整体刷新
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataChanged {
+    pub ids: Vec<String>,
+}
+
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: serde_json::Value);
+
+    /// 通知界面任务有变化；手机端、IPC 命令、离线回放与各类同步写入后都会调用
+    fn todos_changed(&self, ids: Vec<String>) {
+        self.emit(TODOS_CHANGED, changed_payload(ids));
+    }
+
+    /// 通知界面分组有变化
+    fn groups_changed(&self, ids: Vec<String>) {
+        self.emit(GROUPS_CHANGED, changed_payload(ids));
+    }
+}
+
+fn changed_payload(ids: Vec<String>) -> serde_json::Value {
+    serde_json::to_value(DataChanged { ids }).unwrap_or_default()
+}
+
+/// 没有界面时使用，忽略所有事件
+pub struct NoopEventSink;
+
+impl EventSink for NoopEventSink {
+    fn emit(&self, _event: &str, _payload: serde_json::Value) {}
+}
@@ -0,0 +1,655 @@
+//! 共享文件夹同步：把 Syncthing、Dropbox、NAS 共享目录等任意文件同步工具当作传输层。
+//! 每台设备只追加写自己的日志分段 `<device_id>.<n>.jsonl`（每行一页带时间戳的 ChangeSet，注明来源设备），
+//! 并读取其他设备的日志合并进本地库；每个外部日志已读到的字节位置记在 sync_meta 中。
+//! 导入的变更在 change_log 中记下来源设备，导出时跳过，不会原样写回自己的日志。
+//! 读完后把各设备已连续读到的游标写进 `<device_id>.ack.json`；分段写满后换新分段，
+//! 旧分段在共享目录中的其他设备都确认读过后删除。发现新设备时导出一次全量，新设备拿得到已删除分段里的数据。
+//! 网络盘与同步目录上的文件系统事件并不可靠，因此按固定间隔比较文件大小与修改时间来发现变化。
+//! 文件读写都走 tokio::fs，不占用运行时的工作线程。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use xy_todo_core::i18n::{t, Msg};
+use xy_todo_core::store::SqliteStore;
+
+use crate::events::EventSink;
+use crate::peer::device_id;
+use crate::sync::{load_changes, load_local_changes, merge_changes, meta_get, meta_set, ChangeSet};
+
+/// 共享目录下存放日志的子目录名
+const FOLDER_SYNC_SUBDIR: &str = "douko-todo-sync";
+const LOG_EXTENSION: &str = "jsonl";
+const ACK_SUFFIX: &str = ".ack.json";
+/// 轮询间隔
+const FOLDER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// 导出时每行的变更条数
+const EXPORT_PAGE_SIZE: i64 = 500;
+/// 日志分段超过这个大小后，下一行写入新分段
+const SEGMENT_MAX_BYTES: u64 = 1024 * 1024;
+
+const META_FOLDER: &str = "sync_folder";
+const META_EXPORT_CURSOR: &str = "sync_folder_export_cursor";
+/// 本机当前写入的分段号
+const META_SEGMENT: &str = "sync_folder_segment";
+/// 已见过的其他设备（JSON 数组），出现新设备时导出全量
+const META_DEVICES: &str = "sync_folder_devices";
+const META_OFFSET_PREFIX: &str = "sync_folder_offset:";
+/// 各设备日志已连续读到的游标，写进本机的确认文件
+const META_ACK_PREFIX: &str = "sync_folder_ack:";
+
+/// 同一时刻只跑一轮，避免轮询与手动触发重复导入
+static FOLDER_SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 确认文件：本机已读到的各设备日志游标
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct AckFile {
+    acks: BTreeMap<String, i64>,
+}
+
+/// 日志目录中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum LogFile {
+    /// 日志分段；旧版本的单文件日志 `<device_id>.jsonl` 记为第 0 段
+    Segment {
+        device: String,
+        segment: u64,
+    },
+    Ack {
+        device: String,
+    },
+}
+
+impl LogFile {
+    fn parse(name: &str) -> Option<LogFile> {
+        if let Some(device) = name.strip_suffix(ACK_SUFFIX) {
+            return Some(LogFile::Ack {
+                device: device.to_string(),
+            });
+        }
+        let stem = name.strip_suffix(&format!(".{}", LOG_EXTENSION))?;
+        match stem.rsplit_once('.') {
+            Some((device, n)) => n.parse().ok().map(|segment| LogFile::Segment {
+                device: device.to_string(),
+                segment,
+            }),
+            None => Some(LogFile::Segment {
+                device: stem.to_string(),
+                segment: 0,
+            }),
+        }
+    }
+
+    fn device(&self) -> &str {
+        match self {
+            LogFile::Segment { device, .. } | LogFile::Ack { device } => device,
+        }
+    }
+}
+
+/// 分段的文件名去掉扩展名，也是其读取位置在 sync_meta 中的键名后缀（第 0 段与旧版本的键一致）
+fn segment_stem(device: &str, segment: u64) -> String {
+    match segment {
+        0 => device.to_string(),
+        n => format!("{}.{}", device, n),
+    }
+}
+
+fn segment_name(device: &str, segment: u64) -> String {
+    format!("{}.{}", segment_stem(device, segment), LOG_EXTENSION)
+}
+
+fn ack_path(dir: &Path, device: &str) -> PathBuf {
+    dir.join(format!("{}{}", device, ACK_SUFFIX))
+}
+
+/// 当前配置的共享目录
+pub async fn get_folder(pool: &sqlx::SqlitePool) -> Result<Option<String>, sqlx::Error> {
+    meta_get(pool, META_FOLDER).await
+}
+
+/// 设置或清除共享目录。更换目录时重置导出游标、分段与各日志的读取位置，随后立即同步一次。
+pub async fn set_folder(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    folder: Option<&str>,
+) -> Result<(), String> {
+    let folder = folder.map(str::trim).filter(|f| !f.is_empty());
+    if let Some(f) = folder {
+        let is_dir = tokio::fs::metadata(f)
+            .await
+            .map(|m| m.is_dir())
+            .unwrap_or(false);
+        if !is_dir {
+            return Err(t(Msg::FolderNotFound).to_string());
+        }
+    }
+    let _guard = FOLDER_SYNC_LOCK.lock().await;
+    meta_set(pool, META_FOLDER, folder)
+        .await
+        .map_err(|e| e.to_string())?;
+    for key in [META_EXPORT_CURSOR, META_SEGMENT, META_DEVICES] {
+        meta_set(pool, key, None).await.map_err(|e| e.to_string())?;
+    }
+    for prefix in [META_OFFSET_PREFIX, META_ACK_PREFIX] {
+        sqlx::query("DELETE FROM sync_meta WHERE key LIKE ?")
+            .bind(format!("{}%", prefix))
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(f) = folder {
+        sync_folder(pool, events, Path::new(f)).await?;
+    }
+    Ok(())
+}
+
+/// 立即与共享目录同步一次（未配置目录时什么也不做）
+pub async fn sync_now(pool: &sqlx::SqlitePool, events: &dyn EventSink) -> Result<(), String> {
+    let _guard = FOLDER_SYNC_LOCK.lock().await;
+    let Some(folder) = get_folder(pool).await.map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    sync_folder(pool, events, Path::new(&folder)).await
+}
+
+fn log_dir(folder: &Path) -> PathBuf {
+    folder.join(FOLDER_SYNC_SUBDIR)
+}
+
+/// 列出日志目录中可识别的文件，按设备与分段号排序
+async fn list_files(dir: &Path) -> Result<Vec<LogFile>, String> {
+    let mut entries = tokio::fs::read_dir(dir).await.map_err(|e| e.to_string())?;
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        if let Some(file) = entry.file_name().to_str().and_then(LogFile::parse) {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// 共享目录中出现的其他设备（有日志分段或确认文件）
+fn other_devices<'a>(files: &'a [LogFile], own_id: &str) -> BTreeSet<&'a str> {
+    files
+        .iter()
+        .map(LogFile::device)
+        .filter(|d| *d != own_id)
+        .collect()
+}
+
+async fn meta_i64(pool: &sqlx::SqlitePool, key: &str) -> Result<Option<i64>, String> {
+    Ok(meta_get(pool, key)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|v| v.parse().ok()))
+}
+
+async fn sync_folder(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    folder: &Path,
+) -> Result<(), String> {
+    let dir = log_dir(folder);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| e.to_string())?;
+    let own_id = device_id(pool).await.map_err(|e| e.to_string())?;
+    let files = list_files(&dir).await?;
+    import_logs(pool, events, &dir, &own_id, &files).await?;
+    write_ack(pool, &dir, &own_id).await?;
+    export_log(pool, &dir, &own_id, &files).await?;
+    prune_segments(pool, &dir, &own_id, &files).await
+}
+
+/// 把本地 change_log 中尚未导出的变更追加到本机日志，每页一行。
+/// 平时只导出本机产生的变更；共享目录里出现新设备时从头导出全量（含导入的变更），供它补齐已删除分段中的数据
+async fn export_log(
+    pool: &sqlx::SqlitePool,
+    dir: &Path,
+    own_id: &str,
+    files: &[LogFile],
+) -> Result<(), String> {
+    let known: BTreeSet<String> = meta_get(pool, META_DEVICES)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default();
+    let present = other_devices(files, own_id);
+    let full = present.iter().any(|d| !known.contains(*d));
+
+    let mut segment = meta_i64(pool, META_SEGMENT).await?.unwrap_or(1).max(1) as u64;
+    let mut cursor = match full {
+        true => 0,
+        false => meta_i64(pool, META_EXPORT_CURSOR).await?.unwrap_or(0),
+    };
+    loop {
+        let set = match full {
+            true => load_changes(pool, cursor, EXPORT_PAGE_SIZE).await,
+            false => load_local_changes(pool, cursor, EXPORT_PAGE_SIZE).await,
+        }
+        .map_err(|e| e.to_string())?;
+        if set.cursor == cursor {
+            break;
+        }
+        if !set.is_empty() {
+            let mut path = dir.join(segment_name(own_id, segment));
+            let len = tokio::fs::metadata(&path)
+                .await
+                .map(|m| m.len())
+                .unwrap_or(0);
+            if len >= SEGMENT_MAX_BYTES {
+                segment += 1;
+                meta_set(pool, META_SEGMENT, Some(&segment.to_string()))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                path = dir.join(segment_name(own_id, segment));
+            }
+            append_line(&path, &set, own_id).await?;
+        }
+        cursor = set.cursor;
+        meta_set(pool, META_EXPORT_CURSOR, Some(&cursor.to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+        if !set.has_more {
+            break;
+        }
+    }
+    if full {
+        let devices: BTreeSet<&str> = known.iter().map(String::as_str).chain(present).collect();
+        let devices = serde_json::to_string(&devices).map_err(|e| e.to_string())?;
+        meta_set(pool, META_DEVICES, Some(&devices))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 追加一行变更，注明来源设备
+async fn append_line(path: &Path, set: &ChangeSet, own_id: &str) -> Result<(), String> {
+    let mut value = serde_json::to_value(set).map_err(|e| e.to_string())?;
+    value["origin"] = serde_json::Value::String(own_id.to_string());
+    let mut line = value.to_string();
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    file.sync_data().await.map_err(|e| e.to_string())
+}
+
+async fn read_from(path: &Path, offset: u64) -> Result<Vec<u8>, String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(buf)
+}
+
+/// 读取其他设备日志中新增的完整行并合并；末尾未写完的半行留到下次再读。
+/// 文件比记录的位置还短（被替换）时从头重读，合并是幂等的；已被对方删除的分段跳过。
+/// 同一设备的分段按顺序读取，某一段还有半行没到时，之后读到的游标不计入确认，免得对方过早删掉这一段。
+async fn import_logs(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    dir: &Path,
+    own_id: &str,
+    files: &[LogFile],
+) -> Result<(), String> {
+    let mut stalled: BTreeSet<&str> = BTreeSet::new();
+    for file in files {
+        let LogFile::Segment { device, segment } = file else {
+            continue;
+        };
+        if device == own_id {
+            continue;
+        }
+        let path = dir.join(segment_name(device, *segment));
+        let key = format!("{}{}", META_OFFSET_PREFIX, segment_stem(device, *segment));
+        let mut offset = meta_i64(pool, &key).await?.unwrap_or(0) as u64;
+        let Ok(meta) = tokio::fs::metadata(&path).await else {
+            continue;
+        };
+        let len = meta.len();
+        if len < offset {
+            offset = 0;
+        }
+        if len > offset {
+            let buf = read_from(&path, offset).await?;
+            let complete = buf
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let mut max_cursor = None;
+            for line in buf[..complete].split(|b| *b == b'\n') {
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                match serde_json::from_slice::<ChangeSet>(line) {
+                    Ok(mut set) => {
+                        // 旧版本写的行没有来源设备，按文件所属设备记
+                        set.origin.get_or_insert_with(|| device.clone());
+                        merge_changes(&SqliteStore::new(pool.clone()), events, &set)
+                            .await
+                            .map_err(|e| e.to_string())?;
+                        max_cursor = max_cursor.max(Some(set.cursor));
+                    }
+                    Err(e) => eprintln!("folder sync: skip bad line in {}: {}", path.display(), e),
+                }
+            }
+            offset += complete as u64;
+            meta_set(pool, &key, Some(&offset.to_string()))
+                .await
+                .map_err(|e| e.to_string())?;
+            if let (Some(cursor), false) = (max_cursor, stalled.contains(device.as_str())) {
+                let ack_key = format!("{}{}", META_ACK_PREFIX, device);
+                if meta_i64(pool, &ack_key).await?.unwrap_or(0) < cursor {
+                    meta_set(pool, &ack_key, Some(&cursor.to_string()))
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        if offset < len {
+            stalled.insert(device);
+        }
+    }
+
+    // 对方删除了的分段不会再出现，清掉它们的读取位置
+    let present: BTreeSet<String> = files
+        .iter()
+        .filter_map(|f| match f {
+            LogFile::Segment { device, segment } => Some(format!(
+                "{}{}",
+                META_OFFSET_PREFIX,
+                segment_stem(device, *segment)
+            )),
+            LogFile::Ack { .. } => None,
+        })
+        .collect();
+    let keys: Vec<String> = sqlx::query_scalar("SELECT key FROM sync_meta WHERE key LIKE ?")
+        .bind(format!("{}%", META_OFFSET_PREFIX))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    for key in keys.iter().filter(|k| !present.contains(*k)) {
+        meta_set(pool, key, None).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+async fn read_ack(path: &Path) -> Option<AckFile> {
+    let data = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// 把本机对各设备日志的确认写进 `<own_id>.ack.json`（先写临时文件再改名）。
+/// 内容没变时不重写，免得其他设备的轮询把它当作新变化；还没读过任何日志时也写一份空的，让其他设备知道本机在场
+async fn write_ack(pool: &sqlx::SqlitePool, dir: &Path, own_id: &str) -> Result<(), String> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT key, value FROM sync_meta WHERE key LIKE ?")
+            .bind(format!("{}%", META_ACK_PREFIX))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    let ack = AckFile {
+        acks: rows
+            .into_iter()
+            .filter_map(|(k, v)| {
+                Some((
+                    k.strip_prefix(META_ACK_PREFIX)?.to_string(),
+                    v.parse().ok()?,
+                ))
+            })
+            .collect(),
+    };
+    let path = ack_path(dir, own_id);
+    if read_ack(&path).await.as_ref() == Some(&ack) {
+        return Ok(());
+    }
+    let tmp = path.with_extension("json.tmp");
+    let data = serde_json::to_vec_pretty(&ack).map_err(|e| e.to_string())?;
+    tokio::fs::write(&tmp, data)
+        .await
+        .map_err(|e| e.to_string())?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 分段中最大的游标；读不到时返回 None
+async fn segment_end(path: &Path) -> Option<i64> {
+    #[derive(Deserialize)]
+    struct Line {
+        cursor: i64,
+    }
+    let data = tokio::fs::read(path).await.ok()?;
+    data.split(|b| *b == b'\n')
+        .filter_map(|line| serde_json::from_slice::<Line>(line).ok())
+        .map(|line| line.cursor)
+        .max()
+}
+
+/// 删除本机已写完、且共享目录中其他设备都确认读过的分段，当前写入的分段不删。
+/// 还没有确认文件的设备（旧版本）视为什么都没读，此时不删除任何分段
+async fn prune_segments(
+    pool: &sqlx::SqlitePool,
+    dir: &Path,
+    own_id: &str,
+    files: &[LogFile],
+) -> Result<(), String> {
+    let others = other_devices(files, own_id);
+    if others.is_empty() {
+        return Ok(());
+    }
+    let mut acked = i64::MAX;
+    for device in others {
+        let ack = read_ack(&ack_path(dir, device))
+            .await
+            .and_then(|a| a.acks.get(own_id).copied())
+            .unwrap_or(0);
+        acked = acked.min(ack);
+    }
+    let current = meta_i64(pool, META_SEGMENT).await?.unwrap_or(1) as u64;
+    for file in files {
+        let LogFile::Segment { device, segment } = file else {
+            continue;
+        };
+        if device != own_id || *segment >= current {
+            continue;
+        }
+        let path = dir.join(segment_name(device, *segment));
+        if matches!(segment_end(&path).await, Some(end) if end <= acked) {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                eprintln!("folder sync: remove {}: {}", path.display(), e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 目录内各文件的大小与修改时间，用于判断是否有其他设备写入
+async fn snapshot(dir: &Path) -> HashMap<PathBuf, (u64, Option<SystemTime>)> {
+    let mut map = HashMap::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Ok(meta) = entry.metadata().await {
+                map.insert(entry.path(), (meta.len(), meta.modified().ok()));
+            }
+        }
+    }
+    map
+}
+
+/// 后台轮询：启动时同步一次，之后目录内日志变化或本地有新变更时再同步
+pub async fn run_folder_sync_loop(pool: sqlx::SqlitePool, events: Arc<dyn EventSink>) {
+    let mut last_snapshot = None;
+    let mut last_seq: Option<i64> = None;
+    let mut ticker = tokio::time::interval(FOLDER_POLL_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let folder = match get_folder(&pool).await {
+            Ok(Some(f)) => PathBuf::from(f),
+            Ok(None) => {
+                last_snapshot = None;
+                continue;
+            }
+            Err(e) => {
+                eprintln!("folder sync: {}", e);
+                continue;
+            }
+        };
+        let current = snapshot(&log_dir(&folder)).await;
+        let seq: Option<i64> = sqlx::query_scalar("SELECT MAX(seq) FROM change_log")
+            .fetch_one(&pool)
+            .await
+            .unwrap_or(None);
+        if last_snapshot.as_ref() == Some(&current) && last_seq == seq {
+            continue;
+        }
+        let _guard = FOLDER_SYNC_LOCK.lock().await;
+        if let Err(e) = sync_folder(&pool, events.as_ref(), &folder).await {
+            eprintln!("folder sync {}: {}", folder.display(), e);
+            continue;
+        }
+        // 以同步后的状态为基准，避免把自己刚写入的日志当作外部变化
+        last_snapshot = Some(snapshot(&log_dir(&folder)).await);
+        last_seq = sqlx::query_scalar("SELECT MAX(seq) FROM change_log")
+            .fetch_one(&pool)
+            .await
+            .unwrap_or(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NoopEventSink;
+    use xy_todo_core::store::SqliteStore;
+
+    async fn device(folder: &Path) -> sqlx::SqlitePool {
+        let pool = SqliteStore::in_memory().await.unwrap().pool().clone();
+        set_folder(&pool, &NoopEventSink, folder.to_str())
+            .await
+            .unwrap();
+        pool
+    }
+
+    async fn add_todo(pool: &sqlx::SqlitePool, id: &str) {
+        sqlx::query(
+            "INSERT INTO todos (id, content, status, importance, sort_order, updated_at) \
+             VALUES (?, 'x', 'pending', 'normal', 0, 1)",
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn has_todo(pool: &sqlx::SqlitePool, id: &str) -> bool {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM todos WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            > 0
+    }
+
+    async fn own_segments(folder: &Path, pool: &sqlx::SqlitePool) -> Vec<u64> {
+        let own_id = device_id(pool).await.unwrap();
+        list_files(&log_dir(folder))
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|f| match f {
+                LogFile::Segment { device, segment } if device == own_id => Some(segment),
+                _ => None,
+            })
+            .collect()
+    }
+
+    async fn log_len(folder: &Path, pool: &sqlx::SqlitePool, segment: u64) -> u64 {
+        let own_id = device_id(pool).await.unwrap();
+        let path = log_dir(folder).join(segment_name(&own_id, segment));
+        tokio::fs::metadata(path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn file_names_are_parsed_with_legacy_logs_as_segment_zero() {
+        let seg = |device: &str, segment| LogFile::Segment {
+            device: device.to_string(),
+            segment,
+        };
+        assert_eq!(LogFile::parse("abc.jsonl"), Some(seg("abc", 0)));
+        assert_eq!(LogFile::parse("abc.3.jsonl"), Some(seg("abc", 3)));
+        assert_eq!(
+            LogFile::parse("abc.ack.json"),
+            Some(LogFile::Ack {
+                device: "abc".to_string()
+            })
+        );
+        assert_eq!(LogFile::parse("abc.ack.json.tmp"), None);
+        assert_eq!(LogFile::parse("abc.x.jsonl"), None);
+    }
+
+    #[tokio::test]
+    async fn imported_changes_are_not_written_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (device(dir.path()).await, device(dir.path()).await);
+        add_todo(&a, "t1").await;
+        for pool in [&a, &b, &a, &b] {
+            sync_now(pool, &NoopEventSink).await.unwrap();
+        }
+        assert!(has_todo(&b, "t1").await);
+
+        let before = log_len(dir.path(), &b, 1).await;
+        add_todo(&a, "t2").await;
+        sync_now(&a, &NoopEventSink).await.unwrap();
+        sync_now(&b, &NoopEventSink).await.unwrap();
+        assert!(has_todo(&b, "t2").await);
+        assert_eq!(log_len(dir.path(), &b, 1).await, before);
+    }
+
+    #[tokio::test]
+    async fn finished_segments_are_removed_once_every_device_has_read_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (device(dir.path()).await, device(dir.path()).await);
+        add_todo(&a, "t1").await;
+        sync_now(&a, &NoopEventSink).await.unwrap();
+        // 模拟第 1 段写满：之后的变更写入第 2 段
+        meta_set(&a, META_SEGMENT, Some("2")).await.unwrap();
+        add_todo(&a, "t2").await;
+        sync_now(&a, &NoopEventSink).await.unwrap();
+        assert_eq!(own_segments(dir.path(), &a).await, vec![1, 2]);
+
+        // b 读完之前不能删
+        sync_now(&a, &NoopEventSink).await.unwrap();
+        assert_eq!(own_segments(dir.path(), &a).await, vec![1, 2]);
+
+        sync_now(&b, &NoopEventSink).await.unwrap();
+        assert!(has_todo(&b, "t1").await && has_todo(&b, "t2").await);
+        sync_now(&a, &NoopEventSink).await.unwrap();
+        assert_eq!(own_segments(dir.path(), &a).await, vec![2]);
+    }
+}
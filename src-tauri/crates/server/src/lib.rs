@@ -0,0 +1,132 @@
+//! 局域网服务：axum 路由（/api/v1 与 /mobile）、增量与点对点同步、文件夹与 WebDAV 同步、配对与远程模式，
+//! 以及命令行与无界面模式。不依赖 Tauri：桌面端通过 AppState 与 EventSink 接入，douko-todo-server 可单独部署。
+
+pub mod backend;
+pub mod backup;
+pub mod cli;
+pub mod client;
+pub mod crud;
+pub mod error;
+pub mod events;
+pub mod folder_sync;
+pub mod pairing;
+pub mod peer;
+pub mod remote;
+pub mod secrets;
+pub mod server;
+mod sync;
+pub mod webdav;
+
+use std::sync::Arc;
+
+use xy_todo_core::i18n::{tf, Msg};
+use xy_todo_core::store::{OpenDbError, OpenDbErrorKind};
+use xy_todo_core::{i18n, recovery, store};
+
+/// 局域网服务默认端口
+pub const DEFAULT_PORT: u16 = 8080;
+/// 应用设置文件名（位于应用数据目录下，前端通过 tauri-plugin-store 读写）
+pub const APP_SETTINGS_FILENAME: &str = "app-settings.json";
+
+#[derive(Clone)]
+pub struct AppState {
+    /// 同步、备份等仍直接使用连接池
+    pub pool: sqlx::SqlitePool,
+    /// 任务与分组的读写
+    pub store: Arc<dyn store::TodoStore>,
+    /// 向界面推送事件；无界面模式下为 NoopEventSink
+    pub events: Arc<dyn events::EventSink>,
+    /// WebDAV 密码等敏感配置；桌面端为系统钥匙串，无界面模式为 FileSecretStore
+    pub secrets: Arc<dyn secrets::SecretStore>,
+}
+
+impl AppState {
+    pub fn new(
+        pool: sqlx::SqlitePool,
+        events: Arc<dyn events::EventSink>,
+        secrets: Arc<dyn secrets::SecretStore>,
+    ) -> Self {
+        Self {
+            store: Arc::new(store::SqliteStore::new(pool.clone())),
+            pool,
+            events,
+            secrets,
+        }
+    }
+}
+
+/// 本地数据库文件名（位于应用数据目录下）
+pub const DB_FILENAME: &str = "todos.db";
+/// 与 tauri.conf.json 的 identifier 一致，用于在没有 AppHandle 时定位应用数据目录
+pub const APP_IDENTIFIER: &str = "com.xycoder.todo-list";
+
+/// 桌面端使用的应用数据目录；命令行与无界面模式没有 AppHandle，按同样的规则推算
+pub fn default_app_data_dir() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|d| d.join(APP_IDENTIFIER))
+}
+
+/// 无界面模式的启动参数
+pub struct HeadlessOptions {
+    /// 应用数据目录（todos.db、设置与备份）
+    pub data_dir: std::path::PathBuf,
+    pub server: server::ServerConfig,
+}
+
+/// 无界面模式：与 run() 相同地打开 todos.db 并迁移，启动局域网服务与后台同步，不创建任何窗口。
+/// 收到 Ctrl+C 或 SIGTERM 后不再接收新请求，等处理中的请求结束、关闭数据库后返回。
+pub fn run_headless(options: HeadlessOptions) -> Result<(), String> {
+    i18n::load_app_locale(&options.data_dir.join(APP_SETTINGS_FILENAME));
+    let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    rt.block_on(async {
+        let db_path = options.data_dir.join(DB_FILENAME);
+        let opened = backend::LocalBackend::open(
+            &db_path,
+            Arc::new(events::NoopEventSink),
+            Arc::new(secrets::FileSecretStore::new(&options.data_dir)),
+        )
+        .await;
+        let mut backend = match opened {
+            Ok(backend) => backend,
+            Err(e) => return Err(open_failure_message(&db_path, &e).await),
+        };
+        if let Err(e) = backend.start_server(options.server).await {
+            backend.shutdown().await;
+            return Err(e);
+        }
+        let result = tokio::select! {
+            _ = shutdown_signal() => Ok(()),
+            r = backend.server_stopped() => r,
+        };
+        backend.shutdown().await;
+        result
+    })
+}
+
+/// 无界面模式没有恢复窗口：数据库损坏时在错误信息后附上手动恢复的方法
+async fn open_failure_message(db_path: &std::path::Path, e: &OpenDbError) -> String {
+    if e.kind != OpenDbErrorKind::Corrupt {
+        return e.to_string();
+    }
+    let db = db_path.display();
+    let hint = match backup::latest_usable_backup(&recovery::backup_dir(db_path)).await {
+        Some(backup) => tf(Msg::DbRestoreHint, &[&db, &backup.path.display()]),
+        None => tf(Msg::DbNoBackupHint, &[&db]),
+    };
+    format!("{}\n{}", e, hint)
+}
+
+/// 等待 Ctrl+C；Unix 上同时等待 systemd 停止服务时发送的 SIGTERM
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
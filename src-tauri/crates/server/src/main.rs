@@ -0,0 +1,6 @@
+//! 不含图形界面的服务程序：`douko-todo-server [--port 8080] [--data-dir ...]` 运行局域网服务，
+//! 也可执行与 douko-todo 相同的命令行子命令。
+
+fn main() {
+    std::process::exit(xy_todo_server::cli::run_server_from_env());
+}
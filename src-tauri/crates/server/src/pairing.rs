@@ -0,0 +1,481 @@
+//! 配对与访问令牌：主机在设置中生成一次性配对码，另一台电脑（远程模式、点对点同步）用配对码换取访问令牌，
+//! 之后每个请求带 `Authorization: Bearer <token>`。除版本握手与配对外，所有数据与同步接口都要求令牌：
+//! 未携带或令牌无效、已吊销时返回 401。手机扫码页面使用单独的令牌，随二维码地址（`/mobile?token=`）下发。
+//! api_tokens 只保存令牌的摘要（见 xy_todo_core::token_hash），数据库快照与备份中没有可用的令牌；
+//! 手机页面的令牌需要反复生成二维码，明文另存 SecretStore。
+
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use xy_todo_core::i18n::{t, Msg};
+use xy_todo_core::{now_millis, token_hash};
+
+use crate::error::{ApiError, ErrorBody};
+use crate::peer::device_id;
+use crate::secrets::SecretStore;
+use crate::server::API_PREFIX;
+use crate::AppState;
+
+/// 配对码有效期
+const PAIRING_CODE_TTL_MS: i64 = 5 * 60 * 1000;
+/// 配对码允许的错误尝试次数，超过后作废
+const PAIRING_CODE_MAX_ATTEMPTS: i64 = 5;
+/// 令牌最后使用时间的记录粒度
+const LAST_USED_RESOLUTION_MS: i64 = 60 * 1000;
+/// 手机扫码页面的令牌在 api_tokens 中的固定 id
+const MOBILE_TOKEN_ID: &str = "mobile";
+/// 手机扫码页面令牌明文在 SecretStore 中的键
+const MOBILE_TOKEN_SECRET_KEY: &str = "mobile-token";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredPairingCode {
+    code: String,
+    expires_at: i64,
+    attempts: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingCode {
+    pub code: String,
+    /// 过期时间（毫秒）
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairedClient {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+}
+
+async fn save_pairing_code(
+    pool: &sqlx::SqlitePool,
+    stored: &StoredPairingCode,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR REPLACE INTO sync_meta (key, value) VALUES ('pairing_code', ?)")
+        .bind(serde_json::to_string(stored).unwrap_or_default())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn clear_pairing_code(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM sync_meta WHERE key = 'pairing_code'")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// 生成新的 6 位配对码（覆盖旧码）
+pub async fn create_pairing_code(pool: &sqlx::SqlitePool) -> Result<PairingCode, sqlx::Error> {
+    let code = format!("{:06}", uuid::Uuid::new_v4().as_u128() % 1_000_000);
+    let expires_at = now_millis() as i64 + PAIRING_CODE_TTL_MS;
+    save_pairing_code(
+        pool,
+        &StoredPairingCode {
+            code: code.clone(),
+            expires_at,
+            attempts: 0,
+        },
+    )
+    .await?;
+    Ok(PairingCode { code, expires_at })
+}
+
+/// 比较时不在第一个不同的字节处提前返回，耗时不透露猜中了几位
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 校验配对码；成功后配对码立即作废，失败则累计尝试次数
+async fn consume_pairing_code(pool: &sqlx::SqlitePool, code: &str) -> Result<bool, sqlx::Error> {
+    let raw: Option<String> =
+        sqlx::query_scalar("SELECT value FROM sync_meta WHERE key = 'pairing_code'")
+            .fetch_optional(pool)
+            .await?;
+    let Some(mut stored) = raw.and_then(|r| serde_json::from_str::<StoredPairingCode>(&r).ok())
+    else {
+        return Ok(false);
+    };
+    if stored.expires_at < now_millis() as i64 {
+        clear_pairing_code(pool).await?;
+        return Ok(false);
+    }
+    if constant_time_eq(stored.code.as_bytes(), code.trim().as_bytes()) {
+        clear_pairing_code(pool).await?;
+        return Ok(true);
+    }
+    stored.attempts += 1;
+    if stored.attempts >= PAIRING_CODE_MAX_ATTEMPTS {
+        clear_pairing_code(pool).await?;
+    } else {
+        save_pairing_code(pool, &stored).await?;
+    }
+    Ok(false)
+}
+
+pub async fn list_paired_clients(
+    pool: &sqlx::SqlitePool,
+) -> Result<Vec<PairedClient>, sqlx::Error> {
+    let rows: Vec<(String, String, i64, Option<i64>)> = sqlx::query_as(
+        "SELECT id, name, created_at, last_used_at FROM api_tokens ORDER BY created_at",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, created_at, last_used_at)| PairedClient {
+            id,
+            name,
+            created_at,
+            last_used_at,
+        })
+        .collect())
+}
+
+pub async fn revoke_paired_client(pool: &sqlx::SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM api_tokens WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// 生成新的访问令牌（64 位十六进制）
+fn new_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// 手机扫码页面的访问令牌。首次调用时生成，摘要记入 api_tokens（在已配对列表中显示为「手机扫码页面」），
+/// 明文存入 SecretStore；吊销后或明文丢失时重新生成，之前的二维码随之失效。
+/// 明文保存失败时本次仍返回新令牌，只是下次启动后二维码会再换一次
+pub async fn mobile_token(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+) -> Result<String, sqlx::Error> {
+    let saved: Option<String> =
+        sqlx::query_scalar("SELECT token_hash FROM api_tokens WHERE id = ?")
+            .bind(MOBILE_TOKEN_ID)
+            .fetch_optional(pool)
+            .await?;
+    if let (Some(hash), Some(token)) = (saved, secrets.get(MOBILE_TOKEN_SECRET_KEY)) {
+        if token_hash(&token) == hash {
+            return Ok(token);
+        }
+    }
+    let token = new_token();
+    sqlx::query(
+        "INSERT INTO api_tokens (id, token_hash, name, created_at) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (id) DO UPDATE SET token_hash = excluded.token_hash, created_at = excluded.created_at, last_used_at = NULL",
+    )
+    .bind(MOBILE_TOKEN_ID)
+    .bind(token_hash(&token))
+    .bind(t(Msg::MobilePageClient))
+    .bind(now_millis() as i64)
+    .execute(pool)
+    .await?;
+    if let Err(e) = secrets.set(MOBILE_TOKEN_SECRET_KEY, &token) {
+        eprintln!("mobile_token: {}", e);
+    }
+    Ok(token)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct PairBody {
+    /// 对端界面上显示的配对码
+    pub code: String,
+    /// 本设备名称，显示在对端的已配对设备列表中
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PairResponse {
+    pub token: String,
+    pub device_id: String,
+}
+
+/// 用配对码换取访问令牌
+#[utoipa::path(
+    post,
+    path = "/api/v1/pair",
+    tag = "meta",
+    request_body = PairBody,
+    responses(
+        (status = 200, description = "访问令牌与本机设备 id", body = PairResponse),
+        (status = 403, description = "配对码错误、过期或尝试次数用尽（invalid_pairing_code）", body = ErrorBody),
+    )
+)]
+pub(crate) async fn pair(State(state): State<AppState>, Json(body): Json<PairBody>) -> Response {
+    match consume_pairing_code(&state.pool, &body.code).await {
+        Ok(true) => {}
+        Ok(false) => return ApiError::InvalidPairingCode.into_response(),
+        Err(e) => return ApiError::db("pair", Msg::PairFailed, e).into_response(),
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let token = new_token();
+    let name = match body.name.trim() {
+        "" => t(Msg::UnnamedDevice),
+        n => n,
+    };
+    let saved = sqlx::query(
+        "INSERT INTO api_tokens (id, token_hash, name, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(token_hash(&token))
+    .bind(name)
+    .bind(now_millis() as i64)
+    .execute(&state.pool)
+    .await;
+    let device = device_id(&state.pool).await;
+    match (saved, device) {
+        (Ok(_), Ok(device_id)) => Json(PairResponse { token, device_id }).into_response(),
+        (Err(e), _) | (_, Err(e)) => ApiError::db("pair", Msg::PairFailed, e).into_response(),
+    }
+}
+
+/// 距上次记录超过 LAST_USED_RESOLUTION_MS 时在后台更新令牌的最后使用时间。
+/// 校验只读不写，读请求不必排队等写锁；已配对列表只需要分钟级的最后使用时间
+fn touch_token(pool: &sqlx::SqlitePool, id: String, last_used_at: Option<i64>) {
+    let now = now_millis() as i64;
+    if last_used_at.is_some_and(|t| now - t < LAST_USED_RESOLUTION_MS) {
+        return;
+    }
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let r = sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(&id)
+            .execute(&pool)
+            .await;
+        if let Err(e) = r {
+            eprintln!("touch_token: {}", e);
+        }
+    });
+}
+
+/// 校验请求携带的访问令牌：未携带（missing_token）或无效、已吊销（invalid_token）时返回 401。
+/// 经 /api 兼容别名访问且未携带令牌的多是引入令牌之前缓存的手机页面，改报 client_outdated 提示刷新
+pub(crate) async fn require_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string());
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        let legacy = request
+            .extensions()
+            .get::<OriginalUri>()
+            .is_some_and(|uri| !uri.path().starts_with(API_PREFIX));
+        return if legacy {
+            ApiError::ClientOutdated
+        } else {
+            ApiError::MissingToken
+        }
+        .into_response();
+    };
+    let found: Result<Option<(String, Option<i64>)>, _> =
+        sqlx::query_as("SELECT id, last_used_at FROM api_tokens WHERE token_hash = ?")
+            .bind(token_hash(&token))
+            .fetch_optional(&state.pool)
+            .await;
+    match found {
+        Ok(Some((id, last_used_at))) => {
+            touch_token(&state.pool, id, last_used_at);
+            next.run(request).await
+        }
+        Ok(None) => ApiError::InvalidToken.into_response(),
+        Err(e) => ApiError::db("require_token", Msg::CheckTokenFailed, e).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::spawn_test_server;
+
+    async fn pair_with(base: &str, code: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/api/v1/pair", base))
+            .json(&serde_json::json!({ "code": code, "name": "laptop" }))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    /// 返回状态码与错误码（成功时错误码为空）
+    async fn list_todos(base: &str, token: Option<&str>) -> (u16, String) {
+        let mut req = reqwest::Client::new().get(format!("{}/api/v1/todo", base));
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let resp = req.send().await.unwrap();
+        let status = resp.status().as_u16();
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        (
+            status,
+            body["code"].as_str().unwrap_or_default().to_string(),
+        )
+    }
+
+    async fn error_code(resp: reqwest::Response) -> (u16, String) {
+        let status = resp.status().as_u16();
+        let body: serde_json::Value = resp.json().await.unwrap();
+        (status, body["code"].as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn pairing_codes_are_compared_byte_for_byte() {
+        assert!(constant_time_eq(b"123456", b"123456"));
+        assert!(!constant_time_eq(b"123456", b"123457"));
+        assert!(!constant_time_eq(b"123456", b"12345"));
+    }
+
+    #[tokio::test]
+    async fn data_routes_require_a_valid_token() {
+        let (state, base, _dir) = spawn_test_server().await;
+        assert_eq!(list_todos(&base, None).await, (401, "missing_token".into()));
+        assert_eq!(
+            list_todos(&base, Some("nope")).await,
+            (401, "invalid_token".into())
+        );
+        let version = reqwest::get(format!("{}/api/v1/version", base))
+            .await
+            .unwrap();
+        assert!(version.status().is_success());
+
+        let code = create_pairing_code(&state.pool).await.unwrap().code;
+        let paired: PairResponse = pair_with(&base, &code).await.json().await.unwrap();
+        assert_eq!(list_todos(&base, Some(&paired.token)).await.0, 200);
+
+        // 只存摘要，不存明文
+        let stored: Vec<String> = sqlx::query_scalar("SELECT token_hash FROM api_tokens")
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, vec![token_hash(&paired.token)]);
+
+        // 最后使用时间在后台写入
+        let mut clients = list_paired_clients(&state.pool).await.unwrap();
+        for _ in 0..50 {
+            if clients[0].last_used_at.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            clients = list_paired_clients(&state.pool).await.unwrap();
+        }
+        assert!(clients[0].last_used_at.is_some());
+        revoke_paired_client(&state.pool, &clients[0].id)
+            .await
+            .unwrap();
+        assert_eq!(
+            list_todos(&base, Some(&paired.token)).await,
+            (401, "invalid_token".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn pairing_code_works_once() {
+        let (state, base, _dir) = spawn_test_server().await;
+        let code = create_pairing_code(&state.pool).await.unwrap().code;
+        assert!(pair_with(&base, &code).await.status().is_success());
+        assert_eq!(
+            error_code(pair_with(&base, &code).await).await,
+            (403, "invalid_pairing_code".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_pairing_code_is_rejected() {
+        let (state, base, _dir) = spawn_test_server().await;
+        let code = create_pairing_code(&state.pool).await.unwrap().code;
+        save_pairing_code(
+            &state.pool,
+            &StoredPairingCode {
+                code: code.clone(),
+                expires_at: now_millis() as i64 - 1,
+                attempts: 0,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            error_code(pair_with(&base, &code).await).await,
+            (403, "invalid_pairing_code".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn pairing_code_is_dropped_after_too_many_wrong_guesses() {
+        let (state, base, _dir) = spawn_test_server().await;
+        let code = create_pairing_code(&state.pool).await.unwrap().code;
+        let wrong = if code == "000000" { "111111" } else { "000000" };
+        for _ in 0..PAIRING_CODE_MAX_ATTEMPTS {
+            assert_eq!(pair_with(&base, wrong).await.status().as_u16(), 403);
+        }
+        assert_eq!(pair_with(&base, &code).await.status().as_u16(), 403);
+    }
+
+    #[tokio::test]
+    async fn mobile_token_is_kept_across_calls_and_replaced_after_revoke() {
+        let (state, _base, _dir) = spawn_test_server().await;
+        let first = mobile_token(&state.pool, state.secrets.as_ref())
+            .await
+            .unwrap();
+        let again = mobile_token(&state.pool, state.secrets.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(first, again);
+        revoke_paired_client(&state.pool, MOBILE_TOKEN_ID)
+            .await
+            .unwrap();
+        let replaced = mobile_token(&state.pool, state.secrets.as_ref())
+            .await
+            .unwrap();
+        assert_ne!(first, replaced);
+    }
+
+    #[tokio::test]
+    async fn legacy_alias_without_token_asks_to_reload() {
+        let (state, base, _dir) = spawn_test_server().await;
+        let client = reqwest::Client::new();
+        // 手机上缓存的旧页面走 /api 且不带令牌
+        let resp = client
+            .get(format!("{}/api/todo", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()["Deprecation"], "true");
+        assert_eq!(error_code(resp).await, (401, "client_outdated".into()));
+        // 新前缀仍报 missing_token，带令牌时别名照常可用
+        assert_eq!(list_todos(&base, None).await, (401, "missing_token".into()));
+        let token = mobile_token(&state.pool, state.secrets.as_ref())
+            .await
+            .unwrap();
+        let resp = client
+            .get(format!("{}/api/todo", base))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+    }
+}
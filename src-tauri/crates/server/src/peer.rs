@@ -0,0 +1,385 @@
+//! 点对点同步：用对端设置中生成的配对码与局域网内另一台实例的 Axum 服务配对（POST /api/v1/pair 换取访问令牌），之后定时双向交换变更。
+//! 拉取走对端的 GET /api/v1/changes，推送走对端的 POST /api/v1/sync/merge，两者都带配对得到的令牌。
+//! 令牌与 WebDAV 密码一样存在 SecretStore，不写入数据库（数据库快照会被备份和上传）。
+//! 配对与令牌鉴权和 /api/v1 同时引入，能配对的对端一定提供 /api/v1，因此不使用将被移除的 /api 别名。
+//! 两个方向各自持久化游标，每处理完一页就写回 peers 表，断线或退出后下次从游标处继续。
+//! 冲突逐字段按“最后写入者胜”解决（见 sync::merge_changes）。
+
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+use xy_todo_core::i18n::{self, t, tf, Msg};
+use xy_todo_core::store::SqliteStore;
+use xy_todo_core::{now_millis, token_hash};
+
+use crate::error::ApiError;
+use crate::events::EventSink;
+use crate::pairing::{PairBody, PairResponse};
+use crate::remote::{device_name, normalize_url, UrlKind};
+use crate::secrets::SecretStore;
+use crate::server::API_PREFIX;
+use crate::sync::{load_changes, merge_changes, ChangeSet};
+use crate::AppState;
+
+/// 后台自动同步间隔
+const PEER_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// 单次 HTTP 请求超时
+const PEER_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// 每页交换的变更条数
+const PEER_PAGE_SIZE: i64 = 500;
+
+/// 同一时刻只跑一轮同步，避免后台定时与手动触发同时推进同一游标
+static SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub url: String,
+    pub pull_cursor: i64,
+    pub push_cursor: i64,
+    /// 最近一次成功同步时间（毫秒）
+    pub last_sync_at: Option<i64>,
+    /// 最近一次同步失败的原因，成功后清空
+    pub last_error: Option<String>,
+    /// 对端发放的访问令牌，取自 SecretStore，不返回给前端；升级前配对的对端没有令牌，需要重新配对
+    #[serde(skip)]
+    token: Option<String>,
+}
+
+type PeerRow = (
+    String,
+    String,
+    i64,
+    i64,
+    Option<i64>,
+    Option<String>,
+    Option<String>,
+);
+
+/// 对端令牌在 SecretStore 中的键
+fn token_key(device_id: &str) -> String {
+    format!("peer-token-{}", device_id)
+}
+
+/// 把旧版本写在 peers.token 的明文令牌移入 SecretStore，并从表中清除
+async fn move_legacy_token(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+    device_id: &str,
+    token: &str,
+) -> Result<(), String> {
+    secrets.set(&token_key(device_id), token)?;
+    sqlx::query("UPDATE peers SET token = NULL WHERE device_id = ?")
+        .bind(device_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn peer_from_row(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+    row: PeerRow,
+) -> SyncPeer {
+    let (device_id, url, pull_cursor, push_cursor, last_sync_at, last_error, legacy_token) = row;
+    let token = match legacy_token {
+        Some(token) => {
+            if let Err(e) = move_legacy_token(pool, secrets, &device_id, &token).await {
+                eprintln!("move peer token {}: {}", device_id, e);
+            }
+            Some(token)
+        }
+        None => secrets.get(&token_key(&device_id)),
+    };
+    SyncPeer {
+        device_id,
+        url,
+        pull_cursor,
+        push_cursor,
+        last_sync_at,
+        last_error,
+        token,
+    }
+}
+
+/// 本机设备 id：首次调用时生成并写入 sync_meta
+pub(crate) async fn device_id(pool: &sqlx::SqlitePool) -> Result<String, sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO sync_meta (key, value) VALUES ('device_id', ?)")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .execute(pool)
+        .await?;
+    sqlx::query_scalar("SELECT value FROM sync_meta WHERE key = 'device_id'")
+        .fetch_one(pool)
+        .await
+}
+
+/// 配对握手响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct Hello {
+    pub device_id: String,
+    pub version: String,
+}
+
+/// 返回本机设备 id，供对端配对时识别
+#[utoipa::path(
+    get,
+    path = "/api/v1/sync/hello",
+    tag = "sync",
+    security(("bearer" = [])),
+    responses((status = 200, body = Hello))
+)]
+pub(crate) async fn hello(State(state): State<AppState>) -> impl IntoResponse {
+    match device_id(&state.pool).await {
+        Ok(device_id) => Json(Hello {
+            device_id,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+        .into_response(),
+        Err(e) => ApiError::db("hello", Msg::DeviceInfoFailed, e).into_response(),
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(PEER_REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+pub async fn list_peers(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+) -> Result<Vec<SyncPeer>, sqlx::Error> {
+    let rows: Vec<PeerRow> = sqlx::query_as(
+        "SELECT device_id, url, pull_cursor, push_cursor, last_sync_at, last_error, token FROM peers ORDER BY url",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut peers = Vec::with_capacity(rows.len());
+    for row in rows {
+        peers.push(peer_from_row(pool, secrets, row).await);
+    }
+    Ok(peers)
+}
+
+async fn get_peer(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+    device_id: &str,
+) -> Result<Option<SyncPeer>, sqlx::Error> {
+    let row: Option<PeerRow> = sqlx::query_as(
+        "SELECT device_id, url, pull_cursor, push_cursor, last_sync_at, last_error, token FROM peers WHERE device_id = ?",
+    )
+    .bind(device_id)
+    .fetch_optional(pool)
+    .await?;
+    match row {
+        Some(row) => Ok(Some(peer_from_row(pool, secrets, row).await)),
+        None => Ok(None),
+    }
+}
+
+/// 用对端显示的配对码换取访问令牌并记录配对；同一设备换了地址或重新配对时更新地址与令牌，保留游标
+pub async fn pair(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    secrets: &dyn SecretStore,
+    url: &str,
+    code: &str,
+) -> Result<SyncPeer, String> {
+    let url = normalize_url(url, UrlKind::AppServer, Msg::InvalidPeerUrl)?;
+    let resp = http_client()
+        .post(format!("{}{}/pair", url, API_PREFIX))
+        .header(reqwest::header::ACCEPT_LANGUAGE, i18n::app_locale().tag())
+        .json(&PairBody {
+            code: code.trim().to_string(),
+            name: device_name(),
+        })
+        .send()
+        .await
+        .map_err(|e| tf(Msg::PeerUnreachable, &[&e]))?;
+    if !resp.status().is_success() {
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        let msg = body
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or(t(Msg::PairFailed))
+            .to_string();
+        return Err(msg);
+    }
+    let paired: PairResponse = resp
+        .json()
+        .await
+        .map_err(|e| tf(Msg::PeerBadResponse, &[&e]))?;
+    let own_id = device_id(pool).await.map_err(|e| e.to_string())?;
+    if paired.device_id == own_id {
+        // 填的是本机地址：刚换到的令牌就在本机库里，顺手删掉
+        let _ = sqlx::query("DELETE FROM api_tokens WHERE token_hash = ?")
+            .bind(token_hash(&paired.token))
+            .execute(pool)
+            .await;
+        return Err(t(Msg::PairWithSelf).to_string());
+    }
+    secrets.set(&token_key(&paired.device_id), &paired.token)?;
+    sqlx::query(
+        "INSERT INTO peers (device_id, url) VALUES (?, ?) \
+         ON CONFLICT (device_id) DO UPDATE SET url = excluded.url, token = NULL",
+    )
+    .bind(&paired.device_id)
+    .bind(&url)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let peer = get_peer(pool, secrets, &paired.device_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| t(Msg::PairFailed).to_string())?;
+    {
+        let _guard = SYNC_LOCK.lock().await;
+        let result = sync_peer(pool, events, &peer).await;
+        record_result(pool, &peer.device_id, result).await;
+    }
+    get_peer(pool, secrets, &paired.device_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| t(Msg::PairFailed).to_string())
+}
+
+pub async fn remove_peer(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+    device_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM peers WHERE device_id = ?")
+        .bind(device_id)
+        .execute(pool)
+        .await?;
+    if let Err(e) = secrets.delete(&token_key(device_id)) {
+        eprintln!("remove peer token {}: {}", device_id, e);
+    }
+    Ok(())
+}
+
+/// 与单个对端同步：先拉后推，每页处理完即写回游标
+async fn sync_peer(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    peer: &SyncPeer,
+) -> Result<(), String> {
+    let client = http_client();
+    let Some(token) = peer.token.as_deref() else {
+        return Err(t(Msg::PeerNeedsRepair).to_string());
+    };
+
+    let mut pull_cursor = peer.pull_cursor;
+    loop {
+        let set: ChangeSet = client
+            .get(format!("{}{}/changes", peer.url, API_PREFIX))
+            .bearer_auth(token)
+            .query(&[("since", pull_cursor), ("limit", PEER_PAGE_SIZE)])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| tf(Msg::PullChangesFailed, &[&e]))?
+            .json()
+            .await
+            .map_err(|e| tf(Msg::PeerBadResponse, &[&e]))?;
+        merge_changes(&SqliteStore::new(pool.clone()), events, &set)
+            .await
+            .map_err(|e| e.to_string())?;
+        pull_cursor = set.cursor;
+        sqlx::query("UPDATE peers SET pull_cursor = ? WHERE device_id = ?")
+            .bind(pull_cursor)
+            .bind(&peer.device_id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !set.has_more {
+            break;
+        }
+    }
+
+    let mut push_cursor = peer.push_cursor;
+    loop {
+        let set = load_changes(pool, push_cursor, PEER_PAGE_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        if set.cursor == push_cursor {
+            break;
+        }
+        if !set.is_empty() {
+            client
+                .post(format!("{}{}/sync/merge", peer.url, API_PREFIX))
+                .bearer_auth(token)
+                .json(&set)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| tf(Msg::PushChangesFailed, &[&e]))?;
+        }
+        push_cursor = set.cursor;
+        sqlx::query("UPDATE peers SET push_cursor = ? WHERE device_id = ?")
+            .bind(push_cursor)
+            .bind(&peer.device_id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !set.has_more {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn record_result(pool: &sqlx::SqlitePool, device_id: &str, result: Result<(), String>) {
+    let q = match result {
+        Ok(()) => {
+            sqlx::query("UPDATE peers SET last_sync_at = ?, last_error = NULL WHERE device_id = ?")
+                .bind(now_millis() as i64)
+                .bind(device_id)
+        }
+        Err(e) => {
+            eprintln!("peer sync {}: {}", device_id, e);
+            sqlx::query("UPDATE peers SET last_error = ? WHERE device_id = ?")
+                .bind(e)
+                .bind(device_id)
+        }
+    };
+    if let Err(e) = q.execute(pool).await {
+        eprintln!("record peer sync result: {}", e);
+    }
+}
+
+/// 依次与所有已配对对端同步，单个对端失败只记录错误
+pub async fn sync_all(pool: &sqlx::SqlitePool, events: &dyn EventSink, secrets: &dyn SecretStore) {
+    let _guard = SYNC_LOCK.lock().await;
+    let peers = match list_peers(pool, secrets).await {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("list peers: {}", e);
+            return;
+        }
+    };
+    for peer in peers {
+        let result = sync_peer(pool, events, &peer).await;
+        record_result(pool, &peer.device_id, result).await;
+    }
+}
+
+/// 后台定时同步，随应用生命周期运行
+pub async fn run_sync_loop(
+    pool: sqlx::SqlitePool,
+    events: Arc<dyn EventSink>,
+    secrets: Arc<dyn SecretStore>,
+) {
+    let mut ticker = tokio::time::interval(PEER_SYNC_INTERVAL);
+    loop {
+        ticker.tick().await;
+        sync_all(&pool, events.as_ref(), secrets.as_ref()).await;
+    }
+}
@@ -0,0 +1,174 @@
+//! 远程模式：本机界面直接操作另一台实例（主机）的数据库。
+//! 配置保存在应用数据目录的 remote-server.json（含访问令牌，不放进前端读写的 app-settings.json），
+//! 存在时桌面端跳过本地 SQLite 与本机服务，前端通过 get_api_endpoint 改为请求主机地址。
+//! 这里的函数都以应用数据目录为参数，桌面端与命令行共用。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use xy_todo_core::i18n::{self, t, tf, Msg};
+
+use crate::pairing::PairResponse;
+use crate::server::API_PREFIX;
+use crate::DEFAULT_PORT;
+
+const REMOTE_SERVER_FILENAME: &str = "remote-server.json";
+/// 配对请求超时
+const PAIR_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteServerConfig {
+    /// 主机服务地址，如 http://192.168.1.5:8080
+    pub url: String,
+    pub token: String,
+    /// 主机的设备 id（配对时返回）
+    pub device_id: String,
+}
+
+/// 前端请求数据时使用的地址与令牌
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiEndpoint {
+    pub base_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub remote: bool,
+}
+
+/// 远程模式信息（不含令牌），供设置面板展示
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteServerInfo {
+    pub url: String,
+    pub device_id: String,
+}
+
+fn config_path(app_data: &Path) -> PathBuf {
+    app_data.join(REMOTE_SERVER_FILENAME)
+}
+
+/// 读取远程模式配置；文件不存在或损坏时视为本地模式
+pub fn load_config(app_data: &Path) -> Option<RemoteServerConfig> {
+    let data = std::fs::read_to_string(config_path(app_data)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn save_config(app_data: &Path, config: &RemoteServerConfig) -> Result<(), String> {
+    std::fs::create_dir_all(app_data).map_err(|e| e.to_string())?;
+    let data = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(config_path(app_data), data).map_err(|e| e.to_string())
+}
+
+/// config 为 None（本地模式）时指向本机服务
+pub fn api_endpoint(config: Option<RemoteServerConfig>) -> ApiEndpoint {
+    match config {
+        Some(c) => ApiEndpoint {
+            base_url: c.url,
+            token: Some(c.token),
+            remote: true,
+        },
+        None => ApiEndpoint {
+            base_url: format!("http://127.0.0.1:{}", DEFAULT_PORT),
+            token: None,
+            remote: false,
+        },
+    }
+}
+
+impl From<RemoteServerConfig> for RemoteServerInfo {
+    fn from(c: RemoteServerConfig) -> Self {
+        RemoteServerInfo {
+            url: c.url,
+            device_id: c.device_id,
+        }
+    }
+}
+
+/// 本机名称，配对时提交给主机（或对端）用于在已配对列表中识别
+pub(crate) fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "Douko Todo".to_string())
+}
+
+/// 用户输入地址的补全规则
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum UrlKind {
+    /// 本应用的服务（远程模式的主机、点对点对端）：缺省 http 与默认端口，去掉扫码地址末尾的 /mobile
+    AppServer,
+    /// WebDAV 目录：缺省 https，路径原样保留
+    WebDav,
+}
+
+/// 规范化用户输入的地址：补全协议，去掉末尾的 /；没有主机名时返回 invalid 对应的提示
+pub(crate) fn normalize_url(raw: &str, kind: UrlKind, invalid: Msg) -> Result<String, String> {
+    let trimmed = raw.trim();
+    let has_scheme = trimmed.starts_with("http://") || trimmed.starts_with("https://");
+    let with_scheme = match (has_scheme, kind) {
+        (true, _) => trimmed.to_string(),
+        (false, UrlKind::AppServer) => format!("http://{}", trimmed),
+        (false, UrlKind::WebDav) => format!("https://{}", trimmed),
+    };
+    let mut parsed = url::Url::parse(&with_scheme).map_err(|e| e.to_string())?;
+    if parsed.host_str().is_none() {
+        return Err(t(invalid).to_string());
+    }
+    if matches!(kind, UrlKind::AppServer) && !has_scheme && parsed.port().is_none() {
+        let _ = parsed.set_port(Some(DEFAULT_PORT));
+    }
+    let s = parsed.to_string();
+    let base = s.trim_end_matches('/');
+    let base = match kind {
+        UrlKind::AppServer => base.trim_end_matches("/mobile").trim_end_matches('/'),
+        UrlKind::WebDav => base,
+    };
+    Ok(base.to_string())
+}
+
+/// 用主机上显示的配对码换取令牌并保存配置；需重启应用后生效
+pub async fn connect(app_data: &Path, url: &str, code: &str) -> Result<RemoteServerInfo, String> {
+    let url = normalize_url(url, UrlKind::AppServer, Msg::InvalidHostUrl)?;
+    let client = reqwest::Client::builder()
+        .timeout(PAIR_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .post(format!("{}{}/pair", url, API_PREFIX))
+        .header(reqwest::header::ACCEPT_LANGUAGE, i18n::app_locale().tag())
+        .json(&serde_json::json!({ "code": code.trim(), "name": device_name() }))
+        .send()
+        .await
+        .map_err(|e| tf(Msg::HostUnreachable, &[&e]))?;
+    if !resp.status().is_success() {
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        let msg = body
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or(t(Msg::PairFailed))
+            .to_string();
+        return Err(msg);
+    }
+    let paired: PairResponse = resp
+        .json()
+        .await
+        .map_err(|e| tf(Msg::HostBadResponse, &[&e]))?;
+    let config = RemoteServerConfig {
+        url: url.clone(),
+        token: paired.token,
+        device_id: paired.device_id.clone(),
+    };
+    save_config(app_data, &config)?;
+    Ok(RemoteServerInfo {
+        url,
+        device_id: paired.device_id,
+    })
+}
+
+/// 退出远程模式，恢复使用本机数据库；需重启应用后生效
+pub fn disconnect(app_data: &Path) -> Result<(), String> {
+    match std::fs::remove_file(config_path(app_data)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
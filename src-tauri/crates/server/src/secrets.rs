@@ -0,0 +1,91 @@
+//! 密码等敏感配置的存取：WebDAV 密码、点对点对端发放的访问令牌与手机扫码页面的令牌。桌面端实现为系统钥匙串（见桌面端 secrets.rs），
+//! 无界面模式与命令行没有钥匙串可用（服务器上通常没有 Secret Service），改存数据目录下仅所有者可读的 secrets.json。
+//! 钥匙串依赖放在桌面端，本 crate 不需要 dbus 等系统库即可构建。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use xy_todo_core::i18n::{tf, Msg};
+
+const SECRETS_FILENAME: &str = "secrets.json";
+
+pub trait SecretStore: Send + Sync {
+    /// 读取；不存在或读取失败时为 None
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+    /// 删除；不存在时视为成功
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// 数据目录下的 secrets.json；Unix 上权限为 0600
+pub struct FileSecretStore {
+    path: PathBuf,
+    /// 串行化读改写，避免并发保存互相覆盖
+    lock: Mutex<()>,
+}
+
+impl FileSecretStore {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(SECRETS_FILENAME),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn load(&self) -> BTreeMap<String, String> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, secrets: &BTreeMap<String, String>) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(secrets).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("json.tmp");
+        write_private(&tmp, data.as_bytes())
+            .and_then(|()| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| tf(Msg::SavePasswordFailed, &[&e]))
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(data)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, data)
+}
+
+impl SecretStore for FileSecretStore {
+    fn get(&self, key: &str) -> Option<String> {
+        let _guard = self.lock.lock().ok()?;
+        self.load().remove(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        let mut secrets = self.load();
+        secrets.insert(key.to_string(), value.to_string());
+        self.save(&secrets)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        let mut secrets = self.load();
+        if secrets.remove(key).is_none() {
+            return Ok(());
+        }
+        self.save(&secrets)
+    }
+}
@@ -0,0 +1,684 @@
+#[allow(unused_imports)]
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{
+        header::{ACCEPT_LANGUAGE, LINK},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tower_http::cors::{AllowHeaders, Any, CorsLayer};
+use tower_http::services::ServeDir;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{IntoParams, Modify, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+use xy_todo_core::i18n::{self, t, Msg};
+use xy_todo_core::Todo;
+
+use crate::error::{ApiError, ErrorBody};
+use crate::{crud, pairing, peer, sync, AppState, DEFAULT_PORT};
+
+pub use xy_todo_core::{CreateTodoBody, GroupItem, UpdateTodoBody};
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct ReorderBody {
+    pub ordered_ids: Vec<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListTodosQuery {
+    /// comprehensive（默认）| importance | deadline
+    #[serde(default)]
+    sort: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct OkResponse {
+    pub ok: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ContentSuggestionsQuery {
+    /// 已输入的内容，去掉首尾空白后为空时返回空列表
+    #[serde(default)]
+    q: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct MobileUrlResponse {
+    /// 手机扫码地址，附带手机页面的访问令牌
+    pub url: String,
+}
+
+/// 当前 API 主版本，路由挂载在 /api/v1。不兼容的响应结构调整只能放到下一个主版本（/api/v2），
+/// 旧主版本至少再保留一个应用大版本，期间响应带 Deprecation 头。
+pub(crate) const API_VERSION: u32 = 1;
+pub(crate) const API_PREFIX: &str = "/api/v1";
+/// 兼容别名：引入版本号之前的客户端（以及手机上缓存的旧页面）使用的无版本前缀，路由与 /api/v1 相同，已弃用
+const LEGACY_API_PREFIX: &str = "/api";
+
+/// 版本握手响应
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    /// 应用版本
+    pub app_version: String,
+    /// 当前 API 主版本
+    pub api_version: u32,
+    /// 仍可使用的 API 主版本
+    pub supported_api_versions: Vec<u32>,
+    /// 已弃用、将在后续版本移除的路径前缀
+    pub deprecated_prefixes: Vec<String>,
+}
+
+/// 版本握手：客户端据此判断自己使用的 API 版本是否仍受支持
+#[utoipa::path(
+    get,
+    path = "/api/v1/version",
+    tag = "meta",
+    responses((status = 200, body = VersionInfo))
+)]
+async fn version() -> Json<VersionInfo> {
+    Json(VersionInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        api_version: API_VERSION,
+        supported_api_versions: vec![API_VERSION],
+        deprecated_prefixes: vec![LEGACY_API_PREFIX.to_string()],
+    })
+}
+
+/// 按 Accept-Language 协商语言，作用于整个请求（包括错误响应的生成）
+pub(crate) async fn negotiate_locale(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(i18n::Locale::from_accept_language)
+        .unwrap_or_else(i18n::app_locale);
+    i18n::scope_request_locale(locale, next.run(request)).await
+}
+
+/// 经 /api 兼容别名访问时，在响应上标记弃用并通过 Link 指向 /api/v1 下的对应地址
+async fn mark_deprecated(request: Request, next: Next) -> Response {
+    let successor = format!(
+        "<{}{}>; rel=\"successor-version\"",
+        API_PREFIX,
+        request.uri().path()
+    );
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("Deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(LINK, link);
+    }
+    response
+}
+
+/// 服务监听端口与静态资源目录；桌面端使用默认值，无界面模式可通过命令行参数指定
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub port: u16,
+    /// 前端构建产物目录（dist），/mobile 与静态资源从这里读取
+    pub static_dir: PathBuf,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            static_dir: static_dir(),
+        }
+    }
+}
+
+/// 手机扫码地址（局域网 IP），附带手机页面的访问令牌（见 pairing::mobile_token）
+pub fn mobile_page_url(port: u16, token: &str) -> String {
+    let ip = local_ip_address::local_ip()
+        .unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)));
+    format!("http://{}:{}/mobile?token={}", ip, port, token)
+}
+
+/// 返回手机扫码地址（局域网 IP），远程模式的桌面端直接 fetch 主机的这个接口
+#[utoipa::path(
+    get,
+    path = "/api/v1/mobile-url",
+    tag = "meta",
+    security(("bearer" = [])),
+    responses((status = 200, body = MobileUrlResponse))
+)]
+async fn mobile_url(state: AppState, port: u16) -> Result<Json<MobileUrlResponse>, ApiError> {
+    let token = pairing::mobile_token(&state.pool, state.secrets.as_ref())
+        .await
+        .map_err(|e| ApiError::db("mobile_url", Msg::MobileTokenFailed, e))?;
+    Ok(Json(MobileUrlResponse {
+        url: mobile_page_url(port, &token),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todo/content-suggestions",
+    tag = "todo",
+    security(("bearer" = [])),
+    params(ContentSuggestionsQuery),
+    responses((status = 200, description = "包含输入内容的历史任务内容", body = [String]))
+)]
+async fn content_suggestions(
+    State(state): State<AppState>,
+    Query(q): Query<ContentSuggestionsQuery>,
+) -> impl IntoResponse {
+    match crud::content_suggestions(&state, &q.q).await {
+        Ok(rows) => Json(rows).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todo",
+    tag = "todo",
+    security(("bearer" = [])),
+    params(ListTodosQuery),
+    responses((status = 200, description = "全部任务", body = [Todo]))
+)]
+async fn list_todos(
+    State(state): State<AppState>,
+    Query(q): Query<ListTodosQuery>,
+) -> impl IntoResponse {
+    let todos = match crud::list_todos(&state, q.sort.as_str()).await {
+        Ok(t) => t,
+        Err(e) => return e.into_response(),
+    };
+    Json(todos).into_response()
+}
+
+/// 幂等键请求头；重复提交同一个键时返回首次创建的结果
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// 重放响应附带的标记头，便于客户端区分
+const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+fn replayed_response<T: serde::Serialize>(body: T) -> Response {
+    (
+        StatusCode::CREATED,
+        [(IDEMPOTENT_REPLAYED_HEADER, "true")],
+        Json(body),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/todo",
+    tag = "todo",
+    security(("bearer" = [])),
+    request_body = CreateTodoBody,
+    params(("Idempotency-Key" = Option<String>, Header, description = "幂等键；重复提交时返回首次结果并附 Idempotent-Replayed: true")),
+    responses(
+        (status = 201, description = "新建的任务（或重放的首次结果）", body = Todo),
+        (status = 400, description = "参数校验失败（validation_failed）或分组不存在（unknown_group）", body = ErrorBody),
+        (status = 409, description = "客户端 id 已用于内容或分组不同的任务（todo_id_conflict）", body = ErrorBody),
+        (status = 422, description = "同一个幂等键换了请求内容（idempotency_key_reused）", body = ErrorBody),
+    )
+)]
+async fn create_todo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<CreateTodoBody>,
+) -> Result<Response, ApiError> {
+    let idempotency_key = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(v) => Some(
+            v.to_str()
+                .ok()
+                .and_then(crud::client_key)
+                .ok_or(ApiError::InvalidIdempotencyKey)?,
+        ),
+        None => None,
+    };
+    let created = crud::create_todo(&state, &body, idempotency_key).await?;
+    if created.replayed {
+        return Ok(replayed_response(created.todo));
+    }
+    Ok((StatusCode::CREATED, Json(created.todo)).into_response())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/todo/{id}",
+    tag = "todo",
+    security(("bearer" = [])),
+    params(("id" = String, Path, description = "任务 id")),
+    request_body = UpdateTodoBody,
+    responses(
+        (status = 200, description = "更新后的任务", body = Todo),
+        (status = 400, description = "参数校验失败（validation_failed）或分组不存在（unknown_group）", body = ErrorBody),
+        (status = 404, description = "任务不存在（todo_not_found）", body = ErrorBody),
+    )
+)]
+async fn update_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateTodoBody>,
+) -> Result<Json<Todo>, ApiError> {
+    crud::update_todo(&state, &id, &body).await.map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/todo/{id}",
+    tag = "todo",
+    security(("bearer" = [])),
+    params(("id" = String, Path, description = "任务 id")),
+    responses(
+        (status = 200, body = OkResponse),
+        (status = 404, description = "任务不存在（todo_not_found）", body = ErrorBody),
+    )
+)]
+async fn delete_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<OkResponse>, ApiError> {
+    crud::delete_todo(&state, &id).await?;
+    Ok(Json(OkResponse { ok: true }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/todo/reorder",
+    tag = "todo",
+    security(("bearer" = [])),
+    request_body = ReorderBody,
+    responses((status = 200, description = "按给定顺序重写排序值，不存在的 id 忽略", body = OkResponse))
+)]
+async fn reorder_todos(
+    State(state): State<AppState>,
+    Json(body): Json<ReorderBody>,
+) -> Result<Json<OkResponse>, ApiError> {
+    crud::reorder_todos(&state, &body.ordered_ids).await?;
+    Ok(Json(OkResponse { ok: true }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups",
+    tag = "groups",
+    security(("bearer" = [])),
+    responses((status = 200, description = "全部分组（按排序值）", body = [GroupItem]))
+)]
+async fn list_groups(State(state): State<AppState>) -> impl IntoResponse {
+    match crud::list_groups(&state).await {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct CreateGroupBody {
+    pub name: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups",
+    tag = "groups",
+    security(("bearer" = [])),
+    request_body = CreateGroupBody,
+    responses(
+        (status = 201, description = "新建的分组", body = GroupItem),
+        (status = 400, description = "分组名称为空（validation_failed）", body = ErrorBody),
+    )
+)]
+async fn create_group(
+    State(state): State<AppState>,
+    Json(body): Json<CreateGroupBody>,
+) -> Result<Response, ApiError> {
+    let group = crud::create_group(&state, &body.name).await?;
+    Ok((StatusCode::CREATED, Json(group)).into_response())
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct UpdateGroupBody {
+    pub name: String,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/groups/{id}",
+    tag = "groups",
+    security(("bearer" = [])),
+    params(("id" = String, Path, description = "分组 id")),
+    request_body = UpdateGroupBody,
+    responses(
+        (status = 200, description = "重命名后的分组", body = GroupItem),
+        (status = 400, description = "分组名称为空（validation_failed）", body = ErrorBody),
+        (status = 404, description = "分组不存在（group_not_found）", body = ErrorBody),
+    )
+)]
+async fn update_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateGroupBody>,
+) -> Result<Json<GroupItem>, ApiError> {
+    crud::update_group(&state, &id, &body.name).await.map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/groups/{id}",
+    tag = "groups",
+    security(("bearer" = [])),
+    params(("id" = String, Path, description = "分组 id")),
+    responses(
+        (status = 200, description = "删除分组，其下任务变为未分组", body = OkResponse),
+        (status = 404, description = "分组不存在（group_not_found）", body = ErrorBody),
+    )
+)]
+async fn delete_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<OkResponse>, ApiError> {
+    crud::delete_group(&state, &id).await?;
+    Ok(Json(OkResponse { ok: true }))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct ReorderGroupsBody {
+    pub ordered_ids: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups/reorder",
+    tag = "groups",
+    security(("bearer" = [])),
+    request_body = ReorderGroupsBody,
+    responses((status = 200, description = "按给定顺序重写分组排序值", body = OkResponse))
+)]
+async fn reorder_groups(
+    State(state): State<AppState>,
+    Json(body): Json<ReorderGroupsBody>,
+) -> Result<Json<OkResponse>, ApiError> {
+    crud::reorder_groups(&state, &body.ordered_ids).await?;
+    Ok(Json(OkResponse { ok: true }))
+}
+
+fn static_dir() -> PathBuf {
+    let manifest = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+    PathBuf::from(manifest).join("../dist")
+}
+
+async fn serve_index(path: PathBuf) -> Response {
+    match tokio::fs::read_to_string(&path).await {
+        Ok(html) => (StatusCode::OK, [("Content-Type", "text/html")], html).into_response(),
+        Err(_) => {
+            let fallback = format!(
+                r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1"><title>{title}</title></head>
+<body style="font-family:sans-serif;padding:20px;max-width:400px;margin:0 auto;">
+  <h2>{heading}</h2>
+  <p>{build}<code>pnpm run build</code></p>
+  <p>{refresh}</p>
+</body>
+</html>"#,
+                lang = i18n::current().tag(),
+                title = t(Msg::MobilePageTitle),
+                heading = t(Msg::DevModeHeading),
+                build = t(Msg::DevModeBuildHint),
+                refresh = t(Msg::DevModeRefreshHint),
+            );
+            (
+                StatusCode::OK,
+                [("Content-Type", "text/html; charset=utf-8")],
+                fallback,
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 局域网 API 的 OpenAPI 描述，由各处理函数上的 `#[utoipa::path]` 与请求/响应类型生成，
+/// 通过 /api/v1/openapi.json 提供，/api/v1/docs 为内嵌的 Swagger UI；/api 别名下同样提供一份
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Douko Todo List LAN API"),
+    modifiers(&BearerAuth),
+    paths(
+        version,
+        pairing::pair,
+        mobile_url,
+        content_suggestions,
+        list_todos,
+        create_todo,
+        update_todo,
+        delete_todo,
+        reorder_todos,
+        list_groups,
+        create_group,
+        update_group,
+        delete_group,
+        reorder_groups,
+        sync::list_changes,
+        sync::apply_sync,
+        peer::hello,
+        sync::merge
+    ),
+    tags(
+        (name = "meta", description = "版本握手与配对"),
+        (name = "todo", description = "任务"),
+        (name = "groups", description = "分组"),
+        (name = "sync", description = "离线回放与点对点同步")
+    )
+)]
+pub(crate) struct ApiDoc;
+
+/// 注册 bearer 安全方案：除版本握手与配对外，接口都要求 `Authorization: Bearer <令牌>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "配对（POST /api/v1/pair）换取的访问令牌或手机页面令牌；未携带返回 401 missing_token，无效或已吊销返回 401 invalid_token",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// 组装完整的路由：/api/v1 与兼容别名 /api、手机页面、接口文档与静态资源
+pub(crate) fn app_router(state: AppState, config: &ServerConfig) -> Router {
+    // Authorization 不在通配符 * 的覆盖范围内，改为回显预检请求中的请求头
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(AllowHeaders::mirror_request());
+
+    // 版本握手与配对不要求令牌，其余接口见 pairing::require_token
+    let protected = Router::new()
+        .route("/mobile-url", {
+            let port = config.port;
+            get(move |State(state): State<AppState>| mobile_url(state, port))
+        })
+        .route("/todo/reorder", post(reorder_todos))
+        .route("/todo/content-suggestions", get(content_suggestions))
+        .route("/todo", get(list_todos).post(create_todo))
+        .route("/todo/:id", patch(update_todo).delete(delete_todo))
+        .route("/groups", get(list_groups).post(create_group))
+        .route("/groups/reorder", post(reorder_groups))
+        .route("/groups/:id", patch(update_group).delete(delete_group))
+        .route("/changes", get(sync::list_changes))
+        .route("/sync", post(sync::apply_sync))
+        .route("/sync/hello", get(peer::hello))
+        .route("/sync/merge", post(sync::merge))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            pairing::require_token,
+        ));
+    let api = Router::new()
+        .route("/version", get(version))
+        .route("/pair", post(pairing::pair))
+        .merge(protected)
+        .with_state(state.clone());
+    let legacy_api = api
+        .clone()
+        .layer(axum::middleware::from_fn(mark_deprecated));
+
+    let index = config.static_dir.join("index.html");
+    let serve_dir = ServeDir::new(&config.static_dir);
+    Router::new()
+        .nest(API_PREFIX, api)
+        .nest(LEGACY_API_PREFIX, legacy_api)
+        .route("/mobile", {
+            let index = index.clone();
+            get(move || serve_index(index.clone()))
+        })
+        .route("/mobile/*_", get(move || serve_index(index.clone())))
+        .merge(SwaggerUi::new("/api/v1/docs").url("/api/v1/openapi.json", ApiDoc::openapi()))
+        .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi()))
+        .fallback_service(serve_dir)
+        .layer(axum::middleware::from_fn(negotiate_locale))
+        .layer(cors)
+}
+
+/// 测试用：在内存数据库上启动完整的路由，返回状态与服务地址（如 http://127.0.0.1:12345）。
+/// 返回的临时目录存放 secrets.json，须在测试结束前保持存活
+#[cfg(test)]
+pub(crate) async fn spawn_test_server() -> (AppState, String, tempfile::TempDir) {
+    let store = xy_todo_core::store::SqliteStore::in_memory().await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let state = AppState::new(
+        store.pool().clone(),
+        std::sync::Arc::new(crate::events::NoopEventSink),
+        std::sync::Arc::new(crate::secrets::FileSecretStore::new(dir.path())),
+    );
+    let app = app_router(state.clone(), &ServerConfig::default());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (state, format!("http://{}", addr), dir)
+}
+
+/// 绑定服务端口；端口被占用等失败时返回错误
+pub(crate) async fn bind(config: &ServerConfig) -> Result<tokio::net::TcpListener, String> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Axum bind {} failed: {}", addr, e))
+}
+
+/// 在已绑定的端口上提供服务；shutdown 完成后不再接收新连接，等处理中的请求结束后返回
+pub(crate) async fn serve(
+    state: AppState,
+    config: ServerConfig,
+    listener: tokio::net::TcpListener,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    let app = app_router(state.clone(), &config);
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let token = pairing::mobile_token(&state.pool, state.secrets.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mobile_url = mobile_page_url(addr.port(), &token);
+    eprintln!("serving on {} ({})", addr, mobile_url);
+    state
+        .events
+        .emit("web-server-url", serde_json::Value::String(mobile_url));
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_covers_every_route_and_declares_bearer_auth() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/api/v1/version",
+            "/api/v1/pair",
+            "/api/v1/mobile-url",
+            "/api/v1/todo",
+            "/api/v1/todo/{id}",
+            "/api/v1/todo/reorder",
+            "/api/v1/todo/content-suggestions",
+            "/api/v1/groups",
+            "/api/v1/groups/{id}",
+            "/api/v1/groups/reorder",
+            "/api/v1/changes",
+            "/api/v1/sync",
+            "/api/v1/sync/hello",
+            "/api/v1/sync/merge",
+        ] {
+            assert!(paths.contains_key(path), "{} missing from spec", path);
+        }
+        assert_eq!(
+            spec["components"]["securitySchemes"]["bearer"]["scheme"],
+            "bearer"
+        );
+        assert!(paths["/api/v1/version"]["get"]["security"].is_null());
+        assert!(paths["/api/v1/todo"]["get"]["security"].is_array());
+    }
+
+    #[tokio::test]
+    async fn spec_is_served_under_both_prefixes() {
+        let (_state, base, _dir) = spawn_test_server().await;
+        for prefix in [API_PREFIX, LEGACY_API_PREFIX] {
+            let spec: serde_json::Value = reqwest::get(format!("{}{}/openapi.json", base, prefix))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert!(spec["paths"]["/api/v1/todo"].is_object());
+            let docs = reqwest::get(format!("{}{}/docs/", base, prefix))
+                .await
+                .unwrap();
+            assert!(
+                docs.status().is_success(),
+                "{}/docs/: {}",
+                prefix,
+                docs.status()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_follow_accept_language() {
+        let (_state, base, _dir) = spawn_test_server().await;
+        let client = reqwest::Client::new();
+        for (header, locale) in [
+            ("en-US,en;q=0.9", i18n::Locale::En),
+            ("ja-JP", i18n::Locale::Ja),
+            ("zh-CN", i18n::Locale::ZhCn),
+        ] {
+            let body: serde_json::Value = client
+                .get(format!("{}{}/todo", base, API_PREFIX))
+                .header(ACCEPT_LANGUAGE, header)
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(body["code"], "missing_token");
+            assert_eq!(body["error"], Msg::MissingToken.text(locale), "{}", header);
+        }
+    }
+}
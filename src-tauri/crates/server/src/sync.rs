@@ -0,0 +1,750 @@
+//! 增量同步：change_log 表由触发器维护，每次增删改都会为对应实体分配一个新的单调序号（seq）。
+//! 客户端保存上次拿到的 cursor，通过 GET /api/changes?since=<cursor> 拉取之后的新增/修改与删除（墓碑），
+//! 离线期间的操作排队后通过 POST /api/sync 一次性回放。
+//! 桌面端之间的点对点同步则交换带时间戳的 ChangeSet，经 POST /api/sync/merge 逐字段“最后写入者胜”合并：
+//! 每个字段的修改时间记在 field_clock 表，两端改了同一任务的不同字段时各自保留（合并逻辑见 xy_todo_core::merge）。
+//! 回放与合并都经 TodoStore 写入，这里只负责协议与界面通知。
+
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+use xy_todo_core::i18n::Msg;
+use xy_todo_core::merge::group_from_row;
+use xy_todo_core::store::{todo_from_row, TodoRow, TodoStore, TodoUpdate};
+use xy_todo_core::validation;
+
+pub(crate) use xy_todo_core::merge::{ChangeSet, Changed, FieldTimes, Tombstone};
+
+use crate::crud::client_key;
+use crate::error::ApiError;
+use crate::events::EventSink;
+use crate::server::{CreateTodoBody, UpdateTodoBody};
+use crate::AppState;
+
+/// 单次拉取的默认/最大条数
+const DEFAULT_CHANGES_LIMIT: i64 = 500;
+const MAX_CHANGES_LIMIT: i64 = 5000;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ChangesQuery {
+    /// 上次拉取返回的 cursor，首次为 0
+    #[serde(default)]
+    since: i64,
+    /// 本页最多条数，默认 500，最大 5000
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// 推送 groups-changed / todos-changed；删除过分组时任务范围未知，ids 留空让界面整体刷新
+pub(crate) fn emit_changed(changed: Changed, events: &dyn EventSink) {
+    if !changed.groups.is_empty() {
+        events.groups_changed(changed.groups);
+    }
+    if changed.groups_deleted {
+        events.todos_changed(Vec::new());
+    } else if !changed.todos.is_empty() {
+        events.todos_changed(changed.todos);
+    }
+}
+
+/// 一批实体的字段修改时间
+async fn load_field_times<'a>(
+    pool: &sqlx::SqlitePool,
+    entity: &str,
+    ids: impl Iterator<Item = &'a String>,
+) -> Result<Vec<FieldTimes>, sqlx::Error> {
+    let ids = serde_json::to_string(&ids.collect::<Vec<_>>()).unwrap_or_default();
+    let rows: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT entity_id, field, updated_at FROM field_clock \
+         WHERE entity = ? AND entity_id IN (SELECT value FROM json_each(?)) ORDER BY entity_id",
+    )
+    .bind(entity)
+    .bind(ids)
+    .fetch_all(pool)
+    .await?;
+    let mut out: Vec<FieldTimes> = Vec::new();
+    for (id, field, updated_at) in rows {
+        match out.last_mut() {
+            Some(last) if last.id == id => {
+                last.times.insert(field, updated_at);
+            }
+            _ => out.push(FieldTimes {
+                id,
+                times: BTreeMap::from([(field, updated_at)]),
+            }),
+        }
+    }
+    Ok(out)
+}
+
+/// 读取 since 之后的一页变更。同一实体只保留最新一条记录，因此 upsert 返回的是当前行数据。
+/// 本页任务引用的分组即使变更排在后面的页也一并附上，接收方先合并分组再合并任务，不会因分组尚未到达而丢掉 group_id。
+pub(crate) async fn load_changes(
+    pool: &sqlx::SqlitePool,
+    since: i64,
+    limit: i64,
+) -> Result<ChangeSet, sqlx::Error> {
+    load_page(pool, since, limit, false).await
+}
+
+/// 同 load_changes，但跳过从共享目录导入的变更（change_log.origin 非空）：
+/// 各设备都直接读取原始日志，导出本机日志时不需要再转写一遍
+pub(crate) async fn load_local_changes(
+    pool: &sqlx::SqlitePool,
+    since: i64,
+    limit: i64,
+) -> Result<ChangeSet, sqlx::Error> {
+    load_page(pool, since, limit, true).await
+}
+
+async fn load_page(
+    pool: &sqlx::SqlitePool,
+    since: i64,
+    limit: i64,
+    local_only: bool,
+) -> Result<ChangeSet, sqlx::Error> {
+    let limit = limit.clamp(1, MAX_CHANGES_LIMIT);
+    let mut rows: Vec<(i64, String, String, bool, Option<i64>)> = sqlx::query_as(
+        "SELECT seq, entity, entity_id, deleted, deleted_at FROM change_log \
+         WHERE seq > ? AND (? = 0 OR origin IS NULL) ORDER BY seq LIMIT ?",
+    )
+    .bind(since)
+    .bind(local_only)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let cursor = rows.last().map(|r| r.0).unwrap_or(since);
+
+    let mut set = ChangeSet {
+        cursor,
+        has_more,
+        ..Default::default()
+    };
+    for (_, entity, entity_id, deleted, deleted_at) in &rows {
+        if !*deleted {
+            continue;
+        }
+        let tombstone = Tombstone {
+            id: entity_id.clone(),
+            deleted_at: deleted_at.unwrap_or(0),
+        };
+        match entity.as_str() {
+            "todo" => set.deleted_todos.push(tombstone),
+            "group" => set.deleted_groups.push(tombstone),
+            _ => {}
+        }
+    }
+
+    let todo_rows: Vec<TodoRow> = sqlx::query_as(
+        "SELECT t.id, t.content, t.status, t.reminder_time, t.start_time, t.end_time, t.importance, t.group_id, t.sort_order, t.updated_at \
+         FROM change_log c JOIN todos t ON t.id = c.entity_id \
+         WHERE c.entity = 'todo' AND c.deleted = 0 AND c.seq > ? AND c.seq <= ? \
+         AND (? = 0 OR c.origin IS NULL) ORDER BY c.seq",
+    )
+    .bind(since)
+    .bind(cursor)
+    .bind(local_only)
+    .fetch_all(pool)
+    .await?;
+    set.todos = todo_rows.into_iter().map(todo_from_row).collect();
+
+    let group_rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+        "SELECT g.id, g.name, COALESCE(g.sort_order, 0), g.updated_at \
+         FROM change_log c JOIN groups g ON g.id = c.entity_id \
+         WHERE c.entity = 'group' AND c.deleted = 0 AND c.seq > ? AND c.seq <= ? \
+         AND (? = 0 OR c.origin IS NULL) ORDER BY c.seq",
+    )
+    .bind(since)
+    .bind(cursor)
+    .bind(local_only)
+    .fetch_all(pool)
+    .await?;
+    set.groups = group_rows.into_iter().map(group_from_row).collect();
+
+    let mut referenced: Vec<&String> = set
+        .todos
+        .iter()
+        .filter_map(|t| t.group_id.as_ref())
+        .filter(|g| !set.groups.iter().any(|x| &x.id == *g))
+        .collect();
+    referenced.sort();
+    referenced.dedup();
+    if !referenced.is_empty() {
+        let ids = serde_json::to_string(&referenced).unwrap_or_default();
+        let extra: Vec<(String, String, i64, i64)> = sqlx::query_as(
+            "SELECT id, name, COALESCE(sort_order, 0), updated_at FROM groups \
+             WHERE id IN (SELECT value FROM json_each(?))",
+        )
+        .bind(ids)
+        .fetch_all(pool)
+        .await?;
+        set.groups.extend(extra.into_iter().map(group_from_row));
+    }
+
+    set.todo_fields = load_field_times(pool, "todo", set.todos.iter().map(|t| &t.id)).await?;
+    set.group_fields = load_field_times(pool, "group", set.groups.iter().map(|g| &g.id)).await?;
+    Ok(set)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/changes",
+    tag = "sync",
+    security(("bearer" = [])),
+    params(ChangesQuery),
+    responses((status = 200, description = "since 之后的一页变更", body = ChangeSet))
+)]
+pub(crate) async fn list_changes(
+    State(state): State<AppState>,
+    Query(q): Query<ChangesQuery>,
+) -> impl IntoResponse {
+    let limit = q.limit.unwrap_or(DEFAULT_CHANGES_LIMIT);
+    match load_changes(&state.pool, q.since, limit).await {
+        Ok(set) => Json(set).into_response(),
+        Err(e) => ApiError::db("list_changes", Msg::LoadChangesFailed, e).into_response(),
+    }
+}
+
+/// 客户端离线期间排队的一条操作。新建操作必须带客户端生成的 id，重放时据此去重。
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum SyncOp {
+    CreateTodo(CreateTodoBody),
+    UpdateTodo {
+        id: String,
+        #[serde(flatten)]
+        body: UpdateTodoBody,
+    },
+    DeleteTodo {
+        id: String,
+    },
+    ReorderTodos {
+        ordered_ids: Vec<String>,
+    },
+    CreateGroup {
+        id: String,
+        name: String,
+    },
+    UpdateGroup {
+        id: String,
+        name: String,
+    },
+    DeleteGroup {
+        id: String,
+    },
+    ReorderGroups {
+        ordered_ids: Vec<String>,
+    },
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct SyncBody {
+    /// 客户端当前 cursor；提供时响应中附带回放后的变更，客户端据此完成对账
+    #[serde(default)]
+    since: Option<i64>,
+    #[serde(default)]
+    ops: Vec<SyncOp>,
+}
+
+/// 单条操作的结果；失败时 code/error 与 HTTP 接口的错误响应含义相同
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct SyncOpResult {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl SyncOpResult {
+    fn ok() -> Self {
+        Self {
+            ok: true,
+            code: None,
+            error: None,
+        }
+    }
+
+    fn err(e: ApiError) -> Self {
+        Self {
+            ok: false,
+            code: Some(e.code()),
+            error: Some(e.message()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct SyncResponse {
+    results: Vec<SyncOpResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<ChangeSet>,
+}
+
+/// 回放一条操作。重复的新建、删除已不存在的实体都视为成功，保证队列可安全重试。
+async fn apply_op(
+    store: &dyn TodoStore,
+    op: &SyncOp,
+    changed: &mut Changed,
+) -> Result<SyncOpResult, sqlx::Error> {
+    let result = match op {
+        SyncOp::CreateTodo(body) => match body.id.as_deref().and_then(client_key) {
+            None => SyncOpResult::err(ApiError::invalid("id", Msg::MissingTodoId)),
+            Some(id) => match validation::new_todo(body) {
+                Err(errors) => SyncOpResult::err(errors.into()),
+                Ok(new) => {
+                    if store.create_todo_if_absent(id, &new).await? {
+                        changed.todos.push(id.to_string());
+                    }
+                    SyncOpResult::ok()
+                }
+            },
+        },
+        SyncOp::UpdateTodo { id, body } => match store.update_todo(id, body).await? {
+            TodoUpdate::Updated(_) => {
+                changed.todos.push(id.clone());
+                SyncOpResult::ok()
+            }
+            TodoUpdate::NotFound => SyncOpResult::err(ApiError::TodoNotFound),
+            TodoUpdate::Invalid(errors) => SyncOpResult::err(errors.into()),
+        },
+        SyncOp::DeleteTodo { id } => {
+            if store.delete_todo(id).await? {
+                changed.todos.push(id.clone());
+            }
+            SyncOpResult::ok()
+        }
+        SyncOp::ReorderTodos { ordered_ids } => {
+            store.reorder_todos(ordered_ids).await?;
+            changed.todos.extend(ordered_ids.iter().cloned());
+            SyncOpResult::ok()
+        }
+        SyncOp::CreateGroup { id, name } => {
+            let name = name.trim();
+            if client_key(id).is_none() {
+                SyncOpResult::err(ApiError::invalid("id", Msg::MissingGroupId))
+            } else if name.is_empty() {
+                SyncOpResult::err(ApiError::invalid("name", Msg::GroupNameEmpty))
+            } else {
+                if store.create_group_if_absent(id, name).await? {
+                    changed.groups.push(id.clone());
+                }
+                SyncOpResult::ok()
+            }
+        }
+        SyncOp::UpdateGroup { id, name } => {
+            let name = name.trim();
+            if name.is_empty() {
+                SyncOpResult::err(ApiError::invalid("name", Msg::GroupNameEmpty))
+            } else if store.rename_group(id, name).await? {
+                changed.groups.push(id.clone());
+                SyncOpResult::ok()
+            } else {
+                SyncOpResult::err(ApiError::GroupNotFound)
+            }
+        }
+        SyncOp::DeleteGroup { id } => {
+            if store.delete_group(id).await? {
+                changed.groups.push(id.clone());
+                changed.groups_deleted = true;
+            }
+            SyncOpResult::ok()
+        }
+        SyncOp::ReorderGroups { ordered_ids } => {
+            store.reorder_groups(ordered_ids).await?;
+            changed.groups.extend(ordered_ids.iter().cloned());
+            SyncOpResult::ok()
+        }
+    };
+    Ok(result)
+}
+
+/// 按顺序回放离线操作，逐条返回结果；单条失败不影响后续操作
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync",
+    tag = "sync",
+    security(("bearer" = [])),
+    request_body = SyncBody,
+    responses((status = 200, description = "逐条操作的结果；请求带 since 时附带回放后的变更", body = SyncResponse))
+)]
+pub(crate) async fn apply_sync(
+    State(state): State<AppState>,
+    Json(body): Json<SyncBody>,
+) -> impl IntoResponse {
+    let mut results = Vec::with_capacity(body.ops.len());
+    let mut changed = Changed::default();
+    for op in &body.ops {
+        match apply_op(state.store.as_ref(), op, &mut changed).await {
+            Ok(r) => results.push(r),
+            Err(e) => results.push(SyncOpResult::err(ApiError::db(
+                "apply_sync",
+                Msg::SyncOpFailed,
+                e,
+            ))),
+        }
+    }
+    emit_changed(changed, state.events.as_ref());
+    let changes = match body.since {
+        Some(since) => match load_changes(&state.pool, since, DEFAULT_CHANGES_LIMIT).await {
+            Ok(set) => Some(set),
+            Err(e) => return ApiError::db("apply_sync", Msg::LoadChangesFailed, e).into_response(),
+        },
+        None => None,
+    };
+    Json(SyncResponse { results, changes }).into_response()
+}
+
+/// 合并对端变更并通知界面，返回实际写入的条数
+pub(crate) async fn merge_changes(
+    store: &dyn TodoStore,
+    events: &dyn EventSink,
+    set: &ChangeSet,
+) -> Result<usize, sqlx::Error> {
+    let changed = store.apply_changes(set).await?;
+    let applied = changed.len();
+    emit_changed(changed, events);
+    Ok(applied)
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct MergeResponse {
+    pub ok: bool,
+    /// 实际合并进本机的任务与分组数
+    pub applied: usize,
+}
+
+/// 接收对端推送的变更（点对点同步的推送方向）
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync/merge",
+    tag = "sync",
+    security(("bearer" = [])),
+    request_body = ChangeSet,
+    responses((status = 200, body = MergeResponse))
+)]
+pub(crate) async fn merge(
+    State(state): State<AppState>,
+    Json(set): Json<ChangeSet>,
+) -> impl IntoResponse {
+    match merge_changes(state.store.as_ref(), state.events.as_ref(), &set).await {
+        Ok(applied) => Json(MergeResponse { ok: true, applied }).into_response(),
+        Err(e) => ApiError::db("merge", Msg::MergeChangesFailed, e).into_response(),
+    }
+}
+
+/// 读取 sync_meta 中的键值
+pub(crate) async fn meta_get(
+    pool: &sqlx::SqlitePool,
+    key: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT value FROM sync_meta WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+}
+
+/// 写入 sync_meta；value 为 None 时删除该键
+pub(crate) async fn meta_set(
+    pool: &sqlx::SqlitePool,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    match value {
+        Some(v) => {
+            sqlx::query("INSERT OR REPLACE INTO sync_meta (key, value) VALUES (?, ?)")
+                .bind(key)
+                .bind(v)
+                .execute(pool)
+                .await?
+        }
+        None => {
+            sqlx::query("DELETE FROM sync_meta WHERE key = ?")
+                .bind(key)
+                .execute(pool)
+                .await?
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NoopEventSink;
+    use xy_todo_core::store::{fetch_one_todo, SqliteStore};
+    use xy_todo_core::Todo;
+
+    async fn pool() -> sqlx::SqlitePool {
+        SqliteStore::in_memory().await.unwrap().pool().clone()
+    }
+
+    async fn merge(to: &sqlx::SqlitePool, set: &ChangeSet) -> usize {
+        merge_changes(&SqliteStore::new(to.clone()), &NoopEventSink, set)
+            .await
+            .unwrap()
+    }
+
+    async fn exec(pool: &sqlx::SqlitePool, sql: &str) {
+        sqlx::query(sql).execute(pool).await.unwrap();
+    }
+
+    /// 把 from 的全部变更合并进 to
+    async fn push(from: &sqlx::SqlitePool, to: &sqlx::SqlitePool) -> usize {
+        let set = load_changes(from, 0, MAX_CHANGES_LIMIT).await.unwrap();
+        merge(to, &set).await
+    }
+
+    async fn todo(pool: &sqlx::SqlitePool, id: &str) -> Todo {
+        fetch_one_todo(pool, id).await.unwrap().unwrap()
+    }
+
+    async fn seeded_pair() -> (sqlx::SqlitePool, sqlx::SqlitePool) {
+        let (a, b) = (pool().await, pool().await);
+        exec(
+            &a,
+            "INSERT INTO todos (id, content, status, importance, sort_order, updated_at) \
+             VALUES ('t1', 'buy milk', 'pending', 'normal', 1, 100)",
+        )
+        .await;
+        assert_eq!(push(&a, &b).await, 1);
+        (a, b)
+    }
+
+    #[tokio::test]
+    async fn edits_to_different_fields_are_both_kept() {
+        let (a, b) = seeded_pair().await;
+        exec(
+            &a,
+            "UPDATE todos SET content = 'buy oat milk', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        exec(
+            &b,
+            "UPDATE todos SET status = 'completed', updated_at = 300 WHERE id = 't1'",
+        )
+        .await;
+        push(&a, &b).await;
+        push(&b, &a).await;
+        for pool in [&a, &b] {
+            let t = todo(pool, "t1").await;
+            assert_eq!(t.content, "buy oat milk");
+            assert_eq!(t.status, "completed");
+            assert_eq!(t.updated_at, 300);
+        }
+    }
+
+    #[tokio::test]
+    async fn later_write_to_the_same_field_wins_on_both_sides() {
+        let (a, b) = seeded_pair().await;
+        exec(
+            &a,
+            "UPDATE todos SET content = 'from a', updated_at = 300 WHERE id = 't1'",
+        )
+        .await;
+        exec(
+            &b,
+            "UPDATE todos SET content = 'from b', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        push(&b, &a).await;
+        push(&a, &b).await;
+        assert_eq!(todo(&a, "t1").await.content, "from a");
+        assert_eq!(todo(&b, "t1").await.content, "from a");
+    }
+
+    #[tokio::test]
+    async fn same_timestamp_resolves_identically_and_echo_is_a_no_op() {
+        let (a, b) = seeded_pair().await;
+        exec(
+            &a,
+            "UPDATE todos SET content = 'x', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        exec(
+            &b,
+            "UPDATE todos SET content = 'y', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        push(&a, &b).await;
+        push(&b, &a).await;
+        assert_eq!(todo(&a, "t1").await.content, todo(&b, "t1").await.content);
+        assert_eq!(push(&a, &b).await, 0);
+        assert_eq!(push(&b, &a).await, 0);
+    }
+
+    #[tokio::test]
+    async fn page_carries_groups_referenced_by_its_todos() {
+        let (a, b) = (pool().await, pool().await);
+        exec(
+            &a,
+            "INSERT INTO groups (id, name, sort_order, updated_at) VALUES ('g1', 'work', 0, 100)",
+        )
+        .await;
+        exec(
+            &a,
+            "INSERT INTO todos (id, content, status, importance, group_id, sort_order, updated_at) \
+             VALUES ('t1', 'report', 'pending', 'normal', 'g1', 0, 100)",
+        )
+        .await;
+        // 分组改名后变更日志排到任务之后，第一页只有任务
+        exec(
+            &a,
+            "UPDATE groups SET name = 'office', updated_at = 200 WHERE id = 'g1'",
+        )
+        .await;
+        let page = load_changes(&a, 0, 1).await.unwrap();
+        assert_eq!(page.todos.len(), 1);
+        assert_eq!(page.groups.len(), 1);
+        merge(&b, &page).await;
+        assert_eq!(todo(&b, "t1").await.group_id.as_deref(), Some("g1"));
+    }
+
+    #[tokio::test]
+    async fn todo_before_its_group_keeps_the_group_once_it_arrives() {
+        let (a, b) = (pool().await, pool().await);
+        exec(
+            &a,
+            "INSERT INTO groups (id, name, sort_order, updated_at) VALUES ('g1', 'work', 0, 100)",
+        )
+        .await;
+        exec(
+            &a,
+            "INSERT INTO todos (id, content, status, importance, group_id, sort_order, updated_at) \
+             VALUES ('t1', 'report', 'pending', 'normal', 'g1', 0, 100)",
+        )
+        .await;
+        // 旧版本对端不会附上分组：只有任务时先不分组
+        let mut set = load_changes(&a, 0, MAX_CHANGES_LIMIT).await.unwrap();
+        let groups = std::mem::take(&mut set.groups);
+        merge(&b, &set).await;
+        assert_eq!(todo(&b, "t1").await.group_id, None);
+
+        // 之后分组与任务再次到达时，group_id 仍能生效
+        set.groups = groups;
+        merge(&b, &set).await;
+        assert_eq!(todo(&b, "t1").await.group_id.as_deref(), Some("g1"));
+    }
+
+    #[tokio::test]
+    async fn changes_from_peers_without_field_times_fall_back_to_updated_at() {
+        let (a, b) = seeded_pair().await;
+        exec(
+            &b,
+            "UPDATE todos SET status = 'completed', updated_at = 150 WHERE id = 't1'",
+        )
+        .await;
+        exec(
+            &a,
+            "UPDATE todos SET content = 'newer', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        let mut set = load_changes(&a, 0, MAX_CHANGES_LIMIT).await.unwrap();
+        set.todo_fields.clear();
+        merge(&b, &set).await;
+        let t = todo(&b, "t1").await;
+        assert_eq!(t.content, "newer");
+        assert_eq!(t.status, "pending");
+    }
+
+    /// 在 pool 上删除 t1，并把墓碑时间改为 deleted_at，便于与对端的修改时间比较
+    async fn delete_at(pool: &sqlx::SqlitePool, deleted_at: i64) {
+        exec(pool, "DELETE FROM todos WHERE id = 't1'").await;
+        exec(
+            pool,
+            &format!(
+                "UPDATE change_log SET deleted_at = {} WHERE entity = 'todo' AND entity_id = 't1'",
+                deleted_at
+            ),
+        )
+        .await;
+    }
+
+    async fn has_todo(pool: &sqlx::SqlitePool, id: &str) -> bool {
+        fetch_one_todo(pool, id).await.unwrap().is_some()
+    }
+
+    #[tokio::test]
+    async fn delete_wins_over_an_edit_at_the_same_time() {
+        let (a, b) = seeded_pair().await;
+        delete_at(&a, 200).await;
+        exec(
+            &b,
+            "UPDATE todos SET content = 'edited', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        push(&b, &a).await;
+        assert!(!has_todo(&a, "t1").await);
+        push(&a, &b).await;
+        assert!(!has_todo(&b, "t1").await);
+    }
+
+    #[tokio::test]
+    async fn older_edit_does_not_bring_back_a_deleted_todo() {
+        let (a, b) = seeded_pair().await;
+        exec(
+            &b,
+            "UPDATE todos SET content = 'edited', updated_at = 200 WHERE id = 't1'",
+        )
+        .await;
+        delete_at(&a, 300).await;
+        push(&b, &a).await;
+        assert!(!has_todo(&a, "t1").await);
+        push(&a, &b).await;
+        assert!(!has_todo(&b, "t1").await);
+    }
+
+    #[tokio::test]
+    async fn newer_edit_survives_an_older_delete() {
+        let (a, b) = seeded_pair().await;
+        delete_at(&a, 200).await;
+        exec(
+            &b,
+            "UPDATE todos SET content = 'edited', updated_at = 300 WHERE id = 't1'",
+        )
+        .await;
+        push(&a, &b).await;
+        assert_eq!(todo(&b, "t1").await.content, "edited");
+        push(&b, &a).await;
+        assert_eq!(todo(&a, "t1").await.content, "edited");
+    }
+
+    #[tokio::test]
+    async fn concurrent_replays_of_one_create_all_succeed() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = xy_todo_core::store::open_database(&dir.path().join("todos.db"))
+            .await
+            .unwrap();
+        let ops = [
+            r#"{"op": "create_todo", "id": "t1", "content": "buy milk"}"#,
+            r#"{"op": "create_group", "id": "g1", "name": "work"}"#,
+        ];
+        for op in ops {
+            let mut tasks = tokio::task::JoinSet::new();
+            for _ in 0..8 {
+                let pool = pool.clone();
+                tasks.spawn(async move {
+                    let op: SyncOp = serde_json::from_str(op).unwrap();
+                    let mut changed = Changed::default();
+                    let result = apply_op(&SqliteStore::new(pool), &op, &mut changed)
+                        .await
+                        .unwrap();
+                    (result.ok, changed.len())
+                });
+            }
+            let results = tasks.join_all().await;
+            assert!(results.iter().all(|(ok, _)| *ok), "{:?}", results);
+            assert_eq!(results.iter().map(|(_, n)| n).sum::<usize>(), 1);
+        }
+        assert_eq!(todo(&pool, "t1").await.content, "buy milk");
+    }
+}
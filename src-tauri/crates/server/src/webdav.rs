@@ -0,0 +1,852 @@
+//! WebDAV 同步与备份：把 NAS 等 WebDAV 服务当作共享存储。
+//! 同步时每台设备把新增变更按页上传为 `douko-todo-sync/<device_id>_<游标>.json`（WebDAV 不支持追加写，
+//! 因此每页一个文件，PUT 完成即完整可见），并下载其他设备游标大于已读位置的文件合并进本地库。
+//! 各设备把已读到的游标上传为 `<device_id>.ack.json`，自己的变更文件在其他设备都读过后删除；
+//! 发现新设备时重新上传一次全量，新设备拿得到已删除文件中的数据。
+//! 备份时用 VACUUM INTO 生成 todos.db 快照上传到 `douko-todo-backups/`，每台设备只保留最近 WEBDAV_BACKUP_KEEP 份。
+//! 地址与用户名记在 sync_meta，密码交给 SecretStore（桌面端为系统钥匙串，无界面模式为数据目录下的 secrets.json），
+//! 不写入 app-settings.json 或数据库。
+
+use percent_encoding::percent_decode_str;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use quick_xml::name::{Namespace, ResolveResult};
+use quick_xml::NsReader;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+use xy_todo_core::i18n::{t, tf, Msg};
+use xy_todo_core::now_millis;
+use xy_todo_core::store::SqliteStore;
+
+use crate::events::EventSink;
+use crate::peer::device_id;
+use crate::remote::{normalize_url, UrlKind};
+use crate::secrets::SecretStore;
+use crate::sync::{load_changes, merge_changes, meta_get, meta_set, ChangeSet};
+
+/// 后台自动同步间隔
+const WEBDAV_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// 单次 HTTP 请求超时（上传备份可能较慢）
+const WEBDAV_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// 每个变更文件包含的变更条数
+const WEBDAV_PAGE_SIZE: i64 = 500;
+/// 每台设备在远端保留的备份份数
+const WEBDAV_BACKUP_KEEP: usize = 10;
+
+const SYNC_DIR: &str = "douko-todo-sync";
+const BACKUP_DIR: &str = "douko-todo-backups";
+const ACK_SUFFIX: &str = ".ack.json";
+
+/// 密码在 SecretStore 中的键（桌面端即钥匙串中的账户名）
+const SECRET_KEY: &str = "webdav";
+
+const META_CONFIG: &str = "webdav_config";
+const META_EXPORT_CURSOR: &str = "webdav_export_cursor";
+const META_CURSOR_PREFIX: &str = "webdav_cursor:";
+const META_LAST_SYNC_AT: &str = "webdav_last_sync_at";
+const META_LAST_ERROR: &str = "webdav_last_error";
+/// 已见过的其他设备（JSON 数组），出现新设备时重新上传全量
+const META_DEVICES: &str = "webdav_devices";
+/// 最近一次上传的确认文件内容，没变时不重复上传
+const META_ACK_UPLOADED: &str = "webdav_ack_uploaded";
+
+/// 同一时刻只跑一轮，避免后台定时与手动触发重复上传同一页
+static WEBDAV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WebDavConfig {
+    /// WebDAV 目录地址，如 https://nas.local:5006/todo
+    url: String,
+    username: String,
+}
+
+/// WebDAV 配置与状态（不含密码），供设置面板展示
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavInfo {
+    pub url: String,
+    pub username: String,
+    pub has_password: bool,
+    /// 最近一次成功同步时间（毫秒）
+    pub last_sync_at: Option<i64>,
+    /// 最近一次同步失败的原因，成功后清空
+    pub last_error: Option<String>,
+}
+
+async fn load_config(pool: &sqlx::SqlitePool) -> Result<Option<WebDavConfig>, String> {
+    let raw = meta_get(pool, META_CONFIG)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(raw.and_then(|r| serde_json::from_str(&r).ok()))
+}
+
+pub async fn get_info(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+) -> Result<Option<WebDavInfo>, String> {
+    let Some(config) = load_config(pool).await? else {
+        return Ok(None);
+    };
+    let last_sync_at = meta_get(pool, META_LAST_SYNC_AT)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|v| v.parse().ok());
+    let last_error = meta_get(pool, META_LAST_ERROR)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(WebDavInfo {
+        url: config.url,
+        username: config.username,
+        has_password: secrets.get(SECRET_KEY).is_some(),
+        last_sync_at,
+        last_error,
+    }))
+}
+
+/// 保存 WebDAV 配置并校验连接；password 为 None 时沿用已保存的密码。
+/// 地址变化时重置上传与下载游标，随后立即同步一次。
+pub async fn configure(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    secrets: &dyn SecretStore,
+    url: &str,
+    username: &str,
+    password: Option<&str>,
+) -> Result<WebDavInfo, String> {
+    let config = WebDavConfig {
+        url: normalize_url(url, UrlKind::WebDav, Msg::InvalidWebDavUrl)?,
+        username: username.trim().to_string(),
+    };
+    let password = match password {
+        Some(p) => p.to_string(),
+        None => secrets.get(SECRET_KEY).unwrap_or_default(),
+    };
+    let client = Client::new(&config, &password)?;
+    client.ensure_dir(SYNC_DIR).await?;
+
+    let _guard = WEBDAV_LOCK.lock().await;
+    if password.is_empty() {
+        secrets.delete(SECRET_KEY)?;
+    } else {
+        secrets.set(SECRET_KEY, &password)?;
+    }
+    let previous = load_config(pool).await?;
+    if previous.as_ref().map(|p| p.url.as_str()) != Some(config.url.as_str()) {
+        reset_cursors(pool).await.map_err(|e| e.to_string())?;
+    }
+    let raw = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    meta_set(pool, META_CONFIG, Some(&raw))
+        .await
+        .map_err(|e| e.to_string())?;
+    record_result(pool, sync_with(pool, events, &client).await).await;
+    get_info(pool, secrets)
+        .await?
+        .ok_or_else(|| t(Msg::SaveWebDavFailed).to_string())
+}
+
+/// 清除 WebDAV 配置与保存的密码（远端文件保留）
+pub async fn clear(pool: &sqlx::SqlitePool, secrets: &dyn SecretStore) -> Result<(), String> {
+    let _guard = WEBDAV_LOCK.lock().await;
+    meta_set(pool, META_CONFIG, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    reset_cursors(pool).await.map_err(|e| e.to_string())?;
+    secrets.delete(SECRET_KEY)
+}
+
+async fn reset_cursors(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    for key in [
+        META_EXPORT_CURSOR,
+        META_LAST_SYNC_AT,
+        META_LAST_ERROR,
+        META_DEVICES,
+        META_ACK_UPLOADED,
+    ] {
+        meta_set(pool, key, None).await?;
+    }
+    sqlx::query("DELETE FROM sync_meta WHERE key LIKE ?")
+        .bind(format!("{}%", META_CURSOR_PREFIX))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn configured_client(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+) -> Result<Option<Client>, String> {
+    let Some(config) = load_config(pool).await? else {
+        return Ok(None);
+    };
+    let password = secrets.get(SECRET_KEY).unwrap_or_default();
+    Client::new(&config, &password).map(Some)
+}
+
+/// 立即与 WebDAV 同步一次（未配置时什么也不做），返回同步后的状态
+pub async fn sync_now(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    secrets: &dyn SecretStore,
+) -> Result<Option<WebDavInfo>, String> {
+    {
+        let _guard = WEBDAV_LOCK.lock().await;
+        if let Some(client) = configured_client(pool, secrets).await? {
+            let result = sync_with(pool, events, &client).await;
+            record_result(pool, result.clone()).await;
+            result?;
+        }
+    }
+    get_info(pool, secrets).await
+}
+
+/// 生成 todos.db 快照并上传，返回远端文件名
+pub async fn backup_now(
+    pool: &sqlx::SqlitePool,
+    secrets: &dyn SecretStore,
+) -> Result<String, String> {
+    let client = configured_client(pool, secrets)
+        .await?
+        .ok_or_else(|| t(Msg::WebDavNotConfigured).to_string())?;
+    let snapshot = std::env::temp_dir().join(format!("douko-todo-{}.db", uuid::Uuid::new_v4()));
+    sqlx::query("VACUUM INTO ?")
+        .bind(snapshot.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| tf(Msg::SnapshotFailed, &[&e]))?;
+    let data = std::fs::read(&snapshot).map_err(|e| e.to_string());
+    let _ = std::fs::remove_file(&snapshot);
+    let data = data?;
+
+    let own_id = device_id(pool).await.map_err(|e| e.to_string())?;
+    let name = format!("todos-{}-{}.db", own_id, now_millis());
+    client.ensure_dir(BACKUP_DIR).await?;
+    client
+        .put(&format!("{}/{}", BACKUP_DIR, name), data)
+        .await?;
+    prune_backups(&client, &own_id).await?;
+    Ok(name)
+}
+
+/// 删除本机较早的远端备份，只保留最近 WEBDAV_BACKUP_KEEP 份；其他设备的备份不动
+async fn prune_backups(client: &Client, own_id: &str) -> Result<(), String> {
+    let prefix = format!("todos-{}-", own_id);
+    let mut backups: Vec<(u64, String)> = client
+        .list(BACKUP_DIR)
+        .await?
+        .into_iter()
+        .filter_map(|name| {
+            let at = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".db")?
+                .parse()
+                .ok()?;
+            Some((at, name))
+        })
+        .collect();
+    backups.sort_by(|a, b| b.cmp(a));
+    for (_, name) in backups.into_iter().skip(WEBDAV_BACKUP_KEEP) {
+        client.delete(&format!("{}/{}", BACKUP_DIR, name)).await?;
+    }
+    Ok(())
+}
+
+async fn record_result(pool: &sqlx::SqlitePool, result: Result<(), String>) {
+    let r = match result {
+        Ok(()) => {
+            let at = (now_millis() as i64).to_string();
+            match meta_set(pool, META_LAST_SYNC_AT, Some(&at)).await {
+                Ok(()) => meta_set(pool, META_LAST_ERROR, None).await,
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => {
+            eprintln!("webdav sync: {}", e);
+            meta_set(pool, META_LAST_ERROR, Some(&e)).await
+        }
+    };
+    if let Err(e) = r {
+        eprintln!("record webdav sync result: {}", e);
+    }
+}
+
+/// 先下载合并其他设备的变更，再上传确认文件与本机新增变更，最后删除各设备都已读过的本机变更文件
+async fn sync_with(
+    pool: &sqlx::SqlitePool,
+    events: &dyn EventSink,
+    client: &Client,
+) -> Result<(), String> {
+    let own_id = device_id(pool).await.map_err(|e| e.to_string())?;
+    client.ensure_dir(SYNC_DIR).await?;
+
+    let mut others: BTreeSet<String> = BTreeSet::new();
+    let mut own_pages: Vec<(i64, String)> = Vec::new();
+    let mut remote: Vec<(String, i64, String)> = Vec::new();
+    for name in client.list(SYNC_DIR).await? {
+        if let Some(device) = name.strip_suffix(ACK_SUFFIX) {
+            if device != own_id {
+                others.insert(device.to_string());
+            }
+            continue;
+        }
+        let Some((device, cursor)) = parse_page_name(&name) else {
+            continue;
+        };
+        if device == own_id {
+            own_pages.push((cursor, name));
+        } else {
+            others.insert(device.to_string());
+            remote.push((device.to_string(), cursor, name));
+        }
+    }
+    remote.sort();
+    for (device, cursor, name) in remote {
+        let key = format!("{}{}", META_CURSOR_PREFIX, device);
+        let seen: i64 = meta_get(pool, &key)
+            .await
+            .map_err(|e| e.to_string())?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if cursor <= seen {
+            continue;
+        }
+        let bytes = client.get(&format!("{}/{}", SYNC_DIR, name)).await?;
+        let set: ChangeSet =
+            serde_json::from_slice(&bytes).map_err(|e| tf(Msg::InvalidRemoteFile, &[&name, &e]))?;
+        merge_changes(&SqliteStore::new(pool.clone()), events, &set)
+            .await
+            .map_err(|e| e.to_string())?;
+        meta_set(pool, &key, Some(&cursor.to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    upload_ack(pool, client, &own_id).await?;
+    export_pages(pool, client, &own_id, &others).await?;
+    prune_pages(client, &own_id, &others, own_pages).await
+}
+
+/// 确认文件：本机已读到的各设备游标
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AckFile {
+    acks: BTreeMap<String, i64>,
+}
+
+/// 上传本机的确认文件；内容与上次上传的相同时跳过。还没读过任何变更时也上传一份空的，让其他设备知道本机在场
+async fn upload_ack(pool: &sqlx::SqlitePool, client: &Client, own_id: &str) -> Result<(), String> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT key, value FROM sync_meta WHERE key LIKE ?")
+            .bind(format!("{}%", META_CURSOR_PREFIX))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    let ack = AckFile {
+        acks: rows
+            .into_iter()
+            .filter_map(|(k, v)| {
+                Some((
+                    k.strip_prefix(META_CURSOR_PREFIX)?.to_string(),
+                    v.parse().ok()?,
+                ))
+            })
+            .collect(),
+    };
+    let body = serde_json::to_string(&ack).map_err(|e| e.to_string())?;
+    let uploaded = meta_get(pool, META_ACK_UPLOADED)
+        .await
+        .map_err(|e| e.to_string())?;
+    if uploaded.as_deref() == Some(body.as_str()) {
+        return Ok(());
+    }
+    client
+        .put(
+            &format!("{}/{}{}", SYNC_DIR, own_id, ACK_SUFFIX),
+            body.clone().into_bytes(),
+        )
+        .await?;
+    meta_set(pool, META_ACK_UPLOADED, Some(&body))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 上传本机尚未上传的变更，每页一个文件；远端出现新设备时从头上传全量
+async fn export_pages(
+    pool: &sqlx::SqlitePool,
+    client: &Client,
+    own_id: &str,
+    others: &BTreeSet<String>,
+) -> Result<(), String> {
+    let known: BTreeSet<String> = meta_get(pool, META_DEVICES)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default();
+    let full = others.iter().any(|d| !known.contains(d));
+    let mut cursor: i64 = match full {
+        true => 0,
+        false => meta_get(pool, META_EXPORT_CURSOR)
+            .await
+            .map_err(|e| e.to_string())?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0),
+    };
+    loop {
+        let set = load_changes(pool, cursor, WEBDAV_PAGE_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        if set.cursor == cursor {
+            break;
+        }
+        if !set.is_empty() {
+            let body = serde_json::to_vec(&set).map_err(|e| e.to_string())?;
+            client
+                .put(
+                    &format!("{}/{}", SYNC_DIR, page_name(own_id, set.cursor)),
+                    body,
+                )
+                .await?;
+        }
+        cursor = set.cursor;
+        meta_set(pool, META_EXPORT_CURSOR, Some(&cursor.to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+        if !set.has_more {
+            break;
+        }
+    }
+    if full {
+        let devices: BTreeSet<&String> = known.iter().chain(others).collect();
+        let devices = serde_json::to_string(&devices).map_err(|e| e.to_string())?;
+        meta_set(pool, META_DEVICES, Some(&devices))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 删除远端其他设备都已确认读过的本机变更文件。还没有确认文件的设备（旧版本）视为什么都没读，此时不删除
+async fn prune_pages(
+    client: &Client,
+    own_id: &str,
+    others: &BTreeSet<String>,
+    own_pages: Vec<(i64, String)>,
+) -> Result<(), String> {
+    if others.is_empty() {
+        return Ok(());
+    }
+    let mut acked = i64::MAX;
+    for device in others {
+        let ack = match client
+            .get(&format!("{}/{}{}", SYNC_DIR, device, ACK_SUFFIX))
+            .await
+        {
+            Ok(bytes) => serde_json::from_slice::<AckFile>(&bytes)
+                .ok()
+                .and_then(|a| a.acks.get(own_id).copied())
+                .unwrap_or(0),
+            Err(_) => 0,
+        };
+        acked = acked.min(ack);
+    }
+    for (cursor, name) in own_pages {
+        if cursor <= acked {
+            client.delete(&format!("{}/{}", SYNC_DIR, name)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 变更文件名：游标补零到 20 位，按名称排序即按时间排序
+fn page_name(device: &str, cursor: i64) -> String {
+    format!("{}_{:020}.json", device, cursor)
+}
+
+fn parse_page_name(name: &str) -> Option<(&str, i64)> {
+    let stem = name.strip_suffix(".json")?;
+    let (device, cursor) = stem.rsplit_once('_')?;
+    Some((device, cursor.parse().ok()?))
+}
+
+/// PROPFIND 响应中的一个条目
+#[derive(Debug, PartialEq)]
+struct DavEntry {
+    /// href 的最后一段，已做百分号解码
+    name: String,
+    collection: bool,
+}
+
+/// href 的最后一段：href 可能是绝对 URL，也可能是绝对路径；名称经过百分号编码
+fn href_name(href: &str) -> Option<String> {
+    let path = match url::Url::parse(href) {
+        Ok(u) => u.path().to_string(),
+        Err(_) => href.to_string(),
+    };
+    let last = path.trim_end_matches('/').rsplit('/').next()?;
+    let name = percent_decode_str(last).decode_utf8().ok()?;
+    (!name.is_empty()).then(|| name.into_owned())
+}
+
+/// 解析 PROPFIND 的 multistatus 响应。元素按 DAV: 命名空间匹配，与服务端用的前缀（D:、d:、默认命名空间）无关
+fn parse_multistatus(xml: &str) -> Result<Vec<DavEntry>, String> {
+    const DAV: ResolveResult = ResolveResult::Bound(Namespace(b"DAV:"));
+    let mut reader = NsReader::from_str(xml);
+    let mut entries = Vec::new();
+    let mut href: Option<String> = None;
+    let mut text: Option<String> = None;
+    let mut collection = false;
+    loop {
+        let (ns, event) = reader
+            .read_resolved_event()
+            .map_err(|e| tf(Msg::WebDavBadListing, &[&e]))?;
+        match event {
+            Event::Start(e) if ns == DAV => match e.local_name().as_ref() {
+                b"response" => {
+                    href = None;
+                    collection = false;
+                }
+                b"href" => text = Some(String::new()),
+                b"collection" => collection = true,
+                _ => {}
+            },
+            Event::Empty(e) if ns == DAV && e.local_name().as_ref() == b"collection" => {
+                collection = true
+            }
+            Event::Text(t) => {
+                if let Some(text) = text.as_mut() {
+                    text.push_str(&t.decode().map_err(|e| e.to_string())?);
+                }
+            }
+            Event::CData(t) => {
+                if let Some(text) = text.as_mut() {
+                    text.push_str(&t.decode().map_err(|e| e.to_string())?);
+                }
+            }
+            Event::GeneralRef(r) => {
+                if let Some(text) = text.as_mut() {
+                    let name = r.decode().map_err(|e| e.to_string())?;
+                    match r.resolve_char_ref().map_err(|e| e.to_string())? {
+                        Some(c) => text.push(c),
+                        None => text.push_str(resolve_predefined_entity(&name).unwrap_or_default()),
+                    }
+                }
+            }
+            Event::End(e) if ns == DAV => match e.local_name().as_ref() {
+                b"href" => href = text.take().map(|t| t.trim().to_string()),
+                b"response" => {
+                    if let Some(name) = href.take().as_deref().and_then(href_name) {
+                        entries.push(DavEntry { name, collection });
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// 最小 WebDAV 客户端：只用到 MKCOL、PROPFIND、GET、PUT 与 DELETE
+struct Client {
+    http: reqwest::Client,
+    base: String,
+    username: String,
+    password: String,
+}
+
+impl Client {
+    fn new(config: &WebDavConfig, password: &str) -> Result<Self, String> {
+        let http = reqwest::Client::builder()
+            .timeout(WEBDAV_REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            http,
+            base: config.url.clone(),
+            username: config.username.clone(),
+            password: password.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let req = self.http.request(method, format!("{}/{}", self.base, path));
+        if self.username.is_empty() {
+            req
+        } else {
+            req.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| tf(Msg::WebDavUnreachable, &[&e]))?;
+        match resp.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(t(Msg::WebDavAuthFailed).to_string())
+            }
+            s if s.is_success() => Ok(resp),
+            s => Err(tf(Msg::WebDavRequestFailed, &[&s])),
+        }
+    }
+
+    /// 创建目录；已存在时（405）视为成功
+    async fn ensure_dir(&self, dir: &str) -> Result<(), String> {
+        let method = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+        let resp = self
+            .request(method, &format!("{}/", dir))
+            .send()
+            .await
+            .map_err(|e| tf(Msg::WebDavUnreachable, &[&e]))?;
+        match resp.status() {
+            s if s.is_success() || s == StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(t(Msg::WebDavAuthFailed).to_string())
+            }
+            s => Err(tf(Msg::WebDavMkdirFailed, &[&s])),
+        }
+    }
+
+    /// 列出目录下的文件名（不含目录本身与子目录）
+    async fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+        let req = self
+            .request(method, &format!("{}/", dir))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(r#"<?xml version="1.0" encoding="utf-8"?><propfind xmlns="DAV:"><prop><resourcetype/></prop></propfind>"#);
+        let xml = self
+            .send(req)
+            .await?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(parse_multistatus(&xml)?
+            .into_iter()
+            .filter(|entry| !entry.collection)
+            .map(|entry| entry.name)
+            .collect())
+    }
+
+    async fn get(&self, path: &str) -> Result<Vec<u8>, String> {
+        let resp = self.send(self.request(Method::GET, path)).await?;
+        resp.bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn put(&self, path: &str, body: Vec<u8>) -> Result<(), String> {
+        self.send(self.request(Method::PUT, path).body(body))
+            .await?;
+        Ok(())
+    }
+
+    /// 删除文件；已不存在（404）时视为成功
+    async fn delete(&self, path: &str) -> Result<(), String> {
+        let resp = self
+            .request(Method::DELETE, path)
+            .send()
+            .await
+            .map_err(|e| tf(Msg::WebDavUnreachable, &[&e]))?;
+        match resp.status() {
+            s if s.is_success() || s == StatusCode::NOT_FOUND => Ok(()),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(t(Msg::WebDavAuthFailed).to_string())
+            }
+            s => Err(tf(Msg::WebDavRequestFailed, &[&s])),
+        }
+    }
+}
+
+/// 后台定时同步，随应用生命周期运行；未配置 WebDAV 时空转
+pub async fn run_webdav_sync_loop(
+    pool: sqlx::SqlitePool,
+    events: Arc<dyn EventSink>,
+    secrets: Arc<dyn SecretStore>,
+) {
+    let mut ticker = tokio::time::interval(WEBDAV_SYNC_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let _guard = WEBDAV_LOCK.lock().await;
+        match configured_client(&pool, secrets.as_ref()).await {
+            Ok(Some(client)) => {
+                record_result(&pool, sync_with(&pool, events.as_ref(), &client).await).await
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("webdav sync: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NoopEventSink;
+    use xy_todo_core::store::SqliteStore;
+
+    fn names(xml: &str) -> Vec<(String, bool)> {
+        parse_multistatus(xml)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.name, e.collection))
+            .collect()
+    }
+
+    #[test]
+    fn multistatus_is_parsed_regardless_of_namespace_prefix() {
+        let prefixed = r#"<?xml version="1.0"?>
+            <D:multistatus xmlns:D="DAV:">
+              <D:response><D:href>/dav/douko-todo-sync/</D:href>
+                <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+              </D:response>
+              <D:response><D:href>/dav/douko-todo-sync/a_00000000000000000001.json</D:href>
+                <D:propstat><D:prop><D:resourcetype/></D:prop></D:propstat>
+              </D:response>
+            </D:multistatus>"#;
+        let default_ns = r#"<multistatus xmlns="DAV:">
+              <response><href>/dav/douko-todo-sync/</href>
+                <propstat><prop><resourcetype><collection></collection></resourcetype></prop></propstat>
+              </response>
+              <response><href>/dav/douko-todo-sync/a_00000000000000000001.json</href></response>
+            </multistatus>"#;
+        let expected = vec![
+            ("douko-todo-sync".to_string(), true),
+            ("a_00000000000000000001.json".to_string(), false),
+        ];
+        assert_eq!(names(prefixed), expected);
+        assert_eq!(names(default_ns), expected);
+    }
+
+    #[test]
+    fn hrefs_may_be_absolute_urls_and_percent_encoded() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:" xmlns:x="urn:other">
+              <d:response><d:href>https://nas.local:5006/my%20dav/todo%E6%96%87%E4%BB%B6.json</d:href></d:response>
+              <d:response><d:href>/a%26b/x&amp;y.json</d:href></d:response>
+              <d:response><x:href>/not-dav.json</x:href></d:response>
+            </d:multistatus>"#;
+        assert_eq!(
+            names(xml),
+            vec![
+                ("todo文件.json".to_string(), false),
+                ("x&y.json".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_listing_is_an_error() {
+        assert!(
+            parse_multistatus("<d:multistatus xmlns:d=\"DAV:\"><d:response></d:href>").is_err()
+        );
+    }
+
+    /// 本地 WebDAV 服务（内存文件系统），挂在 /dav 下以覆盖带路径前缀的地址
+    async fn dav_server() -> Client {
+        let dav = dav_server::DavHandler::builder()
+            .filesystem(dav_server::memfs::MemFs::new())
+            .strip_prefix("/dav")
+            .build_handler();
+        let app = axum::Router::new().fallback(move |req: axum::extract::Request| {
+            let dav = dav.clone();
+            async move { dav.handle(req).await.map(axum::body::Body::new) }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let config = WebDavConfig {
+            url: format!("http://{}/dav", addr),
+            username: String::new(),
+        };
+        Client::new(&config, "").unwrap()
+    }
+
+    async fn device() -> sqlx::SqlitePool {
+        SqliteStore::in_memory().await.unwrap().pool().clone()
+    }
+
+    async fn add_todo(pool: &sqlx::SqlitePool, id: &str) {
+        sqlx::query(
+            "INSERT INTO todos (id, content, status, importance, sort_order, updated_at) \
+             VALUES (?, 'x', 'pending', 'normal', 0, 1)",
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn has_todo(pool: &sqlx::SqlitePool, id: &str) -> bool {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM todos WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            > 0
+    }
+
+    async fn pages_of(client: &Client, pool: &sqlx::SqlitePool) -> usize {
+        let own_id = device_id(pool).await.unwrap();
+        client
+            .list(SYNC_DIR)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|name| matches!(parse_page_name(name), Some((d, _)) if d == own_id))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn devices_exchange_changes_and_read_pages_are_removed() {
+        let client = dav_server().await;
+        let (a, b) = (device().await, device().await);
+        add_todo(&a, "t1").await;
+        sync_with(&a, &NoopEventSink, &client).await.unwrap();
+        assert_eq!(pages_of(&client, &a).await, 1);
+
+        sync_with(&b, &NoopEventSink, &client).await.unwrap();
+        assert!(has_todo(&b, "t1").await);
+        add_todo(&b, "t2").await;
+        sync_with(&b, &NoopEventSink, &client).await.unwrap();
+        sync_with(&a, &NoopEventSink, &client).await.unwrap();
+        assert!(has_todo(&a, "t2").await);
+
+        // 双方都读过对方的变更并上传了确认，再同步一轮后各自的变更文件被删除
+        sync_with(&b, &NoopEventSink, &client).await.unwrap();
+        sync_with(&a, &NoopEventSink, &client).await.unwrap();
+        sync_with(&b, &NoopEventSink, &client).await.unwrap();
+        assert_eq!(pages_of(&client, &a).await, 0);
+        assert_eq!(pages_of(&client, &b).await, 0);
+
+        // 之后加入的设备仍能拿到全部数据
+        let c = device().await;
+        sync_with(&c, &NoopEventSink, &client).await.unwrap();
+        sync_with(&a, &NoopEventSink, &client).await.unwrap();
+        sync_with(&c, &NoopEventSink, &client).await.unwrap();
+        assert!(has_todo(&c, "t1").await && has_todo(&c, "t2").await);
+    }
+
+    #[tokio::test]
+    async fn only_the_latest_backups_of_this_device_are_kept() {
+        let client = dav_server().await;
+        client.ensure_dir(BACKUP_DIR).await.unwrap();
+        for at in 0..WEBDAV_BACKUP_KEEP as u64 + 3 {
+            let name = format!("{}/todos-own-{}.db", BACKUP_DIR, 1000 + at);
+            client.put(&name, vec![0]).await.unwrap();
+        }
+        let other = format!("{}/todos-other-1.db", BACKUP_DIR);
+        client.put(&other, vec![0]).await.unwrap();
+
+        prune_backups(&client, "own").await.unwrap();
+        let mut left = client.list(BACKUP_DIR).await.unwrap();
+        left.sort();
+        assert_eq!(left.len(), WEBDAV_BACKUP_KEEP + 1);
+        assert!(left.contains(&"todos-other-1.db".to_string()));
+        assert!(!left.contains(&"todos-own-1002.db".to_string()));
+        assert!(left.contains(&"todos-own-1003.db".to_string()));
+    }
+}
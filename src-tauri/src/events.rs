@@ -0,0 +1,15 @@
+//! EventSink 的桌面端实现：服务端推送的事件转发给所有 Tauri 窗口。
+
+use tauri::{AppHandle, Emitter};
+use xy_todo_server::events::EventSink;
+
+/// 转发给所有 Tauri 窗口
+pub struct TauriEventSink(pub AppHandle);
+
+impl EventSink for TauriEventSink {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Err(e) = self.0.emit(event, payload) {
+            eprintln!("emit {}: {}", event, e);
+        }
+    }
+}
@@ -3,15 +3,26 @@
     windows_subsystem = "windows"
 )]
 
-mod server;
+mod events;
+mod secrets;
 
 use serde::{Deserialize, Serialize};
-use std::net::Ipv4Addr;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Manager};
 use tauri::webview::WebviewWindowBuilder;
 use tauri::WebviewUrl;
+use tauri::{AppHandle, Manager};
+use xy_todo_core::i18n::{self, t, tf, Msg};
+use xy_todo_core::store::{OpenDbError, OpenDbErrorKind};
+use xy_todo_core::{
+    maintenance, now_millis, recovery, CreateTodoBody, GroupItem, Todo, UpdateTodoBody,
+};
+use xy_todo_server::backend::LocalBackend;
+use xy_todo_server::error::ApiError;
+use xy_todo_server::{
+    backup, crud, folder_sync, pairing, peer, remote, server, webdav, AppState,
+    APP_SETTINGS_FILENAME, DB_FILENAME, DEFAULT_PORT,
+};
 
 /// 主窗体首次启动时的宽度（逻辑像素）
 const MAIN_WINDOW_INITIAL_WIDTH: f64 = 375.0;
@@ -57,8 +68,6 @@ fn save_main_window_state(app: &AppHandle, state: &MainWindowState) -> Result<()
     std::fs::write(&path, data).map_err(|e| e.to_string())
 }
 
-pub const DEFAULT_PORT: u16 = 8080;
-
 /// 供前端获取应用版本号（关于我们等页面使用）
 #[tauri::command]
 fn get_app_version(app: AppHandle) -> String {
@@ -68,8 +77,12 @@ fn get_app_version(app: AppHandle) -> String {
 /// 主窗背景透明度刷新：微移窗口再还原，触发 DWM 重新合成（解决启动后透明度不生效、需拖到别屏再拖回才正常的问题）
 #[tauri::command]
 fn refresh_main_window_transparency(app: AppHandle) -> Result<(), String> {
-    let Some(main_win) = app.get_webview_window("main") else { return Ok(()) };
-    let Ok(pos) = main_win.outer_position() else { return Ok(()) };
+    let Some(main_win) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let Ok(pos) = main_win.outer_position() else {
+        return Ok(());
+    };
     let orig = tauri::PhysicalPosition::new(pos.x, pos.y);
     let _ = main_win.set_position(tauri::PhysicalPosition::new(pos.x + 1, pos.y));
     let app_clone = app.clone();
@@ -85,11 +98,52 @@ fn refresh_main_window_transparency(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-/// 供前端在未收到 web-server-url 事件时主动获取扫码地址（服务就绪后调用）
+/// 手机扫码地址，附带扫码页面的访问令牌；远程模式下向主机请求主机的扫码地址
+#[tauri::command]
+async fn get_web_server_url(app: AppHandle) -> Result<String, String> {
+    if let Some(remote) = remote_config(&app) {
+        let client = xy_todo_server::client::TodoClient::new(remote.url)
+            .map_err(|e| e.to_string())?
+            .with_token(remote.token)
+            .with_locale(i18n::app_locale().tag());
+        return client.mobile_url().await.map_err(|e| e.to_string());
+    }
+    let state = local_state(&app).map_err(|e| e.message())?;
+    let token = pairing::mobile_token(&state.pool, state.secrets.as_ref())
+        .await
+        .map_err(|e| {
+            eprintln!("get_web_server_url: {}", e);
+            t(Msg::MobileTokenFailed).to_string()
+        })?;
+    Ok(server::mobile_page_url(DEFAULT_PORT, &token))
+}
+
+/// 前端请求数据的地址与令牌：本地模式为本机服务，远程模式为主机地址
 #[tauri::command]
-fn get_web_server_url() -> String {
-    let ip = local_ip_address::local_ip().unwrap_or(std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
-    format!("http://{}:{}/mobile", ip, DEFAULT_PORT)
+fn get_api_endpoint(app: AppHandle) -> remote::ApiEndpoint {
+    remote::api_endpoint(remote_config(&app))
+}
+
+/// 远程模式配置；本地模式（或取不到应用数据目录）时为 None
+fn remote_config(app: &AppHandle) -> Option<remote::RemoteServerConfig> {
+    remote::load_config(&app.path().app_data_dir().ok()?)
+}
+
+/// 本机后端；远程模式下不注册，还原备份替换数据库期间暂时为 None
+type BackendSlot = std::sync::Mutex<Option<LocalBackend>>;
+
+/// 当前本机后端的服务状态；还原失败后重新打开的后端会换上新的连接池，所以每次从槽位中取
+fn local_backend_state(app: &AppHandle) -> Option<AppState> {
+    let slot = app.try_state::<BackendSlot>()?;
+    let backend = slot.lock().ok()?;
+    backend.as_ref().map(|backend| backend.state.clone())
+}
+
+/// 本机数据库连接池；远程模式下没有本地数据库
+fn local_pool(app: &AppHandle) -> Result<sqlx::SqlitePool, String> {
+    local_backend_state(app)
+        .map(|s| s.pool)
+        .ok_or_else(|| t(Msg::RemoteModeUnavailable).to_string())
 }
 
 /// 背景图缓存文件名（保存在应用数据目录）
@@ -110,7 +164,7 @@ fn save_background_image(app: AppHandle, source_path: String) -> Result<String,
     std::fs::copy(&source_path, &dest).map_err(|e| e.to_string())?;
     dest.into_os_string()
         .into_string()
-        .map_err(|_| "路径编码错误".to_string())
+        .map_err(|_| t(Msg::PathEncodingError).to_string())
 }
 
 /// 清除应用数据目录中的背景图缓存（用户点击「清除背景图」时调用）。
@@ -162,13 +216,15 @@ fn dir_size(path: &std::path::Path) -> u64 {
 #[tauri::command]
 fn get_storage_info(app: AppHandle) -> Result<StorageInfo, String> {
     let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    let sqlite_path = app_data.join("todos.db");
-    let sqlite_size_bytes = file_size(&sqlite_path);
+    let sqlite_path = app_data.join(DB_FILENAME);
+    // WAL 模式下最近的提交还在 -wal 文件中，一并计入
+    let sqlite_size_bytes = file_size(&sqlite_path)
+        + file_size(&sqlite_path.with_file_name(format!("{}-wal", DB_FILENAME)));
     let app_data_size_bytes = dir_size(&app_data);
     let sqlite_path_str = sqlite_path
         .into_os_string()
         .into_string()
-        .map_err(|_| "路径编码错误")?;
+        .map_err(|_| t(Msg::PathEncodingError))?;
     Ok(StorageInfo {
         sqlite_path: sqlite_path_str,
         sqlite_size_bytes,
@@ -181,7 +237,7 @@ fn get_storage_info(app: AppHandle) -> Result<StorageInfo, String> {
 fn open_path_in_folder(path: String) -> Result<(), String> {
     let p = std::path::Path::new(&path);
     if !p.exists() {
-        return Err("路径不存在".to_string());
+        return Err(t(Msg::PathNotFound).to_string());
     }
     #[cfg(target_os = "windows")]
     {
@@ -189,7 +245,7 @@ fn open_path_in_folder(path: String) -> Result<(), String> {
         let path_str = path_abs
             .into_os_string()
             .into_string()
-            .map_err(|_| "路径编码错误")?;
+            .map_err(|_| t(Msg::PathEncodingError))?;
         std::process::Command::new("explorer")
             .arg(format!("/select,{}", path_str))
             .spawn()
@@ -204,9 +260,7 @@ fn open_path_in_folder(path: String) -> Result<(), String> {
     }
     #[cfg(target_os = "linux")]
     {
-        let parent = p
-            .parent()
-            .ok_or("无法获取父目录")?;
+        let parent = p.parent().ok_or(t(Msg::NoParentDir))?;
         std::process::Command::new("xdg-open")
             .arg(parent)
             .spawn()
@@ -215,6 +269,366 @@ fn open_path_in_folder(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// 列出已配对的同步对端（设置「数据与安全」面板展示）
+#[tauri::command]
+async fn list_sync_peers(app: AppHandle) -> Result<Vec<peer::SyncPeer>, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    peer::list_peers(&state.pool, state.secrets.as_ref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 与另一台实例配对：url 为对端 Axum 服务地址（如 http://192.168.1.5:8080），code 为对端生成的配对码；配对后立即同步一次
+#[tauri::command]
+async fn pair_sync_peer(
+    app: AppHandle,
+    url: String,
+    code: String,
+) -> Result<peer::SyncPeer, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    peer::pair(
+        &state.pool,
+        state.events.as_ref(),
+        state.secrets.as_ref(),
+        &url,
+        &code,
+    )
+    .await
+}
+
+/// 取消与某个对端的配对（不影响已同步的数据）
+#[tauri::command]
+async fn remove_sync_peer(app: AppHandle, device_id: String) -> Result<(), String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    peer::remove_peer(&state.pool, state.secrets.as_ref(), &device_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 立即与所有对端同步一次，返回同步后的对端状态
+#[tauri::command]
+async fn sync_peers_now(app: AppHandle) -> Result<Vec<peer::SyncPeer>, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    peer::sync_all(&state.pool, state.events.as_ref(), state.secrets.as_ref()).await;
+    peer::list_peers(&state.pool, state.secrets.as_ref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 当前用于同步的共享文件夹（未设置返回 None）
+#[tauri::command]
+async fn get_sync_folder(app: AppHandle) -> Result<Option<String>, String> {
+    let pool = local_pool(&app)?;
+    folder_sync::get_folder(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置共享文件夹（Syncthing / Dropbox / NAS 等同步目录），传 None 停止文件夹同步；设置后立即同步一次
+#[tauri::command]
+async fn set_sync_folder(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    folder_sync::set_folder(&state.pool, state.events.as_ref(), path.as_deref()).await
+}
+
+/// 立即与共享文件夹同步一次
+#[tauri::command]
+async fn sync_folder_now(app: AppHandle) -> Result<(), String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    folder_sync::sync_now(&state.pool, state.events.as_ref()).await
+}
+
+/// 当前 WebDAV 配置与同步状态（未配置返回 None；不含密码）
+#[tauri::command]
+async fn get_webdav_config(app: AppHandle) -> Result<Option<webdav::WebDavInfo>, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    webdav::get_info(&state.pool, state.secrets.as_ref()).await
+}
+
+/// 保存 WebDAV 配置：校验连接后密码写入系统钥匙串，password 为空时沿用已保存的密码；保存后立即同步一次
+#[tauri::command]
+async fn set_webdav_config(
+    app: AppHandle,
+    url: String,
+    username: String,
+    password: Option<String>,
+) -> Result<webdav::WebDavInfo, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    webdav::configure(
+        &state.pool,
+        state.events.as_ref(),
+        state.secrets.as_ref(),
+        &url,
+        &username,
+        password.as_deref(),
+    )
+    .await
+}
+
+/// 清除 WebDAV 配置与保存的密码（远端文件保留）
+#[tauri::command]
+async fn clear_webdav_config(app: AppHandle) -> Result<(), String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    webdav::clear(&state.pool, state.secrets.as_ref()).await
+}
+
+/// 立即与 WebDAV 同步一次，返回同步后的状态
+#[tauri::command]
+async fn sync_webdav_now(app: AppHandle) -> Result<Option<webdav::WebDavInfo>, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    webdav::sync_now(&state.pool, state.events.as_ref(), state.secrets.as_ref()).await
+}
+
+/// 上传一份 todos.db 快照到 WebDAV，返回远端文件名
+#[tauri::command]
+async fn backup_to_webdav(app: AppHandle) -> Result<String, String> {
+    let state = local_state(&app).map_err(|e| e.message())?;
+    webdav::backup_now(&state.pool, state.secrets.as_ref()).await
+}
+
+/// 本机数据库路径（应用数据目录下的 todos.db）
+fn local_db_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DB_FILENAME))
+        .map_err(|_| t(Msg::NoAppDataDir).to_string())
+}
+
+/// 定时备份设置
+#[tauri::command]
+async fn get_backup_settings(app: AppHandle) -> Result<backup::BackupSettings, String> {
+    let pool = local_pool(&app)?;
+    backup::get_settings(&pool).await
+}
+
+/// 保存定时备份设置，按新的保留份数立即清理多余的备份
+#[tauri::command]
+async fn set_backup_settings(
+    app: AppHandle,
+    settings: backup::BackupSettings,
+) -> Result<backup::BackupSettings, String> {
+    let pool = local_pool(&app)?;
+    let backup_dir = recovery::backup_dir(&local_db_path(&app)?);
+    backup::set_settings(&pool, &backup_dir, settings).await
+}
+
+/// 本机备份列表，最新的在前
+#[tauri::command]
+fn list_backups(app: AppHandle) -> Result<Vec<recovery::BackupFile>, String> {
+    local_pool(&app)?;
+    Ok(recovery::list_backups(&recovery::backup_dir(
+        &local_db_path(&app)?,
+    )))
+}
+
+/// 立即生成一份手动备份，手动备份只保留最近若干份
+#[tauri::command]
+async fn create_backup(app: AppHandle) -> Result<recovery::BackupFile, String> {
+    let pool = local_pool(&app)?;
+    let backup_dir = recovery::backup_dir(&local_db_path(&app)?);
+    backup::create_backup(&pool, &backup_dir, "manual").await
+}
+
+/// 用指定备份替换当前数据库：校验备份并先生成一份还原前快照，再关闭本机后端、替换文件并重启应用。
+/// 替换失败时原数据库仍在原处，重新打开本机后端并返回错误，不重启
+#[tauri::command]
+async fn restore_backup(app: AppHandle, name: String) -> Result<(), String> {
+    let pool = local_pool(&app)?;
+    let db_path = local_db_path(&app)?;
+    let prepared = backup::prepare_restore(&pool, &db_path, &name).await?;
+    drop(pool);
+    let slot = app
+        .try_state::<BackendSlot>()
+        .ok_or_else(|| t(Msg::RemoteModeUnavailable).to_string())?;
+    let backend = slot.lock().ok().and_then(|mut backend| backend.take());
+    if let Some(backend) = backend {
+        backend.shutdown().await;
+    }
+    // 还原后的新变更接着当前的同步序号编号，见 backup::carry_change_seq
+    let swapped = match backup::carry_change_seq(&db_path, &prepared.staged).await {
+        Ok(()) => recovery::swap_in(&db_path, &prepared.staged),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = swapped {
+        eprintln!("restore_backup: {}", e);
+        let _ = std::fs::remove_file(&prepared.staged);
+        match open_local_backend(&app, &db_path).await {
+            Ok(backend) => {
+                if let Ok(mut slot) = slot.lock() {
+                    *slot = Some(backend);
+                }
+            }
+            Err(open) => {
+                // 原数据库也打不开时交给重启后的恢复窗口处理
+                eprintln!("restore_backup: reopen failed: {}", open);
+                app.restart();
+            }
+        }
+        return Err(tf(Msg::RestoreFailed, &[&e]));
+    }
+    eprintln!(
+        "restore_backup: restored {} (previous data saved as {})",
+        name, prepared.safety.file_name
+    );
+    app.restart();
+}
+
+/// 数据库统计：各表行数、已完成与未完成任务数、空闲页
+#[tauri::command]
+async fn get_db_stats(app: AppHandle) -> Result<maintenance::DbStats, String> {
+    let pool = local_pool(&app)?;
+    maintenance::stats(&pool).await.map_err(|e| e.to_string())
+}
+
+/// 完整检查数据库（PRAGMA integrity_check）
+#[tauri::command]
+async fn check_db_integrity(app: AppHandle) -> Result<maintenance::IntegrityReport, String> {
+    let pool = local_pool(&app)?;
+    maintenance::integrity_check(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 整理数据库（VACUUM + ANALYZE），返回整理前后的大小
+#[tauri::command]
+async fn compact_database(app: AppHandle) -> Result<maintenance::CompactResult, String> {
+    let pool = local_pool(&app)?;
+    maintenance::compact(&pool).await.map_err(|e| e.to_string())
+}
+
+/// 当前远程模式配置（本地模式返回 None）
+#[tauri::command]
+fn get_remote_server(app: AppHandle) -> Option<remote::RemoteServerInfo> {
+    remote_config(&app).map(Into::into)
+}
+
+/// 进入远程模式：用主机设置中显示的配对码完成配对，重启应用后生效
+#[tauri::command]
+async fn connect_remote_server(
+    app: AppHandle,
+    url: String,
+    code: String,
+) -> Result<remote::RemoteServerInfo, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| t(Msg::NoAppDataDir).to_string())?;
+    remote::connect(&app_data, &url, &code).await
+}
+
+/// 退出远程模式，重启应用后恢复使用本机数据库
+#[tauri::command]
+fn disconnect_remote_server(app: AppHandle) -> Result<(), String> {
+    let Ok(app_data) = app.path().app_data_dir() else {
+        return Ok(());
+    };
+    remote::disconnect(&app_data)
+}
+
+/// 主机端：生成一次性配对码（5 分钟内有效），供另一台电脑进入远程模式
+#[tauri::command]
+async fn create_pairing_code(app: AppHandle) -> Result<pairing::PairingCode, String> {
+    let pool = local_pool(&app)?;
+    pairing::create_pairing_code(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 主机端：列出已配对的远程设备
+#[tauri::command]
+async fn list_paired_clients(app: AppHandle) -> Result<Vec<pairing::PairedClient>, String> {
+    let pool = local_pool(&app)?;
+    pairing::list_paired_clients(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 主机端：吊销某个远程设备的访问令牌
+#[tauri::command]
+async fn revoke_paired_client(app: AppHandle, id: String) -> Result<(), String> {
+    let pool = local_pool(&app)?;
+    pairing::revoke_paired_client(&pool, &id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 本机服务状态，IPC 的增删改查命令与局域网服务共用；远程模式下没有本地数据库
+fn local_state(app: &AppHandle) -> Result<AppState, ApiError> {
+    local_backend_state(app).ok_or(ApiError::RemoteModeUnavailable)
+}
+
+// 以下命令与 /api/v1/todo、/api/v1/groups 一一对应，桌面端本地模式下直接调用，不经过局域网端口；
+// 失败时返回与 HTTP 相同结构的 { code, error, fields? }
+
+#[tauri::command]
+async fn list_todos(app: AppHandle, sort: Option<String>) -> Result<Vec<Todo>, ApiError> {
+    let state = local_state(&app)?;
+    crud::list_todos(&state, sort.as_deref().unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn get_content_suggestions(app: AppHandle, q: String) -> Result<Vec<String>, ApiError> {
+    let state = local_state(&app)?;
+    crud::content_suggestions(&state, &q).await
+}
+
+/// body.id 已存在时视为重放，直接返回该任务
+#[tauri::command]
+async fn create_todo(app: AppHandle, body: CreateTodoBody) -> Result<Todo, ApiError> {
+    let state = local_state(&app)?;
+    crud::create_todo(&state, &body, None)
+        .await
+        .map(|created| created.todo)
+}
+
+#[tauri::command]
+async fn update_todo(app: AppHandle, id: String, body: UpdateTodoBody) -> Result<Todo, ApiError> {
+    let state = local_state(&app)?;
+    crud::update_todo(&state, &id, &body).await
+}
+
+#[tauri::command]
+async fn delete_todo(app: AppHandle, id: String) -> Result<(), ApiError> {
+    let state = local_state(&app)?;
+    crud::delete_todo(&state, &id).await
+}
+
+#[tauri::command]
+async fn reorder_todos(app: AppHandle, ordered_ids: Vec<String>) -> Result<(), ApiError> {
+    let state = local_state(&app)?;
+    crud::reorder_todos(&state, &ordered_ids).await
+}
+
+#[tauri::command]
+async fn list_groups(app: AppHandle) -> Result<Vec<GroupItem>, ApiError> {
+    let state = local_state(&app)?;
+    crud::list_groups(&state).await
+}
+
+#[tauri::command]
+async fn create_group(app: AppHandle, name: String) -> Result<GroupItem, ApiError> {
+    let state = local_state(&app)?;
+    crud::create_group(&state, &name).await
+}
+
+#[tauri::command]
+async fn update_group(app: AppHandle, id: String, name: String) -> Result<GroupItem, ApiError> {
+    let state = local_state(&app)?;
+    crud::update_group(&state, &id, &name).await
+}
+
+#[tauri::command]
+async fn delete_group(app: AppHandle, id: String) -> Result<(), ApiError> {
+    let state = local_state(&app)?;
+    crud::delete_group(&state, &id).await
+}
+
+#[tauri::command]
+async fn reorder_groups(app: AppHandle, ordered_ids: Vec<String>) -> Result<(), ApiError> {
+    let state = local_state(&app)?;
+    crud::reorder_groups(&state, &ordered_ids).await
+}
+
 /// 与 Vue Router History 模式一致，使用路径 /qrcode-window（不用 hash）
 const QRCODE_WINDOW_PATH: &str = "/qrcode-window";
 /// 设置窗口路径
@@ -255,13 +669,12 @@ async fn open_qrcode_window(app: AppHandle, url: String) -> Result<(), String> {
     let webview_url = WebviewUrl::External(parsed);
 
     let mut builder = WebviewWindowBuilder::new(&app, "qrcode", webview_url)
-        .title("扫码用手机编辑")
+        .title(t(Msg::QrcodeWindowTitle))
         .inner_size(QRCODE_WINDOW_WIDTH, QRCODE_WINDOW_HEIGHT)
         .resizable(false)
         .always_on_top(true);
 
-    if let Some((x, y)) = center_position_on_main(&app, QRCODE_WINDOW_WIDTH, QRCODE_WINDOW_HEIGHT)
-    {
+    if let Some((x, y)) = center_position_on_main(&app, QRCODE_WINDOW_WIDTH, QRCODE_WINDOW_HEIGHT) {
         builder = builder.position(x, y);
     }
 
@@ -272,8 +685,8 @@ async fn open_qrcode_window(app: AppHandle, url: String) -> Result<(), String> {
 
 fn resolve_qrcode_window_url(app: &AppHandle, frontend_url: String) -> String {
     if let Ok(parsed) = url::Url::parse(&frontend_url) {
-        let is_bad_localhost = parsed.host_str() == Some("localhost")
-            && parsed.port().map_or(true, |p| p == 80);
+        let is_bad_localhost =
+            parsed.host_str() == Some("localhost") && parsed.port().map_or(true, |p| p == 80);
         if is_bad_localhost {
             return backend_qrcode_url(app);
         }
@@ -324,7 +737,7 @@ async fn open_settings_window(app: AppHandle, url: String) -> Result<(), String>
     let webview_url = WebviewUrl::External(parsed);
 
     let mut builder = WebviewWindowBuilder::new(&app, "settings", webview_url)
-        .title("设置")
+        .title(t(Msg::SettingsWindowTitle))
         .inner_size(SETTINGS_WINDOW_WIDTH, SETTINGS_WINDOW_HEIGHT)
         .resizable(false)
         .always_on_top(true);
@@ -411,7 +824,10 @@ fn set_sidebar_position(app: AppHandle, position: String) -> Result<(), String>
     let pos_trimmed = position.trim();
     let on_right = pos_trimmed.eq_ignore_ascii_case("right");
     SIDEBAR_POSITION.store(if on_right { 1 } else { 0 }, Ordering::Relaxed);
-    println!("Setting sidebar position to: {}", if on_right { "right" } else { "left" });
+    println!(
+        "Setting sidebar position to: {}",
+        if on_right { "right" } else { "left" }
+    );
     let app_clone = app.clone();
     let _ = app.run_on_main_thread(move || {
         // 确保 sidebar 窗口存在，如果不存在则创建
@@ -441,16 +857,35 @@ fn sidebar_window_url(app: &AppHandle) -> String {
     "tauri://localhost/?window=sidebar".to_string()
 }
 
-
-const APP_SETTINGS_FILENAME: &str = "app-settings.json";
+/// 设置窗口切换界面语言后调用：后端文案（接口错误、窗口标题等）随之切换，已打开窗口的标题立即更新
+#[tauri::command]
+fn set_locale(app: AppHandle, locale: String) -> Result<(), String> {
+    // 与前端 setLocale 一致：不支持的语言回退为简体中文
+    i18n::set_app_locale(i18n::Locale::parse(&locale).unwrap_or(i18n::Locale::ZhCn));
+    if let Some(w) = app.get_webview_window("qrcode") {
+        let _ = w.set_title(t(Msg::QrcodeWindowTitle));
+    }
+    if let Some(w) = app.get_webview_window("settings") {
+        let _ = w.set_title(t(Msg::SettingsWindowTitle));
+    }
+    Ok(())
+}
 
 /// 启动时从设置文件读取吸附位置（左/右），使分组窗体在首次显示前就按上次设置吸附在正确侧。
 fn load_sidebar_position_from_store(app: &AppHandle) {
-    let Ok(app_data) = app.path().app_data_dir() else { return };
+    let Ok(app_data) = app.path().app_data_dir() else {
+        return;
+    };
     let path = app_data.join(APP_SETTINGS_FILENAME);
-    let Ok(data) = std::fs::read_to_string(&path) else { return };
-    let Ok(root) = serde_json::from_str::<serde_json::Value>(&data) else { return };
-    let Some(pos) = root.get("sidebarPosition").and_then(|v| v.as_str()) else { return };
+    let Ok(data) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let Ok(root) = serde_json::from_str::<serde_json::Value>(&data) else {
+        return;
+    };
+    let Some(pos) = root.get("sidebarPosition").and_then(|v| v.as_str()) else {
+        return;
+    };
     if pos.eq_ignore_ascii_case("right") {
         SIDEBAR_POSITION.store(1, Ordering::Relaxed);
     } else {
@@ -460,7 +895,9 @@ fn load_sidebar_position_from_store(app: &AppHandle) {
 
 /// 主窗体首次启动：宽 375px，高为屏幕高度的 3/4；后续启动使用上次保存的尺寸。
 fn apply_main_window_initial_size(app: &AppHandle) {
-    let Some(main_win) = app.get_webview_window("main") else { return };
+    let Some(main_win) = app.get_webview_window("main") else {
+        return;
+    };
 
     if let Some(state) = load_main_window_state(app) {
         if state.width > 0.0 && state.height > 0.0 {
@@ -481,17 +918,24 @@ fn apply_main_window_initial_size(app: &AppHandle) {
         MAIN_WINDOW_INITIAL_WIDTH,
         initial_height,
     ));
-    let _ = save_main_window_state(app, &MainWindowState {
-        version: None,
-        width: MAIN_WINDOW_INITIAL_WIDTH,
-        height: initial_height,
-    });
+    let _ = save_main_window_state(
+        app,
+        &MainWindowState {
+            version: None,
+            width: MAIN_WINDOW_INITIAL_WIDTH,
+            height: initial_height,
+        },
+    );
 }
 
 /// 主窗体尺寸变化时保存到文件，供下次启动恢复。
 fn save_main_window_size_on_resize(app: &AppHandle) {
-    let Some(main_win) = app.get_webview_window("main") else { return };
-    let Ok(phys) = main_win.outer_size() else { return };
+    let Some(main_win) = app.get_webview_window("main") else {
+        return;
+    };
+    let Ok(phys) = main_win.outer_size() else {
+        return;
+    };
     let scale = main_win.scale_factor().unwrap_or(1.0);
     let state = MainWindowState {
         version: None,
@@ -566,30 +1010,33 @@ fn create_sidebar_window(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-fn now_millis() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
-}
-
 /// 节流间隔（毫秒）：Resized/Moved 期间最多每这么久同步一次位置/尺寸，减轻卡顿。
 const SIDEBAR_SYNC_THROTTLE_MS: u64 = 80;
 
 /// 按“吸附位置”方案更新副窗体位置与尺寸；bring_to_front 为 true 时再执行 set_focus 保证层级，拖动时传 false 避免卡顿。
 /// 当分组窗在左侧且主窗左移导致分组窗贴屏左边界时，会约束主窗右移以保持吸附关系，避免主窗盖住分组窗。
 fn sync_sidebar_to_main(app: &AppHandle, bring_to_front: bool) {
-    let Some(main_win) = app.get_webview_window("main") else { return };
-    let Some(sidebar) = app.get_webview_window("sidebar") else { return };
+    let Some(main_win) = app.get_webview_window("main") else {
+        return;
+    };
+    let Some(sidebar) = app.get_webview_window("sidebar") else {
+        return;
+    };
     LAST_SIDEBAR_SYNC_MS.store(now_millis(), Ordering::Relaxed);
-    let Ok(mut main_pos) = main_win.outer_position() else { return };
-    let Ok(main_outer) = main_win.outer_size() else { return };
+    let Ok(mut main_pos) = main_win.outer_position() else {
+        return;
+    };
+    let Ok(main_outer) = main_win.outer_size() else {
+        return;
+    };
     if main_outer.height == 0 || main_outer.width == 0 {
         let _ = sidebar.show();
         return;
     }
     let w = sidebar_width();
-    let sidebar_scale = sidebar.scale_factor().unwrap_or_else(|_| main_win.scale_factor().unwrap_or(1.0));
+    let sidebar_scale = sidebar
+        .scale_factor()
+        .unwrap_or_else(|_| main_win.scale_factor().unwrap_or(1.0));
     let sidebar_width_physical = (w * sidebar_scale).round() as i32;
     let overlap_left = 16;
     if !sidebar_on_right() {
@@ -649,7 +1096,8 @@ fn sync_sidebar_on_moved(app: &AppHandle) {
 fn sync_sidebar_on_resized(app: &AppHandle) {
     save_main_window_size_on_resize(app);
     let now = now_millis();
-    if now.saturating_sub(LAST_SIDEBAR_SYNC_MS.load(Ordering::Relaxed)) >= SIDEBAR_SYNC_THROTTLE_MS {
+    if now.saturating_sub(LAST_SIDEBAR_SYNC_MS.load(Ordering::Relaxed)) >= SIDEBAR_SYNC_THROTTLE_MS
+    {
         sync_sidebar_to_main(app, false);
     }
     let my_count = RESIZE_DEBOUNCE.fetch_add(1, Ordering::SeqCst) + 1;
@@ -665,57 +1113,157 @@ fn sync_sidebar_on_resized(app: &AppHandle) {
     });
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Todo {
-    pub id: String,
-    pub content: String,
-    pub status: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub reminder_time: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub start_time: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub end_time: Option<String>,
-    pub importance: String, // normal | important | urgent
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub group_id: Option<String>,
-    pub sort_order: i64,
-}
-
-#[derive(Clone)]
-pub struct AppState {
-    pub pool: sqlx::SqlitePool,
-    pub app_handle: AppHandle,
-}
-
-const CREATE_GROUPS_SQL: &str = r#"
-CREATE TABLE IF NOT EXISTS groups (
-    id TEXT PRIMARY KEY NOT NULL,
-    name TEXT NOT NULL
-)"#;
-
-const CREATE_TODOS_SQL: &str = r#"
-CREATE TABLE IF NOT EXISTS todos (
-    id TEXT PRIMARY KEY NOT NULL,
-    content TEXT NOT NULL,
-    status TEXT NOT NULL,
-    reminder_time TEXT,
-    start_time TEXT,
-    end_time TEXT,
-    importance TEXT NOT NULL DEFAULT 'normal',
-    group_id TEXT,
-    sort_order INTEGER NOT NULL DEFAULT 0
-)"#;
-
-const MIGRATE_TODOS_COLUMNS: &[&str] = &[
-    "ALTER TABLE todos ADD COLUMN start_time TEXT",
-    "ALTER TABLE todos ADD COLUMN end_time TEXT",
-    "ALTER TABLE todos ADD COLUMN importance TEXT NOT NULL DEFAULT 'normal'",
-    "ALTER TABLE todos ADD COLUMN group_id TEXT",
-    "ALTER TABLE todos ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
-];
-
-const MIGRATE_GROUPS_COLUMNS: &[&str] = &["ALTER TABLE groups ADD COLUMN sort_order INTEGER DEFAULT 0"];
+/// 本地模式：打开 todos.db、检查完整性并迁移，再启动局域网服务与点对点同步。
+/// 数据库、后台同步与 axum 都运行在 Tauri 的 async runtime 上，退出时由 shutdown_local_backend 依次关闭。
+/// 数据库打不开时不启动后台，返回原因，由 setup 改为显示恢复窗口
+fn init_local_backend(app: &tauri::App, db_path: &std::path::Path) -> Result<(), OpenDbError> {
+    let backend = tauri::async_runtime::block_on(open_local_backend(app.handle(), db_path))?;
+    app.manage(BackendSlot::new(Some(backend)));
+    Ok(())
+}
+
+/// 打开数据库并启动后台同步与局域网服务；启动时与还原失败后重新打开时共用
+async fn open_local_backend(
+    app: &AppHandle,
+    db_path: &std::path::Path,
+) -> Result<LocalBackend, OpenDbError> {
+    let events = Arc::new(events::TauriEventSink(app.clone()));
+    let mut backend =
+        LocalBackend::open(db_path, events, Arc::new(secrets::KeyringSecretStore)).await?;
+    // 端口被占用时只影响手机端与其他设备，桌面窗口通过 IPC 读写数据
+    if let Err(e) = backend.start_server(server::ServerConfig::default()).await {
+        eprintln!("{}", e);
+    }
+    Ok(backend)
+}
+
+/// 启动时数据库打开失败的记录；存在时不显示主窗口，只显示恢复窗口，恢复命令也只在此时可用
+struct DbRecovery {
+    db_path: std::path::PathBuf,
+    error: OpenDbError,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DbRecoveryInfo {
+    kind: OpenDbErrorKind,
+    message: String,
+    db_path: String,
+    /// 可用于恢复的最近一份备份（见 backup::latest_usable_backup）；只有损坏时才会查找
+    latest_backup: Option<recovery::BackupFile>,
+}
+
+/// 恢复窗口路径
+const RECOVERY_WINDOW_PATH: &str = "/recovery-window";
+/// 恢复窗口逻辑尺寸
+const RECOVERY_WINDOW_WIDTH: f64 = 520.0;
+const RECOVERY_WINDOW_HEIGHT: f64 = 400.0;
+
+fn recovery_window_url(app: &AppHandle) -> String {
+    if cfg!(debug_assertions) {
+        if let Some(ref u) = app.config().build.dev_url {
+            let base = u.as_str().trim_end_matches('/');
+            return format!("{}{}", base, RECOVERY_WINDOW_PATH);
+        }
+    }
+    format!("tauri://localhost{}", RECOVERY_WINDOW_PATH)
+}
+
+/// 数据库打不开时代替主窗口显示；关闭它即退出应用
+fn open_recovery_window(app: &AppHandle) -> Result<(), String> {
+    let parsed = url::Url::parse(&recovery_window_url(app)).map_err(|e| e.to_string())?;
+    let window = WebviewWindowBuilder::new(app, "recovery", WebviewUrl::External(parsed))
+        .title(t(Msg::RecoveryWindowTitle))
+        .inner_size(RECOVERY_WINDOW_WIDTH, RECOVERY_WINDOW_HEIGHT)
+        .resizable(false)
+        .center()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let _ = window.set_focus();
+    Ok(())
+}
+
+fn db_recovery(app: &AppHandle) -> Result<tauri::State<'_, DbRecovery>, String> {
+    app.try_state::<DbRecovery>()
+        .ok_or_else(|| t(Msg::RecoveryNotNeeded).to_string())
+}
+
+/// 替换或移走数据库文件前调用，返回数据库路径。只有文件损坏（含完整性检查失败）时才允许：
+/// 被其他程序占用时只能重试；其他原因（无权限、磁盘已满、版本过高等）下文件本身完好，替换只会丢数据
+fn db_recovery_for_replace(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    let recovery = db_recovery(app)?;
+    match recovery.error.kind {
+        OpenDbErrorKind::Corrupt => Ok(recovery.db_path.clone()),
+        OpenDbErrorKind::Locked => Err(t(Msg::DbLockedRetry).to_string()),
+        OpenDbErrorKind::Other => Err(t(Msg::DbNotCorruptRetry).to_string()),
+    }
+}
+
+/// 恢复窗口：失败原因、数据库路径与可用于恢复的最近备份
+#[tauri::command]
+async fn get_db_recovery_info(app: AppHandle) -> Result<DbRecoveryInfo, String> {
+    let (kind, message, db_path) = {
+        let recovery = db_recovery(&app)?;
+        (
+            recovery.error.kind,
+            recovery.error.to_string(),
+            recovery.db_path.clone(),
+        )
+    };
+    let latest_backup = match kind {
+        OpenDbErrorKind::Corrupt => {
+            backup::latest_usable_backup(&recovery::backup_dir(&db_path)).await
+        }
+        _ => None,
+    };
+    Ok(DbRecoveryInfo {
+        kind,
+        message,
+        db_path: db_path.to_string_lossy().into_owned(),
+        latest_backup,
+    })
+}
+
+/// 用最近一份可用的备份替换数据库，损坏的文件改名保留；前端随后重启应用
+#[tauri::command]
+async fn restore_latest_backup(app: AppHandle) -> Result<(), String> {
+    let db_path = db_recovery_for_replace(&app)?;
+    let backup = backup::latest_usable_backup(&recovery::backup_dir(&db_path))
+        .await
+        .ok_or_else(|| t(Msg::NoBackupAvailable).to_string())?;
+    let staged = recovery::stage_copy(&db_path, &backup.path)?;
+    if let Err(e) = backup::carry_change_seq(&db_path, &staged).await {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+    let aside = recovery::restore_staged(&db_path, &staged)?;
+    eprintln!(
+        "restore_latest_backup: restored {} (damaged file kept at {:?})",
+        backup.path.display(),
+        aside
+    );
+    Ok(())
+}
+
+/// 不恢复数据，把损坏的文件改名保留，重启后创建新的空数据库
+#[tauri::command]
+fn start_fresh_database(app: AppHandle) -> Result<(), String> {
+    let db_path = db_recovery_for_replace(&app)?;
+    let aside = recovery::set_aside(&db_path)?;
+    eprintln!("start_fresh_database: damaged file kept at {:?}", aside);
+    Ok(())
+}
+
+/// 应用退出时关闭本机后端：先停止局域网服务，再停止后台同步，最后关闭数据库连接池
+fn shutdown_local_backend(app: &AppHandle) {
+    let Some(slot) = app.try_state::<BackendSlot>() else {
+        return;
+    };
+    let backend = slot.lock().ok().and_then(|mut backend| backend.take());
+    if let Some(backend) = backend {
+        tauri::async_runtime::block_on(backend.shutdown());
+    }
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -731,7 +1279,49 @@ pub fn run() {
             save_background_image,
             clear_background_image,
             get_storage_info,
-            open_path_in_folder
+            open_path_in_folder,
+            get_db_recovery_info,
+            restore_latest_backup,
+            start_fresh_database,
+            list_sync_peers,
+            pair_sync_peer,
+            remove_sync_peer,
+            sync_peers_now,
+            get_sync_folder,
+            set_sync_folder,
+            sync_folder_now,
+            get_webdav_config,
+            set_webdav_config,
+            clear_webdav_config,
+            sync_webdav_now,
+            backup_to_webdav,
+            get_backup_settings,
+            set_backup_settings,
+            list_backups,
+            create_backup,
+            restore_backup,
+            get_db_stats,
+            check_db_integrity,
+            compact_database,
+            get_api_endpoint,
+            get_remote_server,
+            connect_remote_server,
+            disconnect_remote_server,
+            create_pairing_code,
+            list_paired_clients,
+            revoke_paired_client,
+            set_locale,
+            list_todos,
+            get_content_suggestions,
+            create_todo,
+            update_todo,
+            delete_todo,
+            reorder_todos,
+            list_groups,
+            create_group,
+            update_group,
+            delete_group,
+            reorder_groups
         ])
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
@@ -739,60 +1329,31 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
-            let app_handle = app.handle().clone();
-            let db_path = app
-                .path()
-                .app_data_dir()
-                .unwrap_or_else(|_| std::path::PathBuf::from("."))
-                .join("todos.db");
-
-            if let Some(parent) = db_path.parent() {
-                std::fs::create_dir_all(parent).expect("创建应用数据目录失败");
+            if let Ok(app_data) = app.path().app_data_dir() {
+                i18n::load_app_locale(&app_data.join(APP_SETTINGS_FILENAME));
             }
-
-            let rt = tokio::runtime::Runtime::new().expect("tokio runtime");
-            let pool = rt.block_on(async {
-                let opts = sqlx::sqlite::SqliteConnectOptions::new()
-                    .filename(&db_path)
-                    .create_if_missing(true);
-                let pool = sqlx::sqlite::SqlitePoolOptions::new()
-                    .connect_with(opts)
-                    .await
-                    .expect("SQLite 连接失败");
-                sqlx::query(CREATE_GROUPS_SQL)
-                    .execute(&pool)
-                    .await
-                    .expect("创建 groups 表失败");
-                sqlx::query(CREATE_TODOS_SQL)
-                    .execute(&pool)
-                    .await
-                    .expect("创建 todos 表失败");
-                for sql in MIGRATE_TODOS_COLUMNS {
-                    let _ = sqlx::query(*sql).execute(&pool).await;
-                }
-                for sql in MIGRATE_GROUPS_COLUMNS {
-                    let _ = sqlx::query(*sql).execute(&pool).await;
+            // 远程模式下数据全部在主机上，不打开本地数据库，也不启动本机服务
+            if remote_config(app.handle()).is_none() {
+                let db_path = app
+                    .path()
+                    .app_data_dir()
+                    .unwrap_or_else(|_| std::path::PathBuf::from("."))
+                    .join(DB_FILENAME);
+                if let Err(error) = init_local_backend(app, &db_path) {
+                    eprintln!("open database: {}", error);
+                    app.manage(DbRecovery { db_path, error });
                 }
-                pool
-            });
-
-            let state = AppState {
-                pool,
-                app_handle: app_handle.clone(),
-            };
-
-            let stop = Arc::new(AtomicBool::new(false));
-            let stop_clone = stop.clone();
-            let state_clone = state.clone();
-            std::thread::spawn(move || {
-                server::run_axum(state_clone, stop_clone);
-            });
-
-            app.manage(state);
-            app.manage(stop);
+            }
 
             let handle = app.handle().clone();
             let _ = app.run_on_main_thread(move || {
+                if handle.try_state::<DbRecovery>().is_some() {
+                    if let Err(e) = open_recovery_window(&handle) {
+                        eprintln!("recovery window create failed: {}", e);
+                        handle.exit(1);
+                    }
+                    return;
+                }
                 load_sidebar_position_from_store(&handle);
                 apply_main_window_initial_size(&handle);
                 // 先显示主窗，再创建并显示侧栏，避免主窗未 show 时创建侧栏导致一直不出现
@@ -812,14 +1373,15 @@ pub fn run() {
             let label = window.label();
             match event {
                 tauri::WindowEvent::Destroyed => {
+                    // 恢复窗口期间主窗口一直隐藏，关闭恢复窗口即退出
+                    if label == "recovery" {
+                        window.app_handle().exit(0);
+                    }
                     if label == "main" {
                         if let Some(sidebar) = window.app_handle().get_webview_window("sidebar") {
                             let _ = sidebar.close();
                         }
                     }
-                    if let Some(stop) = window.app_handle().try_state::<Arc<AtomicBool>>() {
-                        stop.store(true, Ordering::SeqCst);
-                    }
                 }
                 tauri::WindowEvent::Resized(_) => {
                     if label == "main" {
@@ -838,6 +1400,11 @@ pub fn run() {
                 _ => {}
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown_local_backend(app);
+            }
+        });
 }
@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // 带子命令时按命令行模式执行（add/list/done/edit/rm/groups），否则启动图形界面
+    if let Some(code) = xy_todo_server::cli::run_from_env() {
+        std::process::exit(code);
+    }
     xy_todo_list_lib::run();
 }
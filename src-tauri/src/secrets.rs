@@ -0,0 +1,32 @@
+//! SecretStore 的桌面端实现：保存在系统钥匙串（Windows 凭据管理器 / macOS 钥匙串 / Secret Service）。
+
+use xy_todo_core::i18n::{tf, Msg};
+use xy_todo_server::secrets::SecretStore;
+
+/// 钥匙串中的服务名；键作为账户名
+const KEYRING_SERVICE: &str = "douko-todo-list";
+
+pub struct KeyringSecretStore;
+
+fn entry(key: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, key).map_err(|e| e.to_string())
+}
+
+impl SecretStore for KeyringSecretStore {
+    fn get(&self, key: &str) -> Option<String> {
+        entry(key).ok()?.get_password().ok()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        entry(key)?
+            .set_password(value)
+            .map_err(|e| tf(Msg::SavePasswordFailed, &[&e]))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        match entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
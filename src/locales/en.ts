@@ -181,6 +181,30 @@ export default {
     deleteAction: "Delete",
     editTask: "Edit task",
   },
+  recovery: {
+    title: {
+      corrupt: "The database file is damaged",
+      locked: "The database is in use by another program",
+      other: "The database could not be opened",
+    },
+    description: {
+      corrupt: "The startup integrity check found that todos.db is damaged. You can restore the latest backup or start over with an empty database; either way the damaged file is renamed and kept in the same folder.",
+      locked: "todos.db is in use by another program (for example another running copy of Douko Todo). Close it and try again.",
+      other: "An error occurred while opening todos.db; see the details below (for example missing permissions, a full disk or a database from a newer version). The file itself is not damaged and will not be replaced or moved; fix the problem and retry.",
+    },
+    dbPath: "Database",
+    error: "Error",
+    latestBackup: "Latest backup",
+    backupItem: "{0} ({1})",
+    noBackup: "No backup available",
+    restore: "Restore latest backup",
+    startFresh: "Start over",
+    retry: "Retry",
+    quit: "Quit",
+    confirmTitle: "Confirm",
+    restoreConfirm: "The current database will be replaced by the backup {0}; changes made after it will be lost. Continue? The app will restart.",
+    startFreshConfirm: "A new empty database will be created and your existing tasks will not appear in the app (the damaged file stays in the same folder). Continue? The app will restart.",
+  },
   qrcode: {
     close: "Close",
   },
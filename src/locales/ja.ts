@@ -181,6 +181,30 @@ export default {
     deleteAction: "削除",
     editTask: "タスクを編集",
   },
+  recovery: {
+    title: {
+      corrupt: "データベースファイルが破損しています",
+      locked: "データベースが他のプログラムで使用中です",
+      other: "データベースを開けませんでした",
+    },
+    description: {
+      corrupt: "起動時の整合性チェックで todos.db の破損が見つかりました。最新のバックアップから復元するか、空のデータベースでやり直すことができます。どちらの場合も破損したファイルは名前を変えて同じフォルダに残ります。",
+      locked: "todos.db が他のプログラム（起動中の別の Douko Todo など）で使用されています。そのプログラムを終了してから再試行してください。",
+      other: "todos.db を開く際にエラーが発生しました。詳細は下記をご覧ください（権限がない、ディスクがいっぱい、新しいバージョンのデータベースなど）。ファイル自体は破損していないため、置き換えや移動は行いません。問題に対処してから再試行してください。",
+    },
+    dbPath: "データベース",
+    error: "エラー",
+    latestBackup: "最新のバックアップ",
+    backupItem: "{0}（{1}）",
+    noBackup: "利用できるバックアップがありません",
+    restore: "最新のバックアップから復元",
+    startFresh: "やり直す",
+    retry: "再試行",
+    quit: "終了",
+    confirmTitle: "確認",
+    restoreConfirm: "現在のデータベースをバックアップ {0} で置き換えます。それ以降の変更は失われます。続行しますか？アプリは自動的に再起動します。",
+    startFreshConfirm: "新しい空のデータベースを作成します。既存のタスクはアプリに表示されなくなります（破損したファイルは同じフォルダに残ります）。続行しますか？アプリは自動的に再起動します。",
+  },
   qrcode: {
     close: "閉じる",
   },
@@ -181,6 +181,30 @@ export default {
     deleteAction: "删除",
     editTask: "编辑任务",
   },
+  recovery: {
+    title: {
+      corrupt: "数据库文件已损坏",
+      locked: "数据库正被其他程序占用",
+      other: "数据库无法打开",
+    },
+    description: {
+      corrupt: "启动时的完整性检查发现 todos.db 已损坏。可以从最近的备份恢复，或者重新开始使用空数据库；两种方式都会把损坏的文件改名保留在原目录。",
+      locked: "todos.db 正被其他程序占用（例如另一个正在运行的 Douko Todo）。请关闭该程序后重试。",
+      other: "打开 todos.db 时出错，详见下方错误信息（例如没有权限、磁盘已满或数据库版本高于本程序）。数据库文件本身没有损坏，不会被替换或移动；请处理后重试。",
+    },
+    dbPath: "数据库",
+    error: "错误",
+    latestBackup: "最近备份",
+    backupItem: "{0}（{1}）",
+    noBackup: "没有可用的备份",
+    restore: "从最近的备份恢复",
+    startFresh: "重新开始",
+    retry: "重试",
+    quit: "退出",
+    confirmTitle: "提示",
+    restoreConfirm: "将用备份 {0} 替换当前数据库，备份之后的修改会丢失。确定继续吗？应用会自动重启。",
+    startFreshConfirm: "将创建新的空数据库，现有任务不会出现在应用中（损坏的文件仍保留在原目录）。确定继续吗？应用会自动重启。",
+  },
   qrcode: {
     close: "关闭",
   },
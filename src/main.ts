@@ -4,6 +4,7 @@ import "element-plus/dist/index.css";
 import App from "./App.vue";
 import router from "./router";
 import { i18n } from "./i18n";
+import { initApiEndpoint } from "./utils/request";
 
 if (typeof window !== "undefined" && window.location.search.includes("window=sidebar")) {
   document.documentElement.classList.add("sidebar-window");
@@ -13,6 +14,6 @@ const app = createApp(App);
 app.use(i18n);
 app.use(ElementPlus);
 app.use(router);
-router.isReady().then(() => {
+Promise.all([router.isReady(), initApiEndpoint()]).then(() => {
   app.mount("#app");
 });
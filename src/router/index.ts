@@ -4,12 +4,14 @@ import Desktop from "@/views/Desktop.vue";
 import MobileTodo from "@/views/MobileTodo.vue";
 import QrcodeWindow from "@/views/QrcodeWindow.vue";
 import SettingsWindow from "@/views/SettingsWindow.vue";
+import RecoveryWindow from "@/views/RecoveryWindow.vue";
 
 const routes: RouteRecordRaw[] = [
   { path: "/", name: "Desktop", component: Desktop },
   { path: "/mobile", name: "MobileTodo", component: MobileTodo },
   { path: "/qrcode-window", name: "QrcodeWindow", component: QrcodeWindow },
   { path: "/settings-window", name: "SettingsWindow", component: SettingsWindow },
+  { path: "/recovery-window", name: "RecoveryWindow", component: RecoveryWindow },
 ];
 
 const router = createRouter({
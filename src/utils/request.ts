@@ -1,12 +1,143 @@
 /**
  * 前端请求封装：统一请求头、响应拦截、错误提示
- * 桌面端请求局域网 Axum 服务；移动端使用当前 origin 作为 baseURL
+ * 桌面端本地模式直接调用 IPC 命令；远程模式请求主机的 Axum 服务；移动端使用当前 origin 作为 baseURL，
+ * 访问令牌来自二维码地址中的 ?token=
  */
 
 import { ElMessage } from "element-plus";
+import { i18n } from "@/i18n";
+
+interface ApiEndpoint {
+  baseUrl: string;
+  token?: string;
+  remote: boolean;
+}
+
+let apiEndpoint: ApiEndpoint | null = null;
+
+/** 手机扫码页面的访问令牌在 localStorage 中的键 */
+const MOBILE_TOKEN_KEY = "douko-api-token";
+let mobileToken: string | null = null;
+
+/** 客户端使用的 API 主版本 */
+export const API_VERSION = 1;
+/** 接口路径前缀；远程主机为旧版本（没有 /api/v1）时退回已弃用的 /api 别名 */
+let apiPrefix = `/api/v${API_VERSION}`;
+
+/** 扫码打开时地址带 ?token=：存入 localStorage 供之后使用（收藏或刷新页面不必再扫码），并从地址栏移除 */
+function initMobileToken(): void {
+  try {
+    const url = new URL(window.location.href);
+    const fromUrl = url.searchParams.get("token");
+    if (fromUrl) {
+      localStorage.setItem(MOBILE_TOKEN_KEY, fromUrl);
+      url.searchParams.delete("token");
+      window.history.replaceState(window.history.state, "", url.toString());
+    }
+    mobileToken = localStorage.getItem(MOBILE_TOKEN_KEY);
+  } catch {
+    mobileToken = null;
+  }
+}
+
+/** 启动时获取数据接口地址：桌面端远程模式下改为请求主机地址并附带访问令牌；浏览器中读取扫码带来的令牌 */
+export async function initApiEndpoint(): Promise<void> {
+  if (typeof window === "undefined") return;
+  if (!(window as Window & { __TAURI__?: unknown }).__TAURI__) {
+    initMobileToken();
+    return;
+  }
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    apiEndpoint = (await invoke("get_api_endpoint")) as ApiEndpoint;
+  } catch {
+    apiEndpoint = null;
+  }
+  if (apiEndpoint?.remote) {
+    await negotiateApiPrefix(apiEndpoint.baseUrl);
+  }
+}
+
+/** 版本握手：主机不认识 /api/v1/version 时说明是旧版本，改走 /api */
+async function negotiateApiPrefix(base: string): Promise<void> {
+  try {
+    const res = await fetch(`${base}/api/v${API_VERSION}/version`, { headers: withAuth() });
+    apiPrefix = res.status === 404 ? "/api" : `/api/v${API_VERSION}`;
+  } catch {
+    // 主机暂时不可达时保持默认前缀，后续请求自行报错
+  }
+}
+
+/** 合并请求头：带上当前界面语言，使服务端错误提示与界面一致；远程模式与手机页面附带 Authorization */
+function withAuth(headers: Record<string, string> = {}): Record<string, string> {
+  const merged = { ...headers, "Accept-Language": i18n.global.locale.value };
+  const token = apiEndpoint?.token ?? mobileToken;
+  if (token) {
+    return { ...merged, Authorization: `Bearer ${token}` };
+  }
+  return merged;
+}
+
+/** 服务端错误响应：code 为稳定的错误码，error 为提示文案，fields 仅在参数校验失败时出现 */
+export interface ApiFieldError {
+  field: string;
+  message: string;
+}
+
+export class ApiError extends Error {
+  status: number;
+  code?: string;
+  fields: ApiFieldError[];
+
+  constructor(message: string, status: number, code?: string, fields: ApiFieldError[] = []) {
+    super(message);
+    this.name = "ApiError";
+    this.status = status;
+    this.code = code;
+    this.fields = fields;
+  }
+}
+
+/** 解析错误响应并提示用户；返回的 ApiError 供调用方按 code 分支处理 */
+async function failWith(res: Response, fallback: string): Promise<ApiError> {
+  const text = await res.text();
+  let err: ApiError;
+  try {
+    const body = JSON.parse(text) as { code?: string; error?: string; fields?: ApiFieldError[] };
+    err = new ApiError(body.error || fallback, res.status, body.code, body.fields ?? []);
+  } catch {
+    err = new ApiError(text || fallback, res.status);
+  }
+  ElMessage.error(err.message);
+  return err;
+}
+
+/** 桌面端本地模式：任务与分组直接通过 IPC 读写本机数据库，不依赖局域网端口是否可用 */
+function useIpc(): boolean {
+  return apiEndpoint !== null && !apiEndpoint.remote;
+}
+
+/** 调用 IPC 命令；失败时的 { code, error, fields } 与 HTTP 错误响应结构相同 */
+async function invokeApi<T>(cmd: string, args: Record<string, unknown>, fallback: string): Promise<T> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  try {
+    return (await invoke(cmd, args)) as T;
+  } catch (e) {
+    const body = (typeof e === "object" && e !== null ? e : {}) as {
+      code?: string;
+      error?: string;
+      fields?: ApiFieldError[];
+    };
+    const message = body.error || (typeof e === "string" ? e : fallback);
+    const err = new ApiError(message, 0, body.code, body.fields ?? []);
+    ElMessage.error(err.message);
+    throw err;
+  }
+}
 
 export function getBaseUrl(): string {
   if (typeof window === "undefined") return "";
+  if (apiEndpoint) return apiEndpoint.baseUrl;
   const origin = window.location.origin;
   if (origin.includes(":5173") || (window as Window & { __TAURI__?: unknown }).__TAURI__) {
     return "http://127.0.0.1:8080";
@@ -14,10 +145,15 @@ export function getBaseUrl(): string {
   return origin;
 }
 
+/** 手机扫码地址（带扫码页面的访问令牌）；桌面端由后端生成，远程模式下由后端向主机获取 */
 export async function getMobileUrl(): Promise<string> {
+  if (apiEndpoint) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return (invoke("get_web_server_url") as Promise<string>).catch(() => "");
+  }
   const base = getBaseUrl();
   if (!base) return "";
-  const res = await fetch(`${base}/api/mobile-url`);
+  const res = await fetch(`${base}${apiPrefix}/mobile-url`, { headers: withAuth() });
   if (!res.ok) return "";
   const data = (await res.json()) as { url?: string };
   return data?.url ?? "";
@@ -41,6 +177,8 @@ export interface TodoItem {
   importance: Importance;
   group_id?: string;
   sort_order: number;
+  /** 最后修改时间（毫秒） */
+  updated_at: number;
 }
 
 export interface CreateTodoParams {
@@ -53,70 +191,72 @@ export interface CreateTodoParams {
 }
 
 export async function getTodoList(sort: SortRule = "comprehensive"): Promise<TodoItem[]> {
+  if (useIpc()) return invokeApi("list_todos", { sort }, "获取任务列表失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/todo?sort=${encodeURIComponent(sort)}`, { method: "GET" });
+  const res = await fetch(`${base}${apiPrefix}/todo?sort=${encodeURIComponent(sort)}`, { method: "GET", headers: withAuth() });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "获取任务列表失败");
-    throw new Error(text || "获取任务列表失败");
+    throw await failWith(res, "获取任务列表失败");
   }
   return res.json();
 }
 
 /** 根据输入获取任务内容联想（历史数据） */
 export async function getContentSuggestions(q: string): Promise<string[]> {
+  if (useIpc()) return invokeApi("get_content_suggestions", { q }, "获取联想失败");
   const base = getBaseUrl();
   if (!base) return [];
   const res = await fetch(
-    `${base}/api/todo/content-suggestions?q=${encodeURIComponent(q.trim())}`,
-    { method: "GET" }
+    `${base}${apiPrefix}/todo/content-suggestions?q=${encodeURIComponent(q.trim())}`,
+    { method: "GET", headers: withAuth() }
   );
-  if (!res.ok) return [];
+  if (!res.ok) {
+    throw await failWith(res, "获取联想失败");
+  }
   return res.json();
 }
 
 export async function createTodo(params: CreateTodoParams): Promise<TodoItem> {
+  const body = {
+    content: params.content,
+    status: params.status ?? "pending",
+    start_time: params.start_time ?? null,
+    end_time: params.end_time ?? null,
+    importance: params.importance ?? "normal",
+    group_id: params.group_id ?? null,
+  };
+  if (useIpc()) return invokeApi("create_todo", { body }, "新增任务失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/todo`, {
+  const res = await fetch(`${base}${apiPrefix}/todo`, {
     method: "POST",
-    headers: { "Content-Type": "application/json" },
-    body: JSON.stringify({
-      content: params.content,
-      status: params.status ?? "pending",
-      start_time: params.start_time ?? null,
-      end_time: params.end_time ?? null,
-      importance: params.importance ?? "normal",
-      group_id: params.group_id ?? null,
-    }),
+    headers: withAuth({ "Content-Type": "application/json" }),
+    body: JSON.stringify(body),
   });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "新增任务失败");
-    throw new Error(text || "新增任务失败");
+    throw await failWith(res, "新增任务失败");
   }
   return res.json();
 }
 
+/** start_time、end_time、group_id 传 null 表示清空 */
 export type UpdateTodoParams = Partial<{
   content: string;
   status: "pending" | "completed";
-  start_time: string;
-  end_time: string;
+  start_time: string | null;
+  end_time: string | null;
   importance: Importance;
-  group_id: string;
+  group_id: string | null;
 }>;
 
 export async function updateTodo(id: string, params: UpdateTodoParams): Promise<TodoItem> {
+  if (useIpc()) return invokeApi("update_todo", { id, body: params }, "更新失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/todo/${id}`, {
+  const res = await fetch(`${base}${apiPrefix}/todo/${encodeURIComponent(id)}`, {
     method: "PATCH",
-    headers: { "Content-Type": "application/json" },
+    headers: withAuth({ "Content-Type": "application/json" }),
     body: JSON.stringify(params),
   });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "更新失败");
-    throw new Error(text || "更新失败");
+    throw await failWith(res, "更新失败");
   }
   return res.json();
 }
@@ -129,86 +269,83 @@ export async function updateTodoStatus(
 }
 
 export async function deleteTodo(id: string): Promise<void> {
+  if (useIpc()) return invokeApi("delete_todo", { id }, "删除任务失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/todo/${id}`, { method: "DELETE" });
+  const res = await fetch(`${base}${apiPrefix}/todo/${encodeURIComponent(id)}`, { method: "DELETE", headers: withAuth() });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "删除任务失败");
-    throw new Error(text || "删除任务失败");
+    throw await failWith(res, "删除任务失败");
   }
 }
 
 export async function reorderTodos(orderedIds: string[]): Promise<void> {
+  if (useIpc()) return invokeApi("reorder_todos", { orderedIds }, "排序失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/todo/reorder`, {
+  const res = await fetch(`${base}${apiPrefix}/todo/reorder`, {
     method: "POST",
-    headers: { "Content-Type": "application/json" },
+    headers: withAuth({ "Content-Type": "application/json" }),
     body: JSON.stringify({ ordered_ids: orderedIds }),
   });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "排序失败");
-    throw new Error(text || "排序失败");
+    throw await failWith(res, "排序失败");
   }
 }
 
 export async function getGroups(): Promise<GroupItem[]> {
+  if (useIpc()) return invokeApi("list_groups", {}, "获取分组失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/groups`, { method: "GET" });
-  if (!res.ok) return [];
+  const res = await fetch(`${base}${apiPrefix}/groups`, { method: "GET", headers: withAuth() });
+  if (!res.ok) {
+    throw await failWith(res, "获取分组失败");
+  }
   return res.json();
 }
 
 export async function createGroup(name: string): Promise<GroupItem> {
+  if (useIpc()) return invokeApi("create_group", { name }, "新增分组失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/groups`, {
+  const res = await fetch(`${base}${apiPrefix}/groups`, {
     method: "POST",
-    headers: { "Content-Type": "application/json" },
+    headers: withAuth({ "Content-Type": "application/json" }),
     body: JSON.stringify({ name }),
   });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "新增分组失败");
-    throw new Error(text || "新增分组失败");
+    throw await failWith(res, "新增分组失败");
   }
   return res.json();
 }
 
 export async function updateGroup(id: string, name: string): Promise<GroupItem> {
+  if (useIpc()) return invokeApi("update_group", { id, name }, "更新分组失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/groups/${id}`, {
+  const res = await fetch(`${base}${apiPrefix}/groups/${encodeURIComponent(id)}`, {
     method: "PATCH",
-    headers: { "Content-Type": "application/json" },
+    headers: withAuth({ "Content-Type": "application/json" }),
     body: JSON.stringify({ name }),
   });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "更新分组失败");
-    throw new Error(text || "更新分组失败");
+    throw await failWith(res, "更新分组失败");
   }
   return res.json();
 }
 
 export async function deleteGroup(id: string): Promise<void> {
+  if (useIpc()) return invokeApi("delete_group", { id }, "删除分组失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/groups/${id}`, { method: "DELETE" });
+  const res = await fetch(`${base}${apiPrefix}/groups/${encodeURIComponent(id)}`, { method: "DELETE", headers: withAuth() });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "删除分组失败");
-    throw new Error(text || "删除分组失败");
+    throw await failWith(res, "删除分组失败");
   }
 }
 
 export async function reorderGroups(orderedIds: string[]): Promise<void> {
+  if (useIpc()) return invokeApi("reorder_groups", { orderedIds }, "分组排序失败");
   const base = getBaseUrl();
-  const res = await fetch(`${base}/api/groups/reorder`, {
+  const res = await fetch(`${base}${apiPrefix}/groups/reorder`, {
     method: "POST",
-    headers: { "Content-Type": "application/json" },
+    headers: withAuth({ "Content-Type": "application/json" }),
     body: JSON.stringify({ ordered_ids: orderedIds }),
   });
   if (!res.ok) {
-    const text = await res.text();
-    ElMessage.error(text || "分组排序失败");
-    throw new Error(text || "分组排序失败");
+    throw await failWith(res, "分组排序失败");
   }
 }